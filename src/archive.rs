@@ -0,0 +1,320 @@
+//! On-disk archive of the raw API responses for each game. Turning a game into pitches is where most of our bugs live, and without
+//! the raw JSON every fix means downloading every game again. With the archive turned on, each playByPlay, boxscore, feed/live,
+//! coaches and people response is kept, so a pull can be re-run against the archive alone.
+//!
+//! Responses are gzipped and stored under the sha256 of their content, so identical responses (coaches for a team that didn't
+//! change, a player pulled twice) are only stored once. A small ref file per (endpoint, id) points at the content:
+//!
+//! ```text
+//! archive/objects/3f/3fa4...e1.json.gz
+//! archive/refs/play_by_play/566083        <- contains 3fa4...e1
+//! archive/refs/coaches/147_2019-6-19
+//! ```
+//!
+//! The archive sits in front of the transport. In `ArchiveMode::Write` every response for an archived endpoint is saved as it comes
+//! in. In `ArchiveMode::ReadOnly` requests are answered from the archive only and nothing goes to the network, which is how
+//! games are reprocessed. Metadata that isn't archived (schedule, teams, venues) comes from the JSON cache as usual, anything that
+//! isn't cached shows up as a `NotArchived` failure.
+
+use crate::config::BossConfig;
+use crate::error::BossError;
+use crate::transport::Transport;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The responses we keep. Everything else is small enough, or changes often enough, that the JSON cache covers it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Endpoint {
+    PlayByPlay,
+    BoxScore,
+    FeedLive,
+    Coaches,
+    People,
+}
+
+impl Endpoint {
+    fn folder (&self) -> &'static str {
+        match self {
+            Endpoint::PlayByPlay => "play_by_play",
+            Endpoint::BoxScore => "boxscore",
+            Endpoint::FeedLive => "feed_live",
+            Endpoint::Coaches => "coaches",
+            Endpoint::People => "people",
+        }
+    }
+
+    /// Works out which endpoint (and id) a Stats API url is for, if it's one we archive. The id is the game_pk for game endpoints,
+    /// the player id for people and "team_year-month-day" for coaches.
+    pub fn from_url (url: &str) -> Option<(Endpoint, String)> {
+        let path = url.split("/api/").nth(1)?;
+        let (path, query) = match path.find('?') {
+            Some (i) => (&path[.. i], &path[i + 1 ..]),
+            None => (path, ""),
+        };
+        let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+
+        match parts.as_slice() {
+            [_, "game", game_pk, "playByPlay"] => Some((Endpoint::PlayByPlay, game_pk.to_string())),
+            [_, "game", game_pk, "boxscore"] => Some((Endpoint::BoxScore, game_pk.to_string())),
+            [_, "game", game_pk, "feed", "live"] => Some((Endpoint::FeedLive, game_pk.to_string())),
+            [_, "people", id] => Some((Endpoint::People, id.to_string())),
+            [_, "teams", team_id, "coaches"] => {
+                // date=6/19/2019
+                let date = query.split('&').filter_map(|param| param.strip_prefix("date=")).nth(0)?;
+                let mdy: Vec<&str> = date.split('/').collect();
+                if mdy.len() != 3 {return None};
+                Some((Endpoint::Coaches, format!("{}_{}-{}-{}", team_id, mdy[2], mdy[0], mdy[1])))
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArchiveMode {
+    /// Don't use the archive at all.
+    Off,
+    /// Fetch as normal and save every archived response.
+    Write,
+    /// Answer from the archive only, never the network. Anything missing comes back as `BossError::NotArchived`.
+    ReadOnly,
+}
+
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+    mode: ArchiveMode,
+}
+
+impl Archive {
+    pub fn new <P: Into<PathBuf>> (dir: P, mode: ArchiveMode) -> Archive {
+        Archive {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    /// Uses the archive folder of the config.
+    pub fn from_config (config: &BossConfig, mode: ArchiveMode) -> Archive {
+        Archive::new(config.archive_dir.clone(), mode)
+    }
+
+    pub fn mode (&self) -> ArchiveMode {
+        self.mode
+    }
+
+    fn ref_path (&self, endpoint: Endpoint, id: &str) -> PathBuf {
+        self.dir.join("refs").join(endpoint.folder()).join(id)
+    }
+
+    fn object_path (&self, hash: &str) -> PathBuf {
+        self.dir.join("objects").join(&hash[.. 2]).join(format!("{}.json.gz", hash))
+    }
+
+    /// The archived response for an id, if we have one.
+    pub fn get (&self, endpoint: Endpoint, id: &str) -> Result<Option<String>, BossError> {
+        let hash = match std::fs::read_to_string(self.ref_path(endpoint, id)) {
+            Ok (hash) => hash,
+            Err (err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err (err) => return Err(err.into()),
+        };
+
+        let file = std::fs::File::open(self.object_path(hash.trim()))?;
+        let mut text = String::new();
+        GzDecoder::new(file).read_to_string(&mut text)?;
+        Ok(Some(text))
+    }
+
+    /// Saves a response, replacing whatever was archived for that id before. The content is only written if we don't already
+    /// have it.
+    pub fn put (&self, endpoint: Endpoint, id: &str, text: &str) -> Result<(), BossError> {
+        let hash: String = Sha256::digest(text.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        let object_path = self.object_path(&hash);
+        if !object_path.exists() {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(text.as_bytes())?;
+            write_atomic(&object_path, &encoder.finish()?)?;
+        }
+
+        write_atomic(&self.ref_path(endpoint, id), hash.as_bytes())
+    }
+
+    /// Every id we have a response for.
+    pub fn ids (&self, endpoint: Endpoint) -> Result<Vec<String>, BossError> {
+        let folder = self.dir.join("refs").join(endpoint.folder());
+        let entries = match std::fs::read_dir(folder) {
+            Ok (entries) => entries,
+            Err (err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err (err) => return Err(err.into()),
+        };
+
+        let mut ids = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".tmp") {ids.push(name)};
+        };
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Writes to a temp file first, so that a pull that gets killed half way never leaves a truncated file behind. The counter keeps
+// two threads writing the same object from sharing a temp file.
+fn write_atomic (path: &Path, bytes: &[u8]) -> Result<(), BossError> {
+    if let Some (parent) = path.parent() {std::fs::create_dir_all(parent)?};
+    let tmp = path.with_extension(format!("{}-{}.tmp", std::process::id(), TMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Transport that reads from and/or writes to the archive, depending on its mode. When writing, urls for endpoints we don't archive
+/// go straight to the inner transport. When reading, the inner transport is never used.
+pub struct ArchiveTransport <T: Transport> {
+    inner: T,
+    archive: Archive,
+}
+
+impl <T: Transport> ArchiveTransport <T> {
+    pub fn new (inner: T, archive: Archive) -> ArchiveTransport<T> {
+        ArchiveTransport {
+            inner,
+            archive,
+        }
+    }
+}
+
+impl <T: Transport> Transport for ArchiveTransport <T> {
+    fn get (&self, url: &str) -> Result<String, BossError> {
+        let (endpoint, id) = match (Endpoint::from_url(url), self.archive.mode) {
+            (Some (key), _) => key,
+            (None, ArchiveMode::ReadOnly) => return Err(BossError::NotArchived {url: url.to_string()}),
+            (None, _) => return self.inner.get(url),
+        };
+
+        match self.archive.mode {
+            ArchiveMode::Off => self.inner.get(url),
+            ArchiveMode::Write => {
+                let text = self.inner.get(url)?;
+                self.archive.put(endpoint, &id, &text)?;
+                Ok(text)
+            },
+            ArchiveMode::ReadOnly => match self.archive.get(endpoint, &id)? {
+                Some (text) => Ok(text),
+                None => Err(BossError::NotArchived {url: url.to_string()}),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir (name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boss_archive_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Answers every url with its own text, and counts how often it was asked.
+    struct EchoTransport {
+        calls: AtomicUsize,
+    }
+
+    impl Transport for EchoTransport {
+        fn get (&self, url: &str) -> Result<String, BossError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("{{\"url\": \"{}\"}}", url))
+        }
+    }
+
+    fn echo () -> EchoTransport {
+        EchoTransport {calls: AtomicUsize::new(0)}
+    }
+
+    #[test]
+    fn endpoint_from_url () {
+        let key = |endpoint: Endpoint, id: &str| Some((endpoint, id.to_string()));
+
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/game/566083/playByPlay"), key(Endpoint::PlayByPlay, "566083"));
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/game/566083/boxscore"), key(Endpoint::BoxScore, "566083"));
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1.1/game/566083/feed/live"), key(Endpoint::FeedLive, "566083"));
+        assert_eq!(
+            Endpoint::from_url("http://statsapi.mlb.com/api/v1/people/100001?hydrate=xrefId,draft,transactions,awards,education"),
+            key(Endpoint::People, "100001"),
+        );
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/teams/147/coaches/?date=6/19/2019"), key(Endpoint::Coaches, "147_2019-6-19"));
+
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/teams/147/coaches/"), None);
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/teams/147/coaches/?date=2019-06-19"), None);
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/schedule?sportId=1"), None);
+        assert_eq!(Endpoint::from_url("http://statsapi.mlb.com/api/v1/game/566083/linescore"), None);
+        assert_eq!(Endpoint::from_url("http://mlb.mlb.com/images/gameday/fields/svg/3.svg"), None);
+    }
+
+    #[test]
+    fn put_then_get () {
+        let archive = Archive::new(temp_dir("round_trip"), ArchiveMode::Write);
+        assert_eq!(archive.get(Endpoint::People, "100001").unwrap(), None);
+
+        archive.put(Endpoint::People, "100001", "{\"id\": 100001}").unwrap();
+        archive.put(Endpoint::People, "100002", "{\"id\": 100001}").unwrap();
+        assert_eq!(archive.get(Endpoint::People, "100001").unwrap().as_deref(), Some("{\"id\": 100001}"));
+        assert_eq!(archive.get(Endpoint::BoxScore, "100001").unwrap(), None);
+        assert_eq!(archive.ids(Endpoint::People).unwrap(), vec!["100001", "100002"]);
+
+        // The same content is only stored once.
+        let objects: usize = std::fs::read_dir(archive.dir.join("objects")).unwrap()
+            .map(|folder| std::fs::read_dir(folder.unwrap().path()).unwrap().count())
+            .sum();
+        assert_eq!(objects, 1);
+
+        archive.put(Endpoint::People, "100001", "{\"id\": 100001, \"weight\": 200}").unwrap();
+        assert_eq!(archive.get(Endpoint::People, "100001").unwrap().as_deref(), Some("{\"id\": 100001, \"weight\": 200}"));
+    }
+
+    #[test]
+    fn write_then_read_only () {
+        let dir = temp_dir("transport");
+        let url = "http://statsapi.mlb.com/api/v1/game/566083/playByPlay";
+        let schedule = "http://statsapi.mlb.com/api/v1/schedule?sportId=1";
+
+        let writer = ArchiveTransport::new(echo(), Archive::new(&dir, ArchiveMode::Write));
+        let text = writer.get(url).unwrap();
+        writer.get(schedule).unwrap();
+        assert_eq!(writer.inner.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(writer.archive.ids(Endpoint::PlayByPlay).unwrap(), vec!["566083"]);
+
+        let reader = ArchiveTransport::new(echo(), Archive::new(&dir, ArchiveMode::ReadOnly));
+        assert_eq!(reader.get(url).unwrap(), text);
+        assert_eq!(reader.inner.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn read_only_misses_are_not_archived () {
+        let reader = ArchiveTransport::new(echo(), Archive::new(temp_dir("misses"), ArchiveMode::ReadOnly));
+
+        let not_archived = |url: &str| matches!(reader.get(url), Err (BossError::NotArchived {url: ref missing}) if missing == url);
+        assert!(not_archived("http://statsapi.mlb.com/api/v1/game/566083/boxscore"));
+        assert!(not_archived("http://statsapi.mlb.com/api/v1/schedule?sportId=1"));
+        assert_eq!(reader.inner.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn off_passes_everything_through () {
+        let dir = temp_dir("off");
+        let transport = ArchiveTransport::new(echo(), Archive::new(&dir, ArchiveMode::Off));
+        transport.get("http://statsapi.mlb.com/api/v1/game/566083/playByPlay").unwrap();
+        assert_eq!(transport.inner.calls.load(Ordering::SeqCst), 1);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,109 @@
+//! Player awards, such as all-star selections, player of the week and MVP. Like the transactions, they come along with the player's
+//! bio (`hydrate=awards`) and are cached in their own file, `awards.json`, one row per award.
+//!
+//! We want to see whether the awards a player picks up in the minors say anything about how they'll do later, so pitches carry the
+//! number of all-star selections the batter and pitcher had before the game.
+//!
+//! Players that were already cached have no awards until their bio is pulled again, such as with
+//! `.refresh(Dataset::Players, Overwrite::Always)`, so until then their pitches count no all-star selections.
+
+use crate::utils::Date;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Award {
+    /// The API's id for the award, such as "ALAS" for an AL all-star selection.
+    pub award_id: String,
+    pub name: String,
+    pub player_id: u32,
+    pub season: Option<u16>,
+    pub date: Option<Date>,
+    pub team_id: Option<u32>,
+    pub league_id: Option<u32>,
+}
+
+impl Award {
+    /// The day the award was handed out. Awards with only a season are treated as handed out at the end of that season.
+    pub fn awarded (&self) -> Option<Date> {
+        self.date.or_else(|| self.season.map(|year| Date {year, month: 12, day: 31}))
+    }
+
+    /// All-star selections at any level, including the minor league all-star games and the Futures Game.
+    pub fn is_all_star (&self) -> bool {
+        self.name.to_lowercase().contains("all-star")
+    }
+}
+
+/// The awards for each player, in the order they were handed out.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerAwards {
+    players: HashMap<u32, Vec<Award>>,
+}
+
+impl PlayerAwards {
+    pub fn get (&self, player_id: u32) -> &[Award] {
+        self.players.get(&player_id).map(|awards| awards.as_slice()).unwrap_or(&[])
+    }
+
+    /// The number of the player's awards that match `filter` and were handed out before `date`.
+    pub fn count_before <F: Fn(&Award) -> bool> (&self, player_id: u32, date: Date, filter: F) -> u16 {
+        self.get(player_id).iter()
+            .filter(|award| award.awarded().map(|awarded| awarded < date).unwrap_or(false))
+            .filter(|award| filter(award))
+            .count() as u16
+    }
+
+    pub fn all_star_selections_before (&self, player_id: u32, date: Date) -> u16 {
+        self.count_before(player_id, date, Award::is_all_star)
+    }
+}
+
+impl From<Vec<Award>> for PlayerAwards {
+    fn from (awards: Vec<Award>) -> PlayerAwards {
+        let mut players: HashMap<u32, Vec<Award>> = HashMap::new();
+        for award in awards {
+            players.entry(award.player_id).or_default().push(award);
+        };
+        for awards in players.values_mut() {
+            awards.sort_by_key(|award| award.awarded());
+        };
+        PlayerAwards {players}
+    }
+}
+
+// Every field is optional: awards come along with the player's bio, so one we can't read mustn't cost us the player.
+#[derive(Deserialize, Debug, Clone)]
+pub (crate) struct AwardDe {
+    id: Option<String>,
+    name: Option<String>,
+    date: Option<String>,
+    season: Option<String>,
+    team: Option<IdRef>,
+    league: Option<IdRef>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub (crate) struct IdRef {
+    id: Option<u32>,
+}
+
+/// Turns the player's awards from the people endpoint into `Award`s. Awards without an id are dropped, since we can't tell what
+/// they are.
+pub (crate) fn parse_awards (player_id: u32, awards: Vec<AwardDe>) -> Vec<Award> {
+    awards.into_iter()
+        .filter_map(|award| {
+            let award_id = award.id?;
+            Some (Award {
+                name: award.name.unwrap_or_else(|| award_id.clone()),
+                award_id,
+                player_id,
+                season: award.season.and_then(|season| season.parse().ok()),
+                date: award.date.and_then(|date| date.try_into().ok()),
+                team_id: award.team.and_then(|team| team.id),
+                league_id: award.league.and_then(|league| league.id),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,403 @@
+//! Command line interface for BOSS. Every command is a thin layer over the library: `fetch` pulls the data (and archives the raw
+//! responses), while `export` and `re288 build` reprocess the archived games, so they can be re-run as often as needed without going back
+//! to the network.
+//!
+//! Exit codes: 0 when everything worked, 1 when the command failed, 2 for bad arguments and 3 when the command finished but some items
+//! (games, players...) failed along the way.
+
+use boss::archive::ArchiveMode;
+use boss::cache::cache_status;
+use boss::config::BossConfig;
+use boss::error::BossError;
+use boss::export::{export_pitches, export_pitch_columns, ExportFormat};
+use boss::get_data::report_failures;
+use boss::manifest::{GameStatus, Manifest};
+use boss::pitch_fields::{FieldGroup, PitchColumns};
+use boss::query::stream_event_games;
+use boss::retrosheet::{write_event_files, EventGame};
+use boss::run_expectancy::build_re288;
+use boss::schedule::GameType;
+use boss::statcast::{merge_statcast, read_statcast};
+use boss::{Boss, BossData};
+use futures::executor::block_on;
+use futures::StreamExt;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+const USAGE: &str = "\
+Usage: boss <command> [options]
+
+Commands:
+    fetch --years 2019-2020 [--sport 1,11] [--game-type R,P]
+        Pull the schedule, metadata and play by play, and archive the raw responses.
+    export --out FILE [--format csv|parquet|sqlite|retrosheet] [--groups core,pitch-tracking] [--columns batter_age,venue_name]
+           [--statcast DIR [--statcast-report FILE]] --years ... [--sport ...] [--game-type ...] [--online]
+        Rebuild the pitches from the archive and write them to FILE. sqlite writes the metadata too, as separate tables.
+        retrosheet writes Retrosheet event files into the folder FILE, one per home team and season.
+        --groups and --columns pick the columns to write (csv and parquet only). Groups: core, pitch-tracking, hit-data,
+        statcast, batter-bio, pitcher-bio, coaches, umpire, venue, weather, scorer.
+        --statcast merges the Baseball Savant CSVs in DIR into the pitches first. --statcast-report writes the rows that
+        didn't match a pitch to FILE.
+    cache status
+        Show what's in the cache.
+    games [--failed] [--status no-boxscore,parse-error] [--retry]
+        Show the games in the manifest. --retry marks the listed games to be pulled again.
+    re288 build [--out FILE] --years ... [--sport ...] [--game-type ...] [--online]
+        Build an RE288 table from the archived games. Prints to the terminal unless --out is given.
+
+Options for every command:
+    --data-root DIR     Folder holding the cache, archive and output folders. Defaults to the current folder.
+    --gameday-mirror DIR
+                        Local mirror of the Gameday XML files, used to fill in umpires and game day weights.
+
+--online lets export and re288 pull games that aren't archived yet, instead of reporting them as failures. Parquet output needs
+boss to be built with the parquet feature, and SQLite output with the sqlite feature.";
+
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_PARTIAL: i32 = 3;
+
+/// Options that don't take a value.
+const FLAGS: [&str; 3] = ["failed", "retry", "online"];
+
+enum CliError {
+    Usage (String),
+    Boss (BossError),
+}
+
+impl From<BossError> for CliError {
+    fn from (err: BossError) -> CliError {
+        CliError::Boss(err)
+    }
+}
+
+struct Args {
+    commands: Vec<String>,
+    options: BTreeMap<String, String>,
+}
+
+impl Args {
+    fn parse <I: Iterator<Item = String>> (mut args: I) -> Result<Args, CliError> {
+        let mut commands = Vec::new();
+        let mut options = BTreeMap::new();
+
+        while let Some (arg) = args.next() {
+            let name = match arg.strip_prefix("--") {
+                Some (name) => name.to_string(),
+                None => {
+                    commands.push(arg);
+                    continue;
+                },
+            };
+
+            if let Some (i) = name.find('=') {
+                options.insert(name[.. i].to_string(), name[i + 1 ..].to_string());
+            }
+            else if FLAGS.contains(&name.as_str()) || name == "help" {
+                options.insert(name, String::new());
+            }
+            else {
+                let value = args.next().ok_or_else(|| CliError::Usage(format!("--{} needs a value", name)))?;
+                options.insert(name, value);
+            };
+        };
+
+        Ok(Args {commands, options})
+    }
+
+    fn get (&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|value| value.as_str())
+    }
+
+    fn flag (&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn required (&self, name: &str) -> Result<&str, CliError> {
+        self.get(name).ok_or_else(|| CliError::Usage(format!("--{} is required", name)))
+    }
+
+    fn config (&self) -> BossConfig {
+        let config = match self.get("data-root") {
+            Some (root) => BossConfig::new(root),
+            None => BossConfig::default(),
+        };
+        match self.get("gameday-mirror") {
+            Some (mirror) => config.gameday_mirror(mirror),
+            None => config,
+        }
+    }
+
+    /// Builds the query from --years, --sport and --game-type.
+    fn query (&self, archive: ArchiveMode) -> Result<Boss, CliError> {
+        let mut query = Boss::new()
+            .config(self.config())
+            .archive(archive)
+            .years(parse_years(self.required("years")?)?);
+
+        if let Some (sports) = self.get("sport") {
+            query = query.sports(parse_list::<u32>(sports, "sport")?);
+        };
+
+        if let Some (game_types) = self.get("game-type") {
+            let game_types = game_types.split(',')
+                .map(|game_type| serde_json::from_value::<GameType>(serde_json::Value::String(game_type.trim().to_string()))
+                    .map_err(|_| CliError::Usage(format!("unknown game type: {}", game_type))))
+                .collect::<Result<Vec<GameType>, CliError>>()?;
+            query = query.game_types(game_types);
+        };
+
+        Ok(query)
+    }
+
+    /// Archive mode for the commands that reprocess games.
+    fn reprocess_mode (&self) -> ArchiveMode {
+        if self.flag("online") {ArchiveMode::Write} else {ArchiveMode::ReadOnly}
+    }
+}
+
+/// "2019", "2015-2019" or "2015,2017,2019".
+fn parse_years (years: &str) -> Result<Vec<u16>, CliError> {
+    let bad_years = || CliError::Usage(format!("can't read the years: {}", years));
+
+    if let Some (i) = years.find('-') {
+        let start: u16 = years[.. i].trim().parse().map_err(|_| bad_years())?;
+        let end: u16 = years[i + 1 ..].trim().parse().map_err(|_| bad_years())?;
+        if start > end {return Err(bad_years())};
+        return Ok((start ..= end).collect());
+    };
+
+    parse_list(years, "years")
+}
+
+fn parse_list <T: FromStr> (list: &str, name: &str) -> Result<Vec<T>, CliError> {
+    list.split(',')
+        .map(|item| item.trim().parse().map_err(|_| CliError::Usage(format!("can't read --{}: {}", name, item))))
+        .collect()
+}
+
+/// Prints the failures from a pull and works out the exit code.
+fn report (data: &BossData) -> i32 {
+    report_failures(&data.failures);
+    for request in data.failed_requests.iter() {
+        println!("  {}", request);
+    };
+
+    if !data.failures.is_empty() || !data.failed_requests.is_empty() {EXIT_PARTIAL} else {EXIT_OK}
+}
+
+fn fetch (args: &Args) -> Result<i32, CliError> {
+    let data = args.query(ArchiveMode::Write)?.fetch()?;
+    println!("Fetched {} pitches from {} games.", data.pitches.len(), data.meta_data.schedule.len());
+    Ok(report(&data))
+}
+
+fn export (args: &Args) -> Result<i32, CliError> {
+    let out = args.required("out")?;
+
+    if args.get("format") == Some("retrosheet") {
+        return export_retrosheet(args, out);
+    };
+
+    #[cfg(feature = "sqlite")]
+    {
+        if args.get("format") == Some("sqlite") {
+            let mut data = args.query(args.reprocess_mode())?.fetch()?;
+            merge_statcast_dir(args, &mut data)?;
+            boss::sqlite::write_sqlite(&data.meta_data, &data.pitches, out)?;
+            println!("Wrote {} pitches and their metadata to {}.", data.pitches.len(), out);
+            return Ok(report(&data));
+        };
+    }
+
+    let format = ExportFormat::from_str(args.get("format").unwrap_or("csv"))
+        .map_err(|_| CliError::Usage(format!("unknown format: {}", args.get("format").unwrap_or(""))))?;
+
+    let columns = match (args.get("groups"), args.get("columns")) {
+        (None, None) => None,
+        (groups, columns) => {
+            let groups = match groups {
+                Some (groups) => parse_list::<FieldGroup>(groups, "groups")?,
+                None => Vec::new(),
+            };
+            let columns: Vec<&str> = columns.map(|columns| columns.split(',').map(|column| column.trim()).collect()).unwrap_or_default();
+            let selection = PitchColumns::new().groups(groups).columns(columns);
+            // Check the names now, rather than after the whole pull.
+            selection.names().map_err(|err| CliError::Usage(err.to_string()))?;
+            Some(selection)
+        },
+    };
+
+    let mut data = args.query(args.reprocess_mode())?.fetch()?;
+    merge_statcast_dir(args, &mut data)?;
+    match columns {
+        Some (columns) => export_pitch_columns(&data.pitches, format, &columns, out)?,
+        None => export_pitches(&data.pitches, format, out)?,
+    };
+    println!("Wrote {} pitches to {}.", data.pitches.len(), out);
+    Ok(report(&data))
+}
+
+/// Merges the Statcast CSVs from --statcast into the pitches, if there are any.
+fn merge_statcast_dir (args: &Args, data: &mut BossData) -> Result<(), CliError> {
+    let dir = match args.get("statcast") {
+        Some (dir) => dir,
+        None => return Ok(()),
+    };
+
+    let report = merge_statcast(&mut data.pitches, read_statcast(dir)?);
+    println!("{}", report);
+    if let Some (out) = args.get("statcast-report") {
+        report.write_csv(out)?;
+        println!("Wrote the unmatched Statcast rows to {}.", out);
+    };
+    Ok(())
+}
+
+/// Streams the games rather than fetching the pitches, since the event files are built from the play by play.
+fn export_retrosheet (args: &Args, out: &str) -> Result<i32, CliError> {
+    let query = args.query(args.reprocess_mode())?;
+    let results: Vec<Result<EventGame, BossError>> = block_on(async {
+        stream_event_games(query).await.collect().await
+    });
+
+    let mut games = Vec::with_capacity(results.len());
+    let mut failed = 0;
+    for result in results {
+        match result {
+            Ok (game) => games.push(game),
+            Err (err) => {
+                println!("  {}", err);
+                failed += 1;
+            },
+        };
+    };
+
+    let game_count = games.len();
+    let files = write_event_files(games, out)?;
+    println!("Wrote {} games to {} event files in {}.", game_count, files.len(), out);
+
+    if failed > 0 {
+        println!("{} items failed.", failed);
+        Ok(EXIT_PARTIAL)
+    } else {
+        Ok(EXIT_OK)
+    }
+}
+
+fn cache (args: &Args) -> Result<i32, CliError> {
+    match args.commands.get(1).map(|command| command.as_str()) {
+        Some ("status") => {},
+        _ => return Err(CliError::Usage("expected: boss cache status".to_string())),
+    };
+
+    let config = args.config();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let days_ago = |fetched: Option<u64>| match fetched {
+        Some (fetched) => format!("{} days ago", now.saturating_sub(fetched) / 86_400),
+        None => "-".to_string(),
+    };
+
+    println!("Cache folder: {}", config.cache_dir.display());
+    println!("{:<20} {:>10} {:>12}   {:<16} {:<16}", "file", "records", "bytes", "oldest fetch", "newest fetch");
+    for file in cache_status(&config)? {
+        println!("{:<20} {:>10} {:>12}   {:<16} {:<16}", file.file_name, file.records, file.bytes, days_ago(file.oldest_fetch), days_ago(file.newest_fetch));
+    };
+
+    println!();
+    println!("Games processed:");
+    for (status, count) in Manifest::load(&config)?.counts() {
+        println!("  {:<24} {:>8}", status, count);
+    };
+
+    Ok(EXIT_OK)
+}
+
+fn games (args: &Args) -> Result<i32, CliError> {
+    let config = args.config();
+    let mut manifest = Manifest::load(&config)?;
+
+    let statuses: Vec<GameStatus> = match (args.get("status"), args.flag("failed")) {
+        (Some (statuses), _) => parse_list(statuses, "status")?,
+        (None, true) => GameStatus::all().into_iter().filter(|status| *status != GameStatus::Ok).collect(),
+        (None, false) => GameStatus::all(),
+    };
+
+    let mut listed = 0;
+    for entry in manifest.entries().filter(|entry| statuses.contains(&entry.status)) {
+        println!("{:>8}  {:<24} attempts: {:<3} {}", entry.game_pk, entry.status, entry.attempts, entry.error.clone().unwrap_or_default());
+        listed += 1;
+    };
+    println!("{} games.", listed);
+
+    if args.flag("retry") {
+        let marked = manifest.retry(&statuses);
+        manifest.save(&config)?;
+        println!("{} games will be retried on the next pull.", marked);
+    };
+
+    Ok(EXIT_OK)
+}
+
+fn re288 (args: &Args) -> Result<i32, CliError> {
+    match args.commands.get(1).map(|command| command.as_str()) {
+        Some ("build") => {},
+        _ => return Err(CliError::Usage("expected: boss re288 build".to_string())),
+    };
+
+    let data = args.query(args.reprocess_mode())?.fetch()?;
+    let table = build_re288(&data.pitches);
+
+    match args.get("out") {
+        Some (out) => {
+            let mut csv_writer = csv::Writer::from_path(out).map_err(BossError::from)?;
+            for re in table.iter() {
+                csv_writer.serialize(re).map_err(BossError::from)?;
+            };
+            csv_writer.flush().map_err(BossError::from)?;
+            println!("Wrote the RE288 table from {} pitches to {}.", data.pitches.len(), out);
+        },
+        None => {
+            println!("{:>5} {:>7} {:>5} {:>5} {:>8}", "outs", "balls", "str", "base", "RE");
+            for re in table.iter() {
+                println!("{:>5} {:>7} {:>5} {:>5} {:>8.3}", re.outs, re.balls, re.strikes, re.base_value, re.run_expectancy);
+            };
+        },
+    };
+
+    Ok(report(&data))
+}
+
+fn run (args: &Args) -> Result<i32, CliError> {
+    if args.flag("help") {
+        println!("{}", USAGE);
+        return Ok(EXIT_OK);
+    };
+
+    match args.commands.first().map(|command| command.as_str()) {
+        Some ("fetch") => fetch(args),
+        Some ("export") => export(args),
+        Some ("cache") => cache(args),
+        Some ("games") => games(args),
+        Some ("re288") => re288(args),
+        Some (command) => Err(CliError::Usage(format!("unknown command: {}", command))),
+        None => Err(CliError::Usage("no command given".to_string())),
+    }
+}
+
+fn main () {
+    let code = match Args::parse(std::env::args().skip(1)).and_then(|args| run(&args)) {
+        Ok (code) => code,
+        Err (CliError::Usage (message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            EXIT_USAGE
+        },
+        Err (CliError::Boss (err)) => {
+            eprintln!("Error: {}", err);
+            EXIT_ERROR
+        },
+    };
+
+    std::process::exit(code);
+}
@@ -1,28 +0,0 @@
-use boss::*;
-
-fn main() {
-    
-    // boxscore::test_boxscore();
-    // schedule::test_schedule();
-
-    get_data::get_everything();
-
-    // coaches::test_coaches();
-
-    // utils::stream();
-
-    // player_changes::test_changes();
-
-    // venues::test_venues();
-
-    // utils::get_directory();
-
-    // sports::test_sports();
-
-    // feed_live::test_feed();
-
-    // game::test_play_by_play ();
-
-    // game::size_of_pitch_struct();
-
-}
\ No newline at end of file
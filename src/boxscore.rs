@@ -45,6 +45,9 @@ pub struct BoxScore {
   
   pub home_team_id: u32,
   pub away_team_id: u32,
+  /// Short team codes, such as "nya". Boxscores cached before these were added won't have them.
+  pub home_team_code: Option<String>,
+  pub away_team_code: Option<String>,
   pub home_league_id: Option<u32>,
   pub home_league_name: Option<String>,
   pub away_league_id: Option<u32>,
@@ -61,6 +64,9 @@ pub struct BoxScore {
   pub away_parent_team_id: u32,
   
   pub hp_umpire_id: Option<u32>,
+  /// The whole umpire crew. Boxscores cached before we kept the crew have an empty list until they're pulled again.
+  #[serde(default)]
+  pub officials: Vec<GameOfficial>,
 
   pub home_players: Vec<Player>,
   pub away_players: Vec<Player>,
@@ -70,6 +76,63 @@ pub struct BoxScore {
 
 }
 
+/// One umpire working the game, and where.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GameOfficial {
+  pub id: u32,
+  pub name: Option<String>,
+  pub official_type: OfficialType,
+}
+
+/// The umpire at each spot, by id. Most games only have the four infield umpires; the outfield umpires are there for the postseason
+/// and a few special games.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UmpireCrew {
+  pub home_plate: Option<u32>,
+  pub first_base: Option<u32>,
+  pub second_base: Option<u32>,
+  pub third_base: Option<u32>,
+  pub left_field: Option<u32>,
+  pub right_field: Option<u32>,
+  /// Only when the boxscore lists a crew chief, which most don't.
+  pub crew_chief: Option<u32>,
+}
+
+impl UmpireCrew {
+  /// Every umpire on the crew, sorted by id, so games worked by the same crew can be grouped together whoever was behind the plate.
+  pub fn members (&self) -> Vec<u32> {
+    let mut members: Vec<u32> = vec![self.home_plate, self.first_base, self.second_base, self.third_base, self.left_field, self.right_field, self.crew_chief]
+      .into_iter()
+      .flatten()
+      .collect();
+    members.sort_unstable();
+    members.dedup();
+    members
+  }
+}
+
+impl BoxScore {
+  /// The umpire working the given spot, if the boxscore has one.
+  pub fn official (&self, official_type: &OfficialType) -> Option<&GameOfficial> {
+    self.officials.iter().find(|official| official.official_type == *official_type)
+  }
+
+  pub fn umpire_crew (&self) -> UmpireCrew {
+    let id = |official_type: OfficialType| self.official(&official_type).map(|official| official.id);
+    UmpireCrew {
+      home_plate: id(OfficialType::HomePlate).or(self.hp_umpire_id),
+      first_base: id(OfficialType::FirstBase),
+      second_base: id(OfficialType::SecondBase),
+      third_base: id(OfficialType::ThirdBase),
+      left_field: id(OfficialType::LeftField),
+      right_field: id(OfficialType::RightField),
+      crew_chief: self.officials.iter()
+        .find(|official| matches!(&official.official_type, OfficialType::Other (name) if name.to_lowercase().contains("crew chief")))
+        .map(|official| official.id),
+    }
+  }
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct Defense {
   pub catcher: Option<u32>,
@@ -198,7 +261,7 @@ impl From <BoxScoreDe> for BoxScore {
     
     let first_pitch = match hours_minutes.len() {
       2 => {
-        let am_pm = first_pitch_vec[1].replace(".","").to_lowercase();
+        let am_pm = first_pitch_vec.get(1).unwrap_or(&"").replace(".","").to_lowercase();
         let first_pitch_f32 = hours_minutes[0].parse::<f32>().unwrap_or(0f32) 
                         + hours_minutes[1].parse::<f32>().unwrap_or(0f32)/60f32 
                         + match am_pm.as_str() {
@@ -219,6 +282,11 @@ impl From <BoxScoreDe> for BoxScore {
                         .nth(0)
                         ;
 
+    let officials: Vec<GameOfficial> = box_score.officials.into_iter()
+                        .map(|ump| GameOfficial {id: ump.official.id, name: ump.official.full_name, official_type: ump.official_type})
+                        .collect()
+                        ;
+
     let home_players = player_id_to_player(box_score.teams.home.players);
     let away_players = player_id_to_player(box_score.teams.away.players);
 
@@ -245,6 +313,8 @@ impl From <BoxScoreDe> for BoxScore {
       first_pitch,
       home_team_id: box_score.teams.home.team.id,
       away_team_id: box_score.teams.away.team.id,
+      home_team_code: Some(box_score.teams.home.team.team_code.clone()),
+      away_team_code: Some(box_score.teams.away.team.team_code.clone()),
       home_league_id: box_score.teams.home.team.league.id,
       home_league_name: box_score.teams.home.team.league.name,
       away_league_id: box_score.teams.away.team.league.id,
@@ -259,6 +329,7 @@ impl From <BoxScoreDe> for BoxScore {
       home_parent_team_id: box_score.teams.home.team.parent_org_id.unwrap_or(box_score.teams.home.team.id),
       away_parent_team_id: box_score.teams.away.team.parent_org_id.unwrap_or(box_score.teams.away.team.id),
       hp_umpire_id,
+      officials,
       home_defense,
       away_defense,
       home_players,
@@ -354,26 +425,58 @@ pub (crate) struct Officials {
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(rename_all="camelCase")]
 pub (crate) struct Official {
   pub (crate) id: u32,
+  pub (crate) full_name: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Eq, PartialEq)]
-pub (crate) enum OfficialType {
-  #[serde(alias="Home Plate")]
+/// Where an umpire is working. Types we haven't seen before are kept as `Other` with the API's name for them rather than failing
+/// the boxscore. Reads and writes as the API's name, such as "Home Plate".
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(from="String", into="String")]
+pub enum OfficialType {
   HomePlate,
-  //we only really care about the HP Umpire, however, we want the program to panic if we see
-  //an umpire type that we don't expect
-  #[serde(alias="First Base")]
   FirstBase,
-  #[serde(alias="Second Base")]
   SecondBase,
-  #[serde(alias="Third Base")]
   ThirdBase,
-  #[serde(alias="Left Field")]
   LeftField,
-  #[serde(alias="Right Field")]
   RightField,
+  Other (String),
+}
+
+impl OfficialType {
+  pub fn name (&self) -> &str {
+    match self {
+      OfficialType::HomePlate => "Home Plate",
+      OfficialType::FirstBase => "First Base",
+      OfficialType::SecondBase => "Second Base",
+      OfficialType::ThirdBase => "Third Base",
+      OfficialType::LeftField => "Left Field",
+      OfficialType::RightField => "Right Field",
+      OfficialType::Other (name) => name,
+    }
+  }
+}
+
+impl From<String> for OfficialType {
+  fn from (name: String) -> OfficialType {
+    match name.as_str() {
+      "Home Plate" | "HomePlate" => OfficialType::HomePlate,
+      "First Base" | "FirstBase" => OfficialType::FirstBase,
+      "Second Base" | "SecondBase" => OfficialType::SecondBase,
+      "Third Base" | "ThirdBase" => OfficialType::ThirdBase,
+      "Left Field" | "LeftField" => OfficialType::LeftField,
+      "Right Field" | "RightField" => OfficialType::RightField,
+      _ => OfficialType::Other(name),
+    }
+  }
+}
+
+impl From<OfficialType> for String {
+  fn from (official_type: OfficialType) -> String {
+    official_type.name().to_string()
+  }
 }
 
 #[derive(Deserialize, Debug)]
@@ -497,7 +600,7 @@ pub (crate) struct Position {
   pub (crate) abbreviation: Pos,
 }
 
-#[derive(Deserialize, Serialize, Debug, Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Deserialize, Serialize, Debug, Hash, Eq, PartialEq, Copy, Clone, Default)]
 pub enum Pos {
   #[serde(rename="C")]
   Catcher,
@@ -522,6 +625,7 @@ pub enum Pos {
   StartingPitcher,
   ReliefPitcher,
   #[serde(other)]
+  #[default]
   Bench,
 }
 
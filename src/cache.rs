@@ -1,11 +1,19 @@
 //! # Caching module for BOSS
 //! Provides caching for data pulled from the network. Data that has been pulled from the network, generally never needs to be pulled again.
 //! For the vast majority of the meta-data, such as Venues, Teams and Players, these data don't change very much. This module will store the smaller data sets as JSON and 
-//! use a custom, highly compact serialization for the pitch by pitch data.
+//! use a custom, highly compact serialization for the pitch by pitch data (see the `pitch_store` module).
 //! 
 //! This module is perhaps the most important as it will allow for easy incremental pulls. It will also serve as a demonstration for how efficicently a game can be stored.
-//! The scraping modules will all need to be aware of the serialized files and have an Overwrite enum to allow for refreshing the data for whatever reason. All serialized data will
-//! be stored in the "/cache" folder with locations specified as consts
+//! The scraping modules are all aware of the serialized files and only pull what's missing, unless the `Overwrite` policy for that data set
+//! (set on the `BossConfig`) says a record is due to be refreshed. All serialized data will be stored in the cache folder of the `BossConfig`
+//! passed in, with file names specified as consts
+//!
+//! # Refreshing
+//!
+//! Every cached metadata record is stored with the time it was fetched (`fetched_at`, seconds since the Unix epoch). Caches written before
+//! we kept timestamps load with a `fetched_at` of 0, so they count as very old. A record that is due for a refresh is pulled again the next
+//! time a pull needs it. If the refresh fails, the old record is kept. The schedule isn't covered by the policy: seasons that aren't
+//! complete are always pulled again.
 //! 
 //! # Groups of Data
 //! 
@@ -21,79 +29,185 @@
 //! 
 //! 
 
-use crate::utils;
+use crate::config::BossConfig;
 use crate::venues;
 use crate::schedule;
 use crate::boxscore;
 use crate::feed_live;
 use crate::coaches;
 use crate::players;
+use crate::player_history;
 use crate::team;
+use crate::transactions;
+use crate::awards;
+use crate::rosters;
 use crate::game;
 use crate::defense;
-use serde::Serialize;
+use crate::manifest;
+use crate::pitch_store;
+use crate::error::BossError;
+use serde::{Serialize, Deserialize};
 use serde::de::DeserializeOwned;
-use csv::{Reader, WriterBuilder};
+use csv::WriterBuilder;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+const VENUE_X_Y_JSON: &str = "venue_xy.json";
+const VENUE_JSON: &str = "venues.json";
+const SCHEDULE_JSON: &str = "schedule.json";
+const FEED_LIVE_JSON: &str = "feed_live.json";
+const BOXSCORE_JSON: &str = "boxscore.json";
+const COACH_JSON: &str = "coaches.json";
+const PLAYER_JSON: &str = "players.json";
+const PLAYER_HISTORY_JSON: &str = "player_history.json";
+const TRANSACTIONS_JSON: &str = "transactions.json";
+const AWARDS_JSON: &str = "awards.json";
+const TEAMS_JSON: &str = "teams.json";
+const GAMES_PROCESSED_JSON: &str = "games_processed.json";
+const MANIFEST_JSON: &str = "manifest.json";
+pub (crate) const PLAY_BY_PLAY: &str = "baseball.csv";
+const PITCH_STORE: &str = "pitches.boss";
+const DEFENSE: &str = "defense.csv";
+
+
+/// When a cached record should be pulled again. Set per data set with `BossConfig::refresh`, the default is `Never`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Overwrite {
+    /// Only pull records we don't have.
+    #[default]
+    Never,
+    /// Pull every record again.
+    Always,
+    /// Pull records fetched more than this many days ago.
+    OlderThan(u32),
+    /// Pull these ids again. The id is the player id for players, the venue id for venues, the team id for teams and rosters and the game_pk
+    /// for boxscores, coaches and feed live data.
+    Ids(BTreeSet<u32>),
+}
+
+impl Overwrite {
+    /// Whether the record for `id`, fetched at `fetched_at` (seconds since the Unix epoch), is due to be pulled again.
+    pub fn is_due (&self, id: u32, fetched_at: u64, now: u64) -> bool {
+        match self {
+            Overwrite::Never => false,
+            Overwrite::Always => true,
+            Overwrite::OlderThan (days) => now.saturating_sub(fetched_at) > *days as u64 * 86_400,
+            Overwrite::Ids (ids) => ids.contains(&id),
+        }
+    }
+}
 
+/// A cached record along with when it was fetched. The record's own fields are flattened into the same JSON object, so the cache files
+/// look the same as they did before we kept timestamps, plus a `fetched_at` field.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub (crate) struct Cached <T> {
+    #[serde(default)]
+    pub fetched_at: u64,
+    #[serde(flatten)]
+    pub record: T,
+}
 
-const VENUE_X_Y_JSON: &str = "\\venue_xy.json";
-const VENUE_JSON: &str = "\\venues.json";
-const SCHEDULE_JSON: &str = "\\schedule.json";
-const FEED_LIVE_JSON: &str = "\\feed_live.json";
-const BOXSCORE_JSON: &str = "\\boxscore.json";
-const COACH_JSON: &str = "\\coaches.json";
-const PLAYER_JSON: &str = "\\players.json";
-const TEAMS_JSON: &str = "\\teams.json";
-const PLAY_BY_PLAY: &str = r#"F:\Baseball\baseball.csv"#;
-const DEFENSE: &str = r#"F:\Baseball\defense.csv"#;
+/// Seconds since the Unix epoch.
+pub (crate) fn now () -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
 
-fn cache_folder () -> String {
-    format!("{}{}", utils::get_directory(), "\\cache" )
+/// A cached data set, split by its `Overwrite` policy into the records we keep as is and the ones that are due to be pulled again.
+pub (crate) struct CachedSet <T> {
+    kept: Vec<Cached<T>>,
+    due: Vec<Cached<T>>,
 }
 
+impl <T> CachedSet <T> {
+    pub fn new <F: Fn(&T) -> u32> (records: Vec<Cached<T>>, overwrite: &Overwrite, id: F) -> CachedSet<T> {
+        let now = now();
+        let (due, kept) = records.into_iter()
+            .partition(|cached| overwrite.is_due(id(&cached.record), cached.fetched_at, now));
+        CachedSet {kept, due}
+    }
+
+    /// Records we don't need to pull again.
+    pub fn kept (&self) -> impl Iterator<Item = &T> {
+        self.kept.iter().map(|cached| &cached.record)
+    }
+
+    /// Records that are due for a refresh.
+    pub fn due (&self) -> impl Iterator<Item = &T> {
+        self.due.iter().map(|cached| &cached.record)
+    }
+
+    /// Adds the newly pulled records, stamped with the current time. A record that was due but didn't get pulled again (it failed, or
+    /// this pull didn't need it) is kept, so that a refresh never loses data. `key` identifies a record within the set.
+    pub fn merge <K: Ord, F: Fn(&T) -> K> (self, new: Vec<T>, key: F) -> Vec<Cached<T>> {
+        let fetched_at = now();
+        let refreshed: BTreeSet<K> = new.iter().map(&key).collect();
 
-fn cache <T> (file_name: &str, data: Vec<T>) 
-where T: Serialize
+        let mut records = self.kept;
+        records.extend(self.due.into_iter().filter(|cached| !refreshed.contains(&key(&cached.record))));
+        records.extend(new.into_iter().map(|record| Cached {fetched_at, record}));
+        records
+    }
+
+    /// Moves the kept records that match `refresh` over to the ones due for a refresh, for records that can go stale whatever the
+    /// `Overwrite` policy says.
+    pub fn refresh_if <F: Fn(&Cached<T>) -> bool> (self, refresh: F) -> CachedSet<T> {
+        let (mut due, kept): (Vec<Cached<T>>, Vec<Cached<T>>) = self.kept.into_iter().partition(|cached| refresh(cached));
+        due.extend(self.due);
+        CachedSet {kept, due}
+    }
+
+    /// Every record, when nothing new was pulled.
+    pub fn into_records (self) -> Vec<T> {
+        self.kept.into_iter().chain(self.due).map(|cached| cached.record).collect()
+    }
+}
+
+/// Strips the timestamps off a cached data set.
+pub (crate) fn records <T: Clone> (cached: &[Cached<T>]) -> Vec<T> {
+    cached.iter().map(|cached| cached.record.clone()).collect()
+}
+
+fn cache <T> (config: &BossConfig, file_name: &str, data: &T) -> Result<(), BossError>
+where T: Serialize + ?Sized
 {
 
-    let folder = cache_folder();
-    let file_name = format!("{}{}", &folder, file_name);
-    let json = serde_json::to_string(&data).unwrap();
-    create_folder(folder);
-    std::fs::write(file_name, json).unwrap();
+    let file_name = config.cache_file(file_name);
+    let json = serde_json::to_string(data)?;
+    create_folder(&config.cache_dir)?;
+    std::fs::write(file_name, json)?;
+    Ok(())
 
 }
 
-fn load <T> (file_name: &str) -> Vec<T>
+/// Loads a cached file. A file that doesn't exist yet simply means we haven't pulled anything, so we return an empty Vec. A file
+/// that exists but can't be parsed is an error, since we'd otherwise overwrite the cache with whatever we pull next.
+fn load <T> (config: &BossConfig, file_name: &str) -> Result<Vec<T>, BossError>
 where T: DeserializeOwned,
-// T: std::fmt::Debug,
 {
-    let folder = cache_folder();
-    let file_name = format!("{}{}", &folder, file_name);
-    create_folder(folder);
-    
-    let json = std::fs::read_to_string(file_name).unwrap_or("".to_string());
-
-    // let dbg_info: Result<T, serde_json::Error> = serde_json::from_str(&json);
-    // if dbg_info.is_err() {
-    //     println!("Error: {:?}", dbg_info);
-    // };
+    let file_name = config.cache_file(file_name);
     
-    serde_json::from_str(&json).unwrap_or(vec![])
+    let json = match std::fs::read_to_string(file_name) {
+        Ok (json) => json,
+        Err (err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err (err) => return Err(err.into()),
+    };
     
+    Ok(serde_json::from_str(&json)?)
 
 }
 
-pub (crate) fn write_defense (defense: &Vec<defense::Defense>) {
+pub (crate) fn write_defense (config: &BossConfig, defense: &Vec<defense::Defense>) -> Result<(), BossError> {
 
-    let exists = match std::fs::File::open(DEFENSE) {
-        Err (_) => false,
-        Ok (_) => true,
-    };
+    let path = config.output_file(DEFENSE);
+    create_folder(&config.output_dir)?;
+
+    let exists = path.exists();
 
     let file = match exists {
-        true => std::fs::OpenOptions::new().append(true).open(DEFENSE).unwrap(),
-        false => std::fs::OpenOptions::new().create(true).write(true).open(DEFENSE).unwrap()
+        true => std::fs::OpenOptions::new().append(true).open(&path)?,
+        false => std::fs::OpenOptions::new().create(true).write(true).open(&path)?
     };
 
 
@@ -102,23 +216,24 @@ pub (crate) fn write_defense (defense: &Vec<defense::Defense>) {
         .from_writer(file);
 
     for d in defense {
-        csv_writer.serialize(d).unwrap();
+        csv_writer.serialize(d)?;
     };
 
+    Ok(())
+
 }
 
-pub (crate) fn append_play_by_play (pitches: &Vec<game::Pitch>) {
+pub (crate) fn append_play_by_play (config: &BossConfig, pitches: &Vec<game::Pitch>) -> Result<(), BossError> {
 
     // Check if the file exists to determine if we need headers and if we should create a new file
+    let path = config.output_file(PLAY_BY_PLAY);
+    create_folder(&config.output_dir)?;
 
-    let exists = match std::fs::File::open(PLAY_BY_PLAY) {
-        Err (_) => false,
-        Ok (_) => true,
-    };
+    let exists = path.exists();
 
     let file = match exists {
-        true => std::fs::OpenOptions::new().append(true).open(PLAY_BY_PLAY).unwrap(),
-        false => std::fs::OpenOptions::new().create(true).write(true).open(PLAY_BY_PLAY).unwrap()
+        true => std::fs::OpenOptions::new().append(true).open(&path)?,
+        false => std::fs::OpenOptions::new().create(true).write(true).open(&path)?
     };
 
     let mut csv_writer = WriterBuilder::new()
@@ -126,111 +241,313 @@ pub (crate) fn append_play_by_play (pitches: &Vec<game::Pitch>) {
                             .from_writer(file);
 
     for pitch in pitches {
-        csv_writer.serialize(pitch).unwrap();
+        csv_writer.serialize(pitch)?;
     };
 
+    Ok(())
+
 }
 
-#[allow(unused)]
-pub (crate) fn load_play_by_play () -> Vec<game::Pitch> {
+/// Adds newly processed games to the pitch store in the cache folder. A store written by an older version of BOSS is migrated to the
+/// current schema first.
+pub (crate) fn cache_play_by_play (config: &BossConfig, pitches: &[game::Pitch]) -> Result<(), BossError> {
+    create_folder(&config.cache_dir)?;
+    let path = config.cache_file(PITCH_STORE);
+    pitch_store::migrate_pitch_store(&path)?;
+    pitch_store::append_pitch_store(path, pitches)
+}
 
-    let mut csv_reader = Reader::from_path(PLAY_BY_PLAY).unwrap();
+/// Loads the pitches from the pitch store that match the filter. If nothing has been stored yet, there are no pitches.
+#[allow(unused)]
+pub (crate) fn load_play_by_play (config: &BossConfig, filter: &pitch_store::PitchFilter) -> Result<Vec<game::Pitch>, BossError> {
 
-    type CSVResult = Result< game::Pitch, csv::Error>;
+    let path = config.cache_file(PITCH_STORE);
+    if !path.exists() {return Ok(vec![])};
 
-    csv_reader.deserialize()
-        .filter_map(|record: CSVResult| record.ok())
-        // .map(|record| {let pitch: game::Pitch = record.unwrap(); pitch})
-        .collect()
+    pitch_store::PitchStore::open(path)?.scan(filter)
         
 }
 
-pub (crate) fn cache_teams_data (teams: &Vec<team::TeamData>) {
-    cache (TEAMS_JSON, teams.clone());
+pub (crate) fn cache_teams_data (config: &BossConfig, teams: &[Cached<team::TeamData>]) -> Result<(), BossError> {
+    cache (config, TEAMS_JSON, teams)
+}
+
+pub (crate) fn load_teams_data (config: &BossConfig) -> Result<Vec<Cached<team::TeamData>>, BossError> {
+    load (config, TEAMS_JSON)
+}
+
+
+pub(crate) fn cache_player_data (config: &BossConfig, players: &[Cached<players::Player>]) -> Result<(), BossError> {
+    cache (config, PLAYER_JSON, players)
+}
+
+pub (crate) fn load_player_data (config: &BossConfig) -> Result<Vec<Cached<players::Player>>, BossError> {
+    load (config, PLAYER_JSON)
+}
+
+pub(crate) fn cache_transactions (config: &BossConfig, transactions: &[transactions::Transaction]) -> Result<(), BossError> {
+    cache (config, TRANSACTIONS_JSON, transactions)
+}
+
+pub (crate) fn load_transactions (config: &BossConfig) -> Result<Vec<transactions::Transaction>, BossError> {
+    load (config, TRANSACTIONS_JSON)
+}
+
+pub(crate) fn cache_awards (config: &BossConfig, awards: &[awards::Award]) -> Result<(), BossError> {
+    cache (config, AWARDS_JSON, awards)
 }
 
-pub (crate) fn load_teams_data () -> Vec<team::TeamData> {
-    load (TEAMS_JSON)
+pub (crate) fn load_awards (config: &BossConfig) -> Result<Vec<awards::Award>, BossError> {
+    load (config, AWARDS_JSON)
 }
 
+/// Rosters are cached one file per team and season, so a pull only reads and writes the seasons it needs.
+fn rosters_file (team_id: u32, season: u16) -> String {
+    format!("rosters_{}_{}.json", team_id, season)
+}
+
+pub(crate) fn cache_rosters (config: &BossConfig, team_id: u32, season: u16, rosters: &[Cached<rosters::RosterSnapshot>]) -> Result<(), BossError> {
+    cache (config, &rosters_file(team_id, season), rosters)
+}
 
-pub(crate) fn cache_player_data (players: &Vec<players::Player>) {
-    cache (PLAYER_JSON, players.clone());
+pub (crate) fn load_rosters (config: &BossConfig, team_id: u32, season: u16) -> Result<Vec<Cached<rosters::RosterSnapshot>>, BossError> {
+    load (config, &rosters_file(team_id, season))
 }
 
-pub (crate) fn load_player_data () -> Vec<players::Player> {
-    load (PLAYER_JSON)
+pub(crate) fn cache_player_history (config: &BossConfig, history: &[player_history::PlayerAttributes]) -> Result<(), BossError> {
+    cache (config, PLAYER_HISTORY_JSON, history)
 }
 
-pub(crate) fn cache_coach_data (coaches: &Vec<coaches::CoachData>) {
-    cache (COACH_JSON, coaches.clone());
+pub (crate) fn load_player_history (config: &BossConfig) -> Result<Vec<player_history::PlayerAttributes>, BossError> {
+    load (config, PLAYER_HISTORY_JSON)
 }
 
-pub (crate) fn load_coach_data () -> Vec<coaches::CoachData> {
-    load (COACH_JSON)
+pub(crate) fn cache_coach_data (config: &BossConfig, coaches: &[Cached<coaches::CoachData>]) -> Result<(), BossError> {
+    cache (config, COACH_JSON, coaches)
 }
 
-pub(crate) fn cache_boxscore_data (boxscores: &Vec<boxscore::BoxScoreData>) {
-    cache (BOXSCORE_JSON, boxscores.clone());
+pub (crate) fn load_coach_data (config: &BossConfig) -> Result<Vec<Cached<coaches::CoachData>>, BossError> {
+    load (config, COACH_JSON)
 }
 
-pub (crate) fn load_boxscore_data () -> Vec<boxscore::BoxScoreData> {
-    load (BOXSCORE_JSON)
+pub(crate) fn cache_boxscore_data (config: &BossConfig, boxscores: &[Cached<boxscore::BoxScoreData>]) -> Result<(), BossError> {
+    cache (config, BOXSCORE_JSON, boxscores)
+}
+
+pub (crate) fn load_boxscore_data (config: &BossConfig) -> Result<Vec<Cached<boxscore::BoxScoreData>>, BossError> {
+    load (config, BOXSCORE_JSON)
 }
 
 
-pub(crate) fn cache_feed_live_data (games: &Vec<feed_live::FeedData>) {
-    cache (FEED_LIVE_JSON, games.clone());
+pub(crate) fn cache_feed_live_data (config: &BossConfig, games: &[Cached<feed_live::FeedData>]) -> Result<(), BossError> {
+    cache (config, FEED_LIVE_JSON, games)
 }
 
-pub (crate) fn load_feed_live_data () -> Vec<feed_live::FeedData> {
-    load (FEED_LIVE_JSON)
+pub (crate) fn load_feed_live_data (config: &BossConfig) -> Result<Vec<Cached<feed_live::FeedData>>, BossError> {
+    load (config, FEED_LIVE_JSON)
 }
 
 
 ///Serialize the schedule data
-pub (crate) fn cache_schedule (games: &Vec<schedule::GameMetaData>) {  
-    cache (SCHEDULE_JSON, games.clone());
+pub (crate) fn cache_schedule (config: &BossConfig, games: &Vec<schedule::GameMetaData>) -> Result<(), BossError> {  
+    cache (config, SCHEDULE_JSON, games)
 }
 
 ///Load the chedule data
-pub (crate) fn load_schedule () -> Vec<schedule::GameMetaData> {
-    load (SCHEDULE_JSON)
+pub (crate) fn load_schedule (config: &BossConfig) -> Result<Vec<schedule::GameMetaData>, BossError> {
+    load (config, SCHEDULE_JSON)
 } 
 
 /// Serialize the venue (x,y) coordinates 
-pub (crate) fn cache_venue_x_y (venues: &Vec<venues::VenueXY>) {
+pub (crate) fn cache_venue_x_y (config: &BossConfig, venues: &[Cached<venues::VenueXY>]) -> Result<(), BossError> {
 
-    cache (VENUE_X_Y_JSON, venues.clone());
+    cache (config, VENUE_X_Y_JSON, venues)
 
 }
 
 /// Load the venue (x,y) coords from cache
-pub (crate) fn load_venue_x_y () -> Vec<venues::VenueXY> {
+pub (crate) fn load_venue_x_y (config: &BossConfig) -> Result<Vec<Cached<venues::VenueXY>>, BossError> {
 
-    load (VENUE_X_Y_JSON)
+    load (config, VENUE_X_Y_JSON)
 
 }
 
 /// Serialize the venue (x,y) coordinates 
-pub (crate) fn cache_venue (venues: &Vec<venues::VenueData>) {
+pub (crate) fn cache_venue (config: &BossConfig, venues: &[Cached<venues::VenueData>]) -> Result<(), BossError> {
 
-    cache (VENUE_JSON, venues.clone());
+    cache (config, VENUE_JSON, venues)
 
 }
 
 /// Load the venue (x,y) coords from cache
-pub (crate) fn load_venue () -> Vec<venues::VenueData> {
+pub (crate) fn load_venue (config: &BossConfig) -> Result<Vec<Cached<venues::VenueData>>, BossError> {
+
+    load (config, VENUE_JSON)
 
-    load (VENUE_JSON)
+}
+
+pub (crate) fn cache_manifest (config: &BossConfig, games: &[&manifest::GameEntry]) -> Result<(), BossError> {
+    cache (config, MANIFEST_JSON, games)
+}
 
+pub (crate) fn load_manifest (config: &BossConfig) -> Result<Vec<manifest::GameEntry>, BossError> {
+    load (config, MANIFEST_JSON)
 }
 
+/// Loads the "good" games from the good/bad lists we kept before the manifest.
+pub (crate) fn load_games_processed (config: &BossConfig) -> Result<BTreeSet<u32>, BossError> {
 
-/// Creates the cache folder if it doesn't exist.
-fn create_folder (path: String) {
-    match std::fs::create_dir(path) {
-        Ok(dir) => {dir},
-        _ => {},
+    #[derive(Deserialize)]
+    struct GamesProcessed {
+        good: BTreeSet<u32>,
     }
-}
\ No newline at end of file
+
+    let json = match std::fs::read_to_string(config.cache_file(GAMES_PROCESSED_JSON)) {
+        Ok (json) => json,
+        Err (err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err (err) => return Err(err.into()),
+    };
+
+    let games_processed: GamesProcessed = serde_json::from_str(&json)?;
+    Ok(games_processed.good)
+}
+
+/// Summary of one of the cache files, as shown by `boss cache status`.
+#[derive(Debug, Clone)]
+pub struct CacheStatus {
+    pub file_name: &'static str,
+    pub records: usize,
+    pub bytes: u64,
+    /// Oldest and newest `fetched_at` timestamps, for the files that have them. Records from before we kept timestamps aren't counted.
+    pub oldest_fetch: Option<u64>,
+    pub newest_fetch: Option<u64>,
+}
+
+/// Summarizes every cache file we have. Files that haven't been written yet are left out.
+pub fn cache_status (config: &BossConfig) -> Result<Vec<CacheStatus>, BossError> {
+
+    let file_names = [
+        SCHEDULE_JSON, TEAMS_JSON, VENUE_JSON, VENUE_X_Y_JSON, BOXSCORE_JSON, COACH_JSON, PLAYER_JSON, PLAYER_HISTORY_JSON, TRANSACTIONS_JSON, AWARDS_JSON, FEED_LIVE_JSON, MANIFEST_JSON,
+    ];
+
+    let mut status = Vec::new();
+    for file_name in file_names.iter() {
+        let bytes = match std::fs::metadata(config.cache_file(file_name)) {
+            Ok (metadata) => metadata.len(),
+            Err (err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err (err) => return Err(err.into()),
+        };
+
+        let records: Vec<serde_json::Value> = load(config, file_name)?;
+        let fetched: Vec<u64> = records.iter()
+            .filter_map(|record| record.get("fetched_at").and_then(|fetched_at| fetched_at.as_u64()))
+            .filter(|fetched_at| *fetched_at > 0)
+            .collect()
+            ;
+
+        status.push(CacheStatus {
+            file_name,
+            records: records.len(),
+            bytes,
+            oldest_fetch: fetched.iter().min().cloned(),
+            newest_fetch: fetched.iter().max().cloned(),
+        });
+    };
+
+    Ok(status)
+}
+
+/// Creates a folder (and any missing parents) if it doesn't exist.
+pub (crate) fn create_folder (path: &Path) -> Result<(), BossError> {
+    std::fs::create_dir_all(path)?;
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Record {
+        id: u32,
+        value: &'static str,
+    }
+
+    fn cached (id: u32, value: &'static str, days_old: u64) -> Cached<Record> {
+        Cached {fetched_at: now() - days_old * DAY, record: Record {id, value}}
+    }
+
+    fn records () -> Vec<Cached<Record>> {
+        vec![cached(1, "fresh", 0), cached(2, "week old", 7), cached(3, "year old", 365)]
+    }
+
+    fn due_ids (set: &CachedSet<Record>) -> Vec<u32> {
+        let mut ids: Vec<u32> = set.due().map(|record| record.id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn split (overwrite: Overwrite) -> CachedSet<Record> {
+        CachedSet::new(records(), &overwrite, |record| record.id)
+    }
+
+    #[test]
+    fn due_by_overwrite () {
+        assert_eq!(due_ids(&split(Overwrite::Never)), Vec::<u32>::new());
+        assert_eq!(due_ids(&split(Overwrite::Always)), vec![1, 2, 3]);
+        assert_eq!(due_ids(&split(Overwrite::OlderThan(30))), vec![3]);
+        assert_eq!(due_ids(&split(Overwrite::OlderThan(5))), vec![2, 3]);
+        assert_eq!(due_ids(&split(Overwrite::Ids(vec![1, 3, 4].into_iter().collect()))), vec![1, 3]);
+    }
+
+    #[test]
+    fn older_than_is_strict () {
+        let now = now();
+        assert!(!Overwrite::OlderThan(7).is_due(1, now - 7 * DAY, now));
+        assert!(Overwrite::OlderThan(7).is_due(1, now - 7 * DAY - 1, now));
+        // Records from before we kept timestamps are always old enough.
+        assert!(Overwrite::OlderThan(10_000).is_due(1, 0, now));
+        assert!(!Overwrite::OlderThan(0).is_due(1, now, now));
+    }
+
+    #[test]
+    fn merge_replaces_the_records_that_were_pulled () {
+        let before = now();
+        let set = split(Overwrite::OlderThan(5));
+        let merged = set.merge(vec![Record {id: 2, value: "pulled"}, Record {id: 4, value: "new"}], |record| record.id);
+
+        let mut values: Vec<(u32, &str)> = merged.iter().map(|cached| (cached.record.id, cached.record.value)).collect();
+        values.sort_unstable();
+        // 3 was due but didn't come back, so the stale record is kept rather than lost.
+        assert_eq!(values, vec![(1, "fresh"), (2, "pulled"), (3, "year old"), (4, "new")]);
+
+        let fetched = |id: u32| merged.iter().find(|cached| cached.record.id == id).unwrap().fetched_at;
+        assert!(fetched(2) >= before);
+        assert!(fetched(4) >= before);
+        assert!(fetched(3) < before - 300 * DAY);
+    }
+
+    #[test]
+    fn merge_keeps_fresh_records_over_stale_ones () {
+        // Two stale copies of the same key, and one fresh one pulled this time. Only the fresh one survives.
+        let set = CachedSet::new(vec![cached(7, "stale", 40), cached(7, "staler", 60), cached(8, "kept", 1)], &Overwrite::OlderThan(30), |record| record.id);
+        let merged = set.merge(vec![Record {id: 7, value: "fresh"}], |record| record.id);
+
+        let mut values: Vec<(u32, &str)> = merged.iter().map(|cached| (cached.record.id, cached.record.value)).collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![(7, "fresh"), (8, "kept")]);
+    }
+
+    #[test]
+    fn refresh_if_moves_kept_records () {
+        let set = split(Overwrite::Ids(vec![3].into_iter().collect()))
+            .refresh_if(|cached| cached.record.value == "fresh");
+        assert_eq!(due_ids(&set), vec![1, 3]);
+        assert_eq!(set.kept().map(|record| record.id).collect::<Vec<u32>>(), vec![2]);
+
+        let mut all: Vec<u32> = set.into_records().iter().map(|record| record.id).collect();
+        all.sort_unstable();
+        assert_eq!(all, vec![1, 2, 3]);
+    }
+}
@@ -1,19 +1,28 @@
 //! Parse the coach data for a particular day. We pull coach data for each game, even though
 //! 
+//! Besides the manager and the hitting and pitching coaches, which go on every pitch, we keep the whole staff for each game: bench,
+//! base, bullpen, assistant hitting and quality control coaches, and the bullpen catchers. `CoachHistory` answers questions like
+//! "who was Oakland's third base coach on 2019-06-19" from those. Coaches cached before we kept the staff have an empty staff until
+//! they're pulled again.
 
 
 use serde::{Deserialize, Serialize};
+use crate::error::BossError;
+use crate::metadata::MetaData;
+use crate::utils::Date;
+use std::collections::{BTreeMap, HashMap};
 
-pub fn test_coaches() {
+pub fn test_coaches() -> Result<(), BossError> {
 
-    let test = get_coaches(COACHES.to_string());
+    let test = get_coaches(COACHES.to_string())?;
     dbg!(test);
+    Ok(())
 
 }
 
-pub (crate) fn get_coaches (coach_data: String) -> Coaches {
+pub (crate) fn get_coaches (coach_data: String) -> Result<Coaches, BossError> {
     
-    serde_json::from_str(&coach_data).unwrap()
+    Ok(serde_json::from_str(&coach_data)?)
     
 }
 
@@ -22,6 +31,125 @@ pub struct CoachData {
   pub game_pk: u32,
   pub home_coaches: Coaches,
   pub away_coaches: Coaches,
+  /// Both teams' full staffs.
+  #[serde(default)]
+  pub staff: Vec<StaffMember>,
+}
+
+/// One member of a team's staff for a game.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct StaffMember {
+  pub team_id: u32,
+  pub role: CoachRole,
+  pub person_id: u32,
+  pub name: String,
+  pub jersey_number: Option<String>,
+}
+
+/// A staff role, from the roster's job code. Job codes we don't know are kept as `Other` with the code. Reads and writes as the job
+/// code, such as "COA3".
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(from="String", into="String")]
+pub enum CoachRole {
+  Manager,
+  BenchCoach,
+  HittingCoach,
+  AssistantHittingCoach,
+  PitchingCoach,
+  FirstBaseCoach,
+  ThirdBaseCoach,
+  BullpenCoach,
+  QualityControlCoach,
+  BullpenCatcher,
+  Other (String),
+}
+
+impl CoachRole {
+  /// The job code for the role. Roles with more than one code, such as the hitting coach, get the one the majors use.
+  pub fn job_id (&self) -> &str {
+    match self {
+      CoachRole::Manager => "MNGR",
+      CoachRole::BenchCoach => "COAB",
+      CoachRole::HittingCoach => "COAT",
+      CoachRole::AssistantHittingCoach => "COAA",
+      CoachRole::PitchingCoach => "COAP",
+      CoachRole::FirstBaseCoach => "COA1",
+      CoachRole::ThirdBaseCoach => "COA3",
+      CoachRole::BullpenCoach => "COAU",
+      CoachRole::QualityControlCoach => "QUAC",
+      CoachRole::BullpenCatcher => "BCAT",
+      CoachRole::Other (job_id) => job_id,
+    }
+  }
+}
+
+impl From<&str> for CoachRole {
+  fn from (job_id: &str) -> CoachRole {
+    match job_id {
+      "MNGR" | "NTRM" => CoachRole::Manager,
+      "COAB" => CoachRole::BenchCoach,
+      "COAT" | "COAH" | "COHI" => CoachRole::HittingCoach,
+      "COAA" => CoachRole::AssistantHittingCoach,
+      "COAP" | "COPI" => CoachRole::PitchingCoach,
+      "COA1" => CoachRole::FirstBaseCoach,
+      "COA3" => CoachRole::ThirdBaseCoach,
+      "COAU" => CoachRole::BullpenCoach,
+      "QUAC" => CoachRole::QualityControlCoach,
+      "BCAT" => CoachRole::BullpenCatcher,
+      _ => CoachRole::Other(job_id.to_string()),
+    }
+  }
+}
+
+impl From<String> for CoachRole {
+  fn from (job_id: String) -> CoachRole {
+    job_id.as_str().into()
+  }
+}
+
+impl From<CoachRole> for String {
+  fn from (role: CoachRole) -> String {
+    role.job_id().to_string()
+  }
+}
+
+/// Each team's staff over time, built from the games we've pulled.
+#[derive(Debug, Clone, Default)]
+pub struct CoachHistory {
+  teams: HashMap<u32, BTreeMap<Date, Vec<StaffMember>>>,
+}
+
+impl CoachHistory {
+  /// The staff from the metadata's coaches, dated by the schedule. Games that aren't in the schedule are left out.
+  pub fn new (meta_data: &MetaData) -> CoachHistory {
+    let mut teams: HashMap<u32, BTreeMap<Date, Vec<StaffMember>>> = HashMap::new();
+    for coaches in meta_data.coaches.values() {
+      let game_date = match meta_data.schedule.get(&coaches.game_pk) {
+        Some (game) => game.game_date,
+        None => continue,
+      };
+      for member in coaches.staff.iter() {
+        let staff = teams.entry(member.team_id).or_default().entry(game_date).or_default();
+        // Double headers list the same staff twice.
+        if !staff.contains(member) {staff.push(member.clone())};
+      };
+    };
+    CoachHistory {teams}
+  }
+
+  /// The team's staff as of its last game on or before `date`.
+  pub fn staff (&self, team_id: u32, date: Date) -> &[StaffMember] {
+    self.teams.get(&team_id)
+      .and_then(|dates| dates.range(..= date).next_back())
+      .map(|(_, staff)| staff.as_slice())
+      .unwrap_or(&[])
+  }
+
+  /// Who held `role` for the team as of its last game on or before `date`, such as the third base coach. Usually one person, but
+  /// some roles, like bullpen catcher, have more.
+  pub fn coaches (&self, team_id: u32, role: &CoachRole, date: Date) -> Vec<&StaffMember> {
+    self.staff(team_id, date).iter().filter(|member| member.role == *role).collect()
+  }
 }
 
 impl Default for CoachData {
@@ -38,6 +166,7 @@ impl Default for CoachData {
         pitching_coach: None,
         manager: None,
       },
+      staff: vec![],
     }
   }
 }
@@ -52,6 +181,27 @@ pub struct Coaches {
 #[derive(Deserialize, Debug)]
 pub(crate) struct Roster {
     pub (crate) roster: Vec<Coach>,
+    #[serde(rename="teamId")]
+    pub (crate) team_id: Option<u32>,
+}
+
+impl Roster {
+  /// The whole staff. Without the team id we can't say whose staff it is, so we leave it out.
+  pub (crate) fn staff (&self) -> Vec<StaffMember> {
+    let team_id = match self.team_id {
+      Some (team_id) => team_id,
+      None => return vec![],
+    };
+    self.roster.iter()
+      .map(|coach| StaffMember {
+        team_id,
+        role: coach.job_id.as_str().into(),
+        person_id: coach.person.id,
+        name: coach.person.full_name.clone(),
+        jersey_number: coach.jersey_number.clone().filter(|number| !number.is_empty()),
+      })
+      .collect()
+  }
 }
 
 #[derive(Deserialize, Debug)]
@@ -60,6 +210,8 @@ pub(crate) struct Coach {
   pub(crate) job: String,
   #[serde(rename="jobId")]
   pub(crate)job_id: String,
+  #[serde(rename="jerseyNumber")]
+  pub(crate) jersey_number: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -71,30 +223,14 @@ pub(crate) struct Person {
 
 impl From <Roster> for Coaches {
     fn from (roster: Roster) -> Coaches {
-        let batting_coach = roster.roster.iter()
-            .filter (|coach| 
-                coach.job_id.as_str() == "COAT" ||
-                coach.job_id.as_str() == "COAH" ||
-                coach.job_id.as_str() == "COHI"
-            )
-            .map (|coach| coach.person.id)
-            .nth(0);
-        
-        let pitching_coach = roster.roster.iter()
-            .filter (|coach| 
-                coach.job_id.as_str() == "COAP" ||
-                coach.job_id.as_str() == "COPI"
-            )
-            .map (|coach| coach.person.id)
-            .nth(0);
-        
-        let manager = roster.roster.iter()
-            .filter (|coach| 
-                coach.job_id.as_str() == "MNGR" ||
-                coach.job_id.as_str() == "NTRM"
-            )
+        let first = |role: CoachRole| roster.roster.iter()
+            .filter (|coach| CoachRole::from(coach.job_id.as_str()) == role)
             .map (|coach| coach.person.id)
             .nth(0);
+
+        let batting_coach = first(CoachRole::HittingCoach);
+        let pitching_coach = first(CoachRole::PitchingCoach);
+        let manager = first(CoachRole::Manager);
         
 
         Coaches {
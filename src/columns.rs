@@ -0,0 +1,603 @@
+//! Turns flat structs like `Pitch` and `Defense` into typed columns, for the columnar output formats. Rather than keeping a second
+//! list of the ~200 fields (and their types) next to each struct, we run the struct through its own `Serialize` impl: each field
+//! comes out as its name and a typed `Cell`, so the columns always match the CSV output exactly.
+//!
+//! Enums come out as `Cell::Enum` with the variant's name. There are only ever a handful of distinct values in those columns (pitch
+//! types, events, positions...), which is what makes them worth dictionary encoding.
+//!
+//! A `None` doesn't tell us the type of the field, so `schema` takes the type of a column from its first non-null value, and a column
+//! that is null in every row has no type (the writers store those as strings). For types that also implement `Deserialize`, such as
+//! `Pitch`, `schema_of` gets the full schema without any data, by asking the `Deserialize` impl which type it wants for each field.
+//! That's the one to use when several files or batches need to line up.
+//!
+//! Going the other way, `from_row` builds the struct back up from its cells, which is how the pitch store reads pitches back in.
+
+use crate::error::BossError;
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize, Impossible};
+use std::fmt::{Display, Formatter};
+
+/// Storage type of a column.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColumnKind {
+    Bool,
+    /// u8, u16, i8, i16 and i32 fields.
+    Int32,
+    /// u32, u64 and i64 fields. u32 ids don't fit in an i32, so they go here.
+    Int64,
+    Float,
+    Double,
+    Utf8,
+    /// A unit enum variant, stored as the variant's name.
+    Enum,
+}
+
+/// The value of one field in one row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Bool (bool),
+    Int32 (i32),
+    Int64 (i64),
+    Float (f32),
+    Double (f64),
+    Utf8 (String),
+    Enum (&'static str),
+}
+
+impl Cell {
+    pub fn kind (&self) -> Option<ColumnKind> {
+        match self {
+            Cell::Null => None,
+            Cell::Bool (_) => Some(ColumnKind::Bool),
+            Cell::Int32 (_) => Some(ColumnKind::Int32),
+            Cell::Int64 (_) => Some(ColumnKind::Int64),
+            Cell::Float (_) => Some(ColumnKind::Float),
+            Cell::Double (_) => Some(ColumnKind::Double),
+            Cell::Utf8 (_) => Some(ColumnKind::Utf8),
+            Cell::Enum (_) => Some(ColumnKind::Enum),
+        }
+    }
+}
+
+/// Cells serialize as the value they hold, so a row of cells writes out the same as the struct it came from.
+impl Serialize for Cell {
+    fn serialize <S: ser::Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Cell::Null => serializer.serialize_none(),
+            Cell::Bool (value) => serializer.serialize_bool(*value),
+            Cell::Int32 (value) => serializer.serialize_i32(*value),
+            Cell::Int64 (value) => serializer.serialize_i64(*value),
+            Cell::Float (value) => serializer.serialize_f32(*value),
+            Cell::Double (value) => serializer.serialize_f64(*value),
+            Cell::Utf8 (value) => serializer.serialize_str(value),
+            Cell::Enum (value) => serializer.serialize_str(value),
+        }
+    }
+}
+
+/// Splits a struct into (field name, value) pairs, in declaration order.
+pub fn row <T: Serialize> (value: &T) -> Result<Vec<(&'static str, Cell)>, BossError> {
+    value.serialize(RowSerializer).map_err(|err| BossError::UnexpectedValue {field: "column", value: err.0})
+}
+
+/// The name and type of every column for a set of rows. Columns that are null in every row have a kind of `None`.
+pub fn schema <'a, T: Serialize + 'a, I: IntoIterator<Item = &'a T>> (rows: I) -> Result<Vec<(&'static str, Option<ColumnKind>)>, BossError> {
+    let mut columns: Vec<(&'static str, Option<ColumnKind>)> = Vec::new();
+
+    for value in rows {
+        let cells = row(value)?;
+        if columns.is_empty() {
+            columns = cells.iter().map(|(name, _)| (*name, None)).collect();
+        };
+
+        for ((name, cell), column) in cells.iter().zip(columns.iter_mut()) {
+            match (column.1, cell.kind()) {
+                (None, kind) => column.1 = kind,
+                (Some (_), None) => {},
+                (Some (known), Some (kind)) if known == kind => {},
+                (Some (known), Some (kind)) => return Err(BossError::UnexpectedValue {
+                    field: "column",
+                    value: format!("{} is both {:?} and {:?}", name, known, kind),
+                }),
+            };
+        };
+    };
+
+    Ok(columns)
+}
+
+/// The name and type of every column of `T`, worked out from its `Deserialize` impl, so every column has a kind even if it's always
+/// null. Only works for flat structs with derived (or equivalent) impls.
+pub fn schema_of <T: DeserializeOwned> () -> Result<Vec<(&'static str, Option<ColumnKind>)>, BossError> {
+    Ok(probe::<T>()?.into_iter().map(|(name, kind, _)| (name, kind)).collect())
+}
+
+/// The variant names of every enum column of `T`, as its `Deserialize` impl lists them. A variant's position in the list is a
+/// stable code for it, for as long as the enum doesn't change.
+pub fn enum_variants_of <T: DeserializeOwned> () -> Result<Vec<(&'static str, &'static [&'static str])>, BossError> {
+    Ok(probe::<T>()?.into_iter()
+        .filter(|(_, kind, _)| *kind == Some(ColumnKind::Enum))
+        .map(|(name, _, variants)| (name, variants))
+        .collect())
+}
+
+/// Builds a struct back up from (field name, value) pairs, such as the ones from `row`. Fields that aren't there are `None`, if the
+/// struct allows it, and fields the struct doesn't know about are skipped.
+pub fn from_row <T: DeserializeOwned> (row: Vec<(&str, Cell)>) -> Result<T, BossError> {
+    let cells = row.into_iter().map(|(name, cell)| (name, CellDeserializer (cell)));
+    T::deserialize(de::value::MapDeserializer::new(cells))
+        .map_err(|err: ColumnError| BossError::UnexpectedValue {field: "column", value: err.0})
+}
+
+fn probe <T: DeserializeOwned> () -> Result<Vec<ProbedColumn>, BossError> {
+    let mut columns = Vec::new();
+    T::deserialize(SchemaProbe {columns: &mut columns})
+        .map_err(|err| BossError::UnexpectedValue {field: "column", value: err.0})?;
+    Ok(columns)
+}
+
+/// The values of one column, in a typed buffer.
+#[derive(Debug, Clone)]
+pub enum ColumnValues {
+    Bool (Vec<Option<bool>>),
+    Int32 (Vec<Option<i32>>),
+    Int64 (Vec<Option<i64>>),
+    Float (Vec<Option<f32>>),
+    Double (Vec<Option<f64>>),
+    Utf8 (Vec<Option<String>>),
+    Enum (Vec<Option<&'static str>>),
+}
+
+impl ColumnValues {
+    /// An empty column. Columns without a kind are treated as strings.
+    pub fn new (kind: Option<ColumnKind>) -> ColumnValues {
+        match kind {
+            Some (ColumnKind::Bool) => ColumnValues::Bool(Vec::new()),
+            Some (ColumnKind::Int32) => ColumnValues::Int32(Vec::new()),
+            Some (ColumnKind::Int64) => ColumnValues::Int64(Vec::new()),
+            Some (ColumnKind::Float) => ColumnValues::Float(Vec::new()),
+            Some (ColumnKind::Double) => ColumnValues::Double(Vec::new()),
+            Some (ColumnKind::Enum) => ColumnValues::Enum(Vec::new()),
+            Some (ColumnKind::Utf8) | None => ColumnValues::Utf8(Vec::new()),
+        }
+    }
+
+    pub fn len (&self) -> usize {
+        match self {
+            ColumnValues::Bool (values) => values.len(),
+            ColumnValues::Int32 (values) => values.len(),
+            ColumnValues::Int64 (values) => values.len(),
+            ColumnValues::Float (values) => values.len(),
+            ColumnValues::Double (values) => values.len(),
+            ColumnValues::Utf8 (values) => values.len(),
+            ColumnValues::Enum (values) => values.len(),
+        }
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push (&mut self, cell: Cell) -> Result<(), BossError> {
+        match (self, cell) {
+            (ColumnValues::Bool (values), Cell::Null) => values.push(None),
+            (ColumnValues::Int32 (values), Cell::Null) => values.push(None),
+            (ColumnValues::Int64 (values), Cell::Null) => values.push(None),
+            (ColumnValues::Float (values), Cell::Null) => values.push(None),
+            (ColumnValues::Double (values), Cell::Null) => values.push(None),
+            (ColumnValues::Utf8 (values), Cell::Null) => values.push(None),
+            (ColumnValues::Enum (values), Cell::Null) => values.push(None),
+            (ColumnValues::Bool (values), Cell::Bool (value)) => values.push(Some(value)),
+            (ColumnValues::Int32 (values), Cell::Int32 (value)) => values.push(Some(value)),
+            (ColumnValues::Int64 (values), Cell::Int64 (value)) => values.push(Some(value)),
+            (ColumnValues::Float (values), Cell::Float (value)) => values.push(Some(value)),
+            (ColumnValues::Double (values), Cell::Double (value)) => values.push(Some(value)),
+            (ColumnValues::Utf8 (values), Cell::Utf8 (value)) => values.push(Some(value)),
+            (ColumnValues::Enum (values), Cell::Enum (value)) => values.push(Some(value)),
+            (_, cell) => return Err(BossError::UnexpectedValue {field: "column", value: format!("{:?}", cell)}),
+        };
+        Ok(())
+    }
+}
+
+/// Builds typed columns for a set of rows, using a schema from `schema`. The schema can be a subset of the fields, such as a
+/// selection of columns to export; fields that aren't in it are left out.
+pub fn columns <'a, T: Serialize + 'a, I: IntoIterator<Item = &'a T>> (schema: &[(&'static str, Option<ColumnKind>)], rows: I) -> Result<Vec<ColumnValues>, BossError> {
+    let mut columns: Vec<ColumnValues> = schema.iter().map(|(_, kind)| ColumnValues::new(*kind)).collect();
+    // Where each field goes, worked out from the first row. Every row of a struct has the same fields in the same order.
+    let mut positions: Option<Vec<Option<usize>>> = None;
+
+    for value in rows {
+        let cells = row(value)?;
+        let positions = positions.get_or_insert_with(|| cells.iter()
+            .map(|(name, _)| schema.iter().position(|(column, _)| column == name))
+            .collect());
+
+        for ((_, cell), position) in cells.into_iter().zip(positions.iter()) {
+            if let Some (i) = position {columns[*i].push(cell)?};
+        };
+    };
+
+    Ok(columns)
+}
+
+#[derive(Debug)]
+struct ColumnError (String);
+
+impl Display for ColumnError {
+    fn fmt (&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ColumnError {}
+
+impl ser::Error for ColumnError {
+    fn custom <M: Display> (msg: M) -> Self {
+        ColumnError(msg.to_string())
+    }
+}
+
+impl de::Error for ColumnError {
+    fn custom <M: Display> (msg: M) -> Self {
+        ColumnError(msg.to_string())
+    }
+}
+
+fn not_flat (what: &str) -> ColumnError {
+    ColumnError(format!("{} can't be stored in a column, only flat structs are supported", what))
+}
+
+// Accepts a struct and serializes each of its fields with the CellSerializer.
+struct RowSerializer;
+
+struct RowFields {
+    cells: Vec<(&'static str, Cell)>,
+}
+
+impl ser::SerializeStruct for RowFields {
+    type Ok = Vec<(&'static str, Cell)>;
+    type Error = ColumnError;
+
+    fn serialize_field <T: ?Sized + Serialize> (&mut self, key: &'static str, value: &T) -> Result<(), ColumnError> {
+        self.cells.push((key, value.serialize(CellSerializer)?));
+        Ok(())
+    }
+
+    fn end (self) -> Result<Self::Ok, ColumnError> {
+        Ok(self.cells)
+    }
+}
+
+macro_rules! not_a_struct {
+    ($($method:ident ($($arg:ty),*)),*) => {
+        $(fn $method (self, $(_: $arg),*) -> Result<Self::Ok, ColumnError> {
+            Err(not_flat("a value outside of a struct"))
+        })*
+    };
+}
+
+impl ser::Serializer for RowSerializer {
+    type Ok = Vec<(&'static str, Cell)>;
+    type Error = ColumnError;
+    type SerializeSeq = Impossible<Self::Ok, ColumnError>;
+    type SerializeTuple = Impossible<Self::Ok, ColumnError>;
+    type SerializeTupleStruct = Impossible<Self::Ok, ColumnError>;
+    type SerializeTupleVariant = Impossible<Self::Ok, ColumnError>;
+    type SerializeMap = Impossible<Self::Ok, ColumnError>;
+    type SerializeStruct = RowFields;
+    type SerializeStructVariant = Impossible<Self::Ok, ColumnError>;
+
+    fn serialize_struct (self, _name: &'static str, len: usize) -> Result<RowFields, ColumnError> {
+        Ok(RowFields {cells: Vec::with_capacity(len)})
+    }
+
+    not_a_struct!(
+        serialize_bool (bool), serialize_i8 (i8), serialize_i16 (i16), serialize_i32 (i32), serialize_i64 (i64),
+        serialize_u8 (u8), serialize_u16 (u16), serialize_u32 (u32), serialize_u64 (u64), serialize_f32 (f32), serialize_f64 (f64),
+        serialize_char (char), serialize_str (&str), serialize_bytes (&[u8]), serialize_none (), serialize_unit (),
+        serialize_unit_struct (&'static str), serialize_unit_variant (&'static str, u32, &'static str)
+    );
+
+    fn serialize_some <T: ?Sized + Serialize> (self, _value: &T) -> Result<Self::Ok, ColumnError> {
+        Err(not_flat("an optional row"))
+    }
+
+    fn serialize_newtype_struct <T: ?Sized + Serialize> (self, _name: &'static str, value: &T) -> Result<Self::Ok, ColumnError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant <T: ?Sized + Serialize> (self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<Self::Ok, ColumnError> {
+        Err(not_flat("an enum"))
+    }
+
+    fn serialize_seq (self, _len: Option<usize>) -> Result<Self::SerializeSeq, ColumnError> {
+        Err(not_flat("a list"))
+    }
+
+    fn serialize_tuple (self, _len: usize) -> Result<Self::SerializeTuple, ColumnError> {
+        Err(not_flat("a tuple"))
+    }
+
+    fn serialize_tuple_struct (self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, ColumnError> {
+        Err(not_flat("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant (self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, ColumnError> {
+        Err(not_flat("an enum"))
+    }
+
+    fn serialize_map (self, _len: Option<usize>) -> Result<Self::SerializeMap, ColumnError> {
+        Err(not_flat("a map"))
+    }
+
+    fn serialize_struct_variant (self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, ColumnError> {
+        Err(not_flat("an enum"))
+    }
+}
+
+// Turns a single field value into a Cell.
+struct CellSerializer;
+
+impl ser::Serializer for CellSerializer {
+    type Ok = Cell;
+    type Error = ColumnError;
+    type SerializeSeq = Impossible<Cell, ColumnError>;
+    type SerializeTuple = Impossible<Cell, ColumnError>;
+    type SerializeTupleStruct = Impossible<Cell, ColumnError>;
+    type SerializeTupleVariant = Impossible<Cell, ColumnError>;
+    type SerializeMap = Impossible<Cell, ColumnError>;
+    type SerializeStruct = Impossible<Cell, ColumnError>;
+    type SerializeStructVariant = Impossible<Cell, ColumnError>;
+
+    fn serialize_bool (self, v: bool) -> Result<Cell, ColumnError> {Ok(Cell::Bool(v))}
+    fn serialize_i8 (self, v: i8) -> Result<Cell, ColumnError> {Ok(Cell::Int32(v as i32))}
+    fn serialize_i16 (self, v: i16) -> Result<Cell, ColumnError> {Ok(Cell::Int32(v as i32))}
+    fn serialize_i32 (self, v: i32) -> Result<Cell, ColumnError> {Ok(Cell::Int32(v))}
+    fn serialize_i64 (self, v: i64) -> Result<Cell, ColumnError> {Ok(Cell::Int64(v))}
+    fn serialize_u8 (self, v: u8) -> Result<Cell, ColumnError> {Ok(Cell::Int32(v as i32))}
+    fn serialize_u16 (self, v: u16) -> Result<Cell, ColumnError> {Ok(Cell::Int32(v as i32))}
+    fn serialize_u32 (self, v: u32) -> Result<Cell, ColumnError> {Ok(Cell::Int64(v as i64))}
+    fn serialize_u64 (self, v: u64) -> Result<Cell, ColumnError> {Ok(Cell::Int64(v as i64))}
+    fn serialize_f32 (self, v: f32) -> Result<Cell, ColumnError> {Ok(Cell::Float(v))}
+    fn serialize_f64 (self, v: f64) -> Result<Cell, ColumnError> {Ok(Cell::Double(v))}
+    fn serialize_char (self, v: char) -> Result<Cell, ColumnError> {Ok(Cell::Utf8(v.to_string()))}
+    fn serialize_str (self, v: &str) -> Result<Cell, ColumnError> {Ok(Cell::Utf8(v.to_string()))}
+    fn serialize_none (self) -> Result<Cell, ColumnError> {Ok(Cell::Null)}
+    fn serialize_unit (self) -> Result<Cell, ColumnError> {Ok(Cell::Null)}
+    fn serialize_unit_struct (self, _name: &'static str) -> Result<Cell, ColumnError> {Ok(Cell::Null)}
+
+    fn serialize_unit_variant (self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Cell, ColumnError> {
+        Ok(Cell::Enum(variant))
+    }
+
+    fn serialize_some <T: ?Sized + Serialize> (self, value: &T) -> Result<Cell, ColumnError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct <T: ?Sized + Serialize> (self, _name: &'static str, value: &T) -> Result<Cell, ColumnError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bytes (self, _v: &[u8]) -> Result<Cell, ColumnError> {
+        Err(not_flat("a byte array"))
+    }
+
+    fn serialize_newtype_variant <T: ?Sized + Serialize> (self, _name: &'static str, _index: u32, variant: &'static str, _value: &T) -> Result<Cell, ColumnError> {
+        Err(not_flat(variant))
+    }
+
+    fn serialize_seq (self, _len: Option<usize>) -> Result<Self::SerializeSeq, ColumnError> {
+        Err(not_flat("a list"))
+    }
+
+    fn serialize_tuple (self, _len: usize) -> Result<Self::SerializeTuple, ColumnError> {
+        Err(not_flat("a tuple"))
+    }
+
+    fn serialize_tuple_struct (self, name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, ColumnError> {
+        Err(not_flat(name))
+    }
+
+    fn serialize_tuple_variant (self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, ColumnError> {
+        Err(not_flat(variant))
+    }
+
+    fn serialize_map (self, _len: Option<usize>) -> Result<Self::SerializeMap, ColumnError> {
+        Err(not_flat("a map"))
+    }
+
+    fn serialize_struct (self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct, ColumnError> {
+        Err(not_flat(name))
+    }
+
+    fn serialize_struct_variant (self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, ColumnError> {
+        Err(not_flat(variant))
+    }
+}
+
+// Deserializes a struct from nothing, noting the type each field asks for. Every field gets a dummy value (0, "", the first variant)
+// since all we're after is the list of types.
+// Name, kind and (for enums) variants of a field.
+type ProbedColumn = (&'static str, Option<ColumnKind>, &'static [&'static str]);
+
+struct SchemaProbe <'a> {
+    columns: &'a mut Vec<ProbedColumn>,
+}
+
+impl <'de, 'a> de::Deserializer<'de> for SchemaProbe<'a> {
+    type Error = ColumnError;
+
+    fn deserialize_struct <V: Visitor<'de>> (self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, ColumnError> {
+        visitor.visit_map(FieldProbe {fields, next: 0, columns: self.columns})
+    }
+
+    fn deserialize_any <V: Visitor<'de>> (self, _visitor: V) -> Result<V::Value, ColumnError> {
+        Err(not_flat("a value outside of a struct"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit unit_struct newtype_struct seq
+        tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct FieldProbe <'a> {
+    fields: &'static [&'static str],
+    next: usize,
+    columns: &'a mut Vec<ProbedColumn>,
+}
+
+impl <'de, 'a> de::MapAccess<'de> for FieldProbe<'a> {
+    type Error = ColumnError;
+
+    fn next_key_seed <K: de::DeserializeSeed<'de>> (&mut self, seed: K) -> Result<Option<K::Value>, ColumnError> {
+        match self.fields.get(self.next) {
+            Some (field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed <V: de::DeserializeSeed<'de>> (&mut self, seed: V) -> Result<V::Value, ColumnError> {
+        let (mut kind, mut variants) = (None, &[][..]);
+        let value = seed.deserialize(KindProbe {kind: &mut kind, variants: &mut variants})?;
+        self.columns.push((self.fields[self.next], kind, variants));
+        self.next += 1;
+        Ok(value)
+    }
+}
+
+struct KindProbe <'a> {
+    kind: &'a mut Option<ColumnKind>,
+    variants: &'a mut &'static [&'static str],
+}
+
+macro_rules! probe {
+    ($($method:ident => $kind:ident, $visit:ident ($value:expr)),*) => {
+        $(fn $method <V: Visitor<'de>> (self, visitor: V) -> Result<V::Value, ColumnError> {
+            *self.kind = Some(ColumnKind::$kind);
+            visitor.$visit($value)
+        })*
+    };
+}
+
+impl <'de, 'a> de::Deserializer<'de> for KindProbe<'a> {
+    type Error = ColumnError;
+
+    probe!(
+        deserialize_bool => Bool, visit_bool (false),
+        deserialize_i8 => Int32, visit_i8 (0), deserialize_i16 => Int32, visit_i16 (0), deserialize_i32 => Int32, visit_i32 (0),
+        deserialize_u8 => Int32, visit_u8 (0), deserialize_u16 => Int32, visit_u16 (0),
+        deserialize_i64 => Int64, visit_i64 (0), deserialize_u32 => Int64, visit_u32 (0), deserialize_u64 => Int64, visit_u64 (0),
+        deserialize_f32 => Float, visit_f32 (0.0), deserialize_f64 => Double, visit_f64 (0.0),
+        deserialize_char => Utf8, visit_char (' '), deserialize_str => Utf8, visit_str (""), deserialize_string => Utf8, visit_string (String::new())
+    );
+
+    fn deserialize_option <V: Visitor<'de>> (self, visitor: V) -> Result<V::Value, ColumnError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct <V: Visitor<'de>> (self, _name: &'static str, visitor: V) -> Result<V::Value, ColumnError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum <V: Visitor<'de>> (self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, ColumnError> {
+        *self.kind = Some(ColumnKind::Enum);
+        *self.variants = variants;
+        let variant = variants.first().ok_or_else(|| not_flat(name))?;
+        visitor.visit_enum(UnitVariant (variant))
+    }
+
+    fn deserialize_any <V: Visitor<'de>> (self, _visitor: V) -> Result<V::Value, ColumnError> {
+        Err(not_flat("a field without a fixed type"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct UnitVariant (&'static str);
+
+impl <'de> de::EnumAccess<'de> for UnitVariant {
+    type Error = ColumnError;
+    type Variant = UnitVariant;
+
+    fn variant_seed <V: de::DeserializeSeed<'de>> (self, seed: V) -> Result<(V::Value, UnitVariant), ColumnError> {
+        let variant = seed.deserialize(self.0.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl <'de> de::VariantAccess<'de> for UnitVariant {
+    type Error = ColumnError;
+
+    fn unit_variant (self) -> Result<(), ColumnError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed <T: de::DeserializeSeed<'de>> (self, _seed: T) -> Result<T::Value, ColumnError> {
+        Err(not_flat(self.0))
+    }
+
+    fn tuple_variant <V: Visitor<'de>> (self, _len: usize, _visitor: V) -> Result<V::Value, ColumnError> {
+        Err(not_flat(self.0))
+    }
+
+    fn struct_variant <V: Visitor<'de>> (self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, ColumnError> {
+        Err(not_flat(self.0))
+    }
+}
+
+// Hands a cell to a `Deserialize` impl, for `from_row`.
+struct CellDeserializer (Cell);
+
+impl <'de> IntoDeserializer<'de, ColumnError> for CellDeserializer {
+    type Deserializer = CellDeserializer;
+
+    fn into_deserializer (self) -> CellDeserializer {
+        self
+    }
+}
+
+impl <'de> de::Deserializer<'de> for CellDeserializer {
+    type Error = ColumnError;
+
+    fn deserialize_any <V: Visitor<'de>> (self, visitor: V) -> Result<V::Value, ColumnError> {
+        match self.0 {
+            Cell::Null => visitor.visit_none(),
+            Cell::Bool (value) => visitor.visit_bool(value),
+            Cell::Int32 (value) => visitor.visit_i32(value),
+            Cell::Int64 (value) => visitor.visit_i64(value),
+            Cell::Float (value) => visitor.visit_f32(value),
+            Cell::Double (value) => visitor.visit_f64(value),
+            Cell::Utf8 (value) => visitor.visit_string(value),
+            Cell::Enum (value) => visitor.visit_str(value),
+        }
+    }
+
+    fn deserialize_option <V: Visitor<'de>> (self, visitor: V) -> Result<V::Value, ColumnError> {
+        match self.0 {
+            Cell::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum <V: Visitor<'de>> (self, name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, ColumnError> {
+        match self.0 {
+            Cell::Enum (variant) => visitor.visit_enum(variant.into_deserializer()),
+            Cell::Utf8 (variant) => visitor.visit_enum(variant.into_deserializer()),
+            cell => Err(ColumnError(format!("{:?} isn't a {}", cell, name))),
+        }
+    }
+
+    fn deserialize_newtype_struct <V: Visitor<'de>> (self, _name: &'static str, visitor: V) -> Result<V::Value, ColumnError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
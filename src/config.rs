@@ -0,0 +1,79 @@
+//! Configuration for where BOSS reads and writes its data, and when cached data gets pulled again.
+//!
+//! Everything BOSS persists lives under the data root: the cache folder (JSON metadata such as the schedule, boxscores
+//! and players), the output folder (the pitch by pitch and defense exports) and the archive folder (raw API responses, see the
+//! `archive` module). By default the data root is the current working directory, with the other folders nested inside it. All paths are built with `Path::join`, so the same configuration
+//! works on Windows, Linux and macOS.
+
+use crate::cache::Overwrite;
+use crate::error::Dataset;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Folder locations used by the caching and export functions. Build one with `BossConfig::new(data_root)` to nest the cache and
+/// output folders under your own data root, or set any of the fields directly if you want them to live somewhere else.
+#[derive(Debug, Clone)]
+pub struct BossConfig {
+    pub data_root: PathBuf,
+    pub cache_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub archive_dir: PathBuf,
+    /// Refresh policy for each cached data set. Data sets that aren't in the map are never refreshed.
+    pub refresh: BTreeMap<Dataset, Overwrite>,
+    /// A local mirror of the Gameday XML files (gd2.mlb.com/components/game), used to fill in umpires and game day weights. See the
+    /// `gameday` module.
+    pub gameday_mirror: Option<PathBuf>,
+}
+
+impl BossConfig {
+    /// Uses `data_root` as the base folder, with the cache in `data_root/cache`, the exports in `data_root/output` and the raw
+    /// responses in `data_root/archive`.
+    pub fn new <P: Into<PathBuf>> (data_root: P) -> BossConfig {
+        let data_root: PathBuf = data_root.into();
+        BossConfig {
+            cache_dir: data_root.join("cache"),
+            output_dir: data_root.join("output"),
+            archive_dir: data_root.join("archive"),
+            data_root,
+            refresh: BTreeMap::new(),
+            gameday_mirror: None,
+        }
+    }
+
+    /// Sets the refresh policy for a data set, e.g. `.refresh(Dataset::Players, Overwrite::OlderThan(30))` to pick up
+    /// player weight changes once a month.
+    pub fn refresh (mut self, dataset: Dataset, overwrite: Overwrite) -> BossConfig {
+        self.refresh.insert(dataset, overwrite);
+        self
+    }
+
+    /// Reads the Gameday XML files from a local mirror when pulling the metadata.
+    pub fn gameday_mirror <P: Into<PathBuf>> (mut self, mirror: P) -> BossConfig {
+        self.gameday_mirror = Some (mirror.into());
+        self
+    }
+
+    /// The refresh policy for a data set.
+    pub fn overwrite (&self, dataset: Dataset) -> &Overwrite {
+        self.refresh.get(&dataset).unwrap_or(&Overwrite::Never)
+    }
+
+    /// Full path for a file stored in the cache folder.
+    pub fn cache_file (&self, file_name: &str) -> PathBuf {
+        self.cache_dir.join(file_name)
+    }
+
+    /// Full path for a file stored in the output folder.
+    pub fn output_file (&self, file_name: &str) -> PathBuf {
+        self.output_dir.join(file_name)
+    }
+}
+
+/// Defaults to the current working directory as the data root. If we can't read the current directory, we fall back to
+/// a relative path, which resolves to the same place.
+impl Default for BossConfig {
+    fn default() -> Self {
+        let data_root = std::env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf());
+        BossConfig::new(data_root)
+    }
+}
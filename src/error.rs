@@ -1,17 +1,21 @@
 //! Error handling module for BOSS
-//! 
-//! There are a lot of things that can go wrong when we try to parse and build out a baseball game into data. 
-//! 
+//!
+//! There are a lot of things that can go wrong when we try to parse and build out a baseball game into data.
+//!
 //! Errors can be grouped as follows:
-//! 
+//!
 //! **Source Data Issues:**\
 //!     * **Extra Records / Garbage Records:** Certain source data are clearly junk as they produce counts (balls and strikes) that are clearly wrong.
 //!     * **Missing Metadata:** Some games do not have any boxscore data, or are missing other metadata such as coaches, venue etc.
-//!     * 
-//! 
-//! 
-//! 
-//! 
+//!     * **Unexpected Values:** The API occasionally sends a value we haven't mapped to one of our enums, or a date/id in a format we don't expect.
+//!
+//! **Pipeline Issues:**\
+//!     * **Network:** Timeouts and dropped connections. These are usually transient and the item can be retried on the next pull.
+//!     * **IO/CSV:** Problems reading or writing the cache and the exported files.
+//!
+//! A multi-hour pull should never be stopped by one bad game. Functions that work through a list of items (games, players, venues...)
+//! return the items that worked, along with a `Failure` for every item that didn't. Only errors that affect the whole pull, such as
+//! not being able to write the cache, are returned as an `Err`.
 
 use std::fmt::{Display, Formatter, Result};
 use std::error::Error;
@@ -19,34 +23,82 @@ use std::error::Error;
 #[derive(Debug)]
 pub enum BossError {
     Network (isahc::Error),
+    /// The server answered, but with an error status we can't use, such as a 503 when it's overloaded.
+    HttpStatus {url: String, status: u16},
+    /// We're reading only from the archive and it doesn't have a response for this url.
+    NotArchived {url: String},
     JSONParse (serde_json::Error),
     IO (std::io::Error),
+    CSV (csv::Error),
+    Regex (regex::Error),
+    #[cfg(feature = "parquet")]
+    Parquet (parquet::errors::ParquetError),
+    #[cfg(feature = "arrow")]
+    Arrow (arrow::error::ArrowError),
+    #[cfg(feature = "sqlite")]
+    Sqlite (rusqlite::Error),
+    /// We don't have the metadata needed to process a game, such as its boxscore or the bio of one of its players.
+    MissingMetaData {game_pk: u32, meta_data: MetaDataType},
+    /// A field we need was null or missing in the source data. The id is the game_pk, player id etc. that the field belongs to.
+    MissingField {id: u32, field: &'static str},
+    /// A value that we couldn't map to one of our enums or parse into the type we expected.
+    UnexpectedValue {field: &'static str, value: String},
+    /// The request can't be run as specified, such as a `Boss` query with no years to pull.
+    InvalidQuery (&'static str),
+    /// An item that failed partway through a pull, along with the data set and key it belongs to. This is how the streams report
+    /// the items `fetch` returns as failures.
+    Failed (Box<Failure>),
+}
 
+/// The piece of metadata that was missing when we tried to build out a game.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MetaDataType {
+    Schedule,
+    BoxScore,
+    Player (u32),
 }
 
 impl Display for BossError {
     fn fmt (&self, f: &mut Formatter) -> Result {
         match *self {
             BossError::Network (ref err) => write!(f, "Network Error: {}", err),
-            BossError::IO (ref err) => write!(f, "Network Error: {}", err),
+            BossError::HttpStatus {ref url, status} => write!(f, "HTTP Status {} for: {}", status, url),
+            BossError::NotArchived {ref url} => write!(f, "Not in the archive: {}", url),
+            BossError::IO (ref err) => write!(f, "IO Error: {}", err),
             BossError::JSONParse (ref err) => write!(f, "JSON Parse Error: {}", err),
+            BossError::CSV (ref err) => write!(f, "CSV Error: {}", err),
+            BossError::Regex (ref err) => write!(f, "Regex Error: {}", err),
+            #[cfg(feature = "parquet")]
+            BossError::Parquet (ref err) => write!(f, "Parquet Error: {}", err),
+            #[cfg(feature = "arrow")]
+            BossError::Arrow (ref err) => write!(f, "Arrow Error: {}", err),
+            #[cfg(feature = "sqlite")]
+            BossError::Sqlite (ref err) => write!(f, "SQLite Error: {}", err),
+            BossError::MissingMetaData {game_pk, meta_data} => write!(f, "Missing {:?} metadata for game_pk: {}", meta_data, game_pk),
+            BossError::MissingField {id, field} => write!(f, "Missing field \"{}\" for id: {}", field, id),
+            BossError::UnexpectedValue {field, ref value} => write!(f, "Unexpected value for \"{}\": {}", field, value),
+            BossError::InvalidQuery (reason) => write!(f, "Invalid query: {}", reason),
+            BossError::Failed (ref failure) => write!(f, "{}", failure),
         }
     }
 }
 
 impl Error for BossError {
-    fn description(&self) -> &str {
-        match *self {
-            BossError::Network(ref err) => err.description(),
-            BossError::IO(ref err) => err.description(),
-            BossError::JSONParse(ref err) => err.description(),
-        }
-    }
     fn source (&self) -> Option<&(dyn Error +'static)> {
         match *self {
             BossError::Network(ref err) => Some(err),
             BossError::IO(ref err) => Some(err),
             BossError::JSONParse(ref err) => Some(err),
+            BossError::CSV(ref err) => Some(err),
+            BossError::Regex(ref err) => Some(err),
+            #[cfg(feature = "parquet")]
+            BossError::Parquet(ref err) => Some(err),
+            #[cfg(feature = "arrow")]
+            BossError::Arrow(ref err) => Some(err),
+            #[cfg(feature = "sqlite")]
+            BossError::Sqlite(ref err) => Some(err),
+            BossError::Failed(ref failure) => Some(&failure.error),
+            _ => None,
         }
     }
 }
@@ -67,4 +119,87 @@ impl From<std::io::Error> for BossError {
     fn from (err: std::io::Error ) -> BossError {
         BossError::IO(err)
     }
-}
\ No newline at end of file
+}
+
+impl From<csv::Error> for BossError {
+    fn from (err: csv::Error ) -> BossError {
+        BossError::CSV(err)
+    }
+}
+
+impl From<regex::Error> for BossError {
+    fn from (err: regex::Error ) -> BossError {
+        BossError::Regex(err)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for BossError {
+    fn from (err: parquet::errors::ParquetError ) -> BossError {
+        BossError::Parquet(err)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<arrow::error::ArrowError> for BossError {
+    fn from (err: arrow::error::ArrowError ) -> BossError {
+        BossError::Arrow(err)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for BossError {
+    fn from (err: rusqlite::Error ) -> BossError {
+        BossError::Sqlite(err)
+    }
+}
+
+/// The data set an item belongs to. Used to report which part of a pull a `Failure` came from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Dataset {
+    Schedule,
+    Teams,
+    BoxScore,
+    Coaches,
+    Players,
+    FeedLive,
+    Venues,
+    VenueXY,
+    PlayByPlay,
+    /// Games read from a local mirror of the Gameday XML files, keyed by the game folder.
+    Gameday,
+    /// Games read from Retrosheet event files, keyed by the Retrosheet game id.
+    Retrosheet,
+    /// Team rosters, keyed by the team id, date and roster type, such as "111/2019-6-1/40Man".
+    Rosters,
+}
+
+/// One item that couldn't be pulled or parsed. The key identifies the item within its data set, such as the game_pk for a boxscore,
+/// the player id for a player or "2019/11" for the 2019 Triple-A schedule.
+#[derive(Debug)]
+pub struct Failure {
+    pub dataset: Dataset,
+    pub key: String,
+    pub error: BossError,
+}
+
+impl Display for Failure {
+    fn fmt (&self, f: &mut Formatter) -> Result {
+        write!(f, "{:?} {}: {}", self.dataset, self.key, self.error)
+    }
+}
+
+/// Splits a batch of per-item results into the items that worked and the ones that failed.
+pub (crate) fn split_failures <T> (results: Vec<std::result::Result<T, Failure>>) -> (Vec<T>, Vec<Failure>) {
+    let mut items = Vec::with_capacity(results.len());
+    let mut failures = Vec::new();
+
+    for result in results {
+        match result {
+            Ok (item) => items.push(item),
+            Err (failure) => failures.push(failure),
+        }
+    };
+
+    (items, failures)
+}
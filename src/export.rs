@@ -0,0 +1,122 @@
+//! Writing pitches out to a file. Pulling data and writing it are separate steps: `Boss::fetch` hands back the pitches, and what
+//! ends up on disk, and in which format, is decided here. Formats other than CSV are behind a feature of the same name, such as
+//! `parquet`.
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::export::{export_pitches, ExportFormat};
+//!
+//! let data = Boss::new().years(2019 ..= 2019).sports(vec![1]).fetch()?;
+//! export_pitches(&data.pitches, ExportFormat::Csv, "baseball.csv")?;
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! `export_pitch_columns` writes only some of the columns, see the `pitch_fields` module.
+
+use crate::columns::{self, Cell};
+use crate::defense::Defense;
+use crate::error::BossError;
+use crate::game::Pitch;
+use crate::pitch_fields::PitchColumns;
+use csv::WriterBuilder;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// One row per pitch with a header row, same layout as the `baseball.csv` output.
+    Csv,
+    /// Columnar, compressed and partitioned by season and sport_id. See the `parquet_writer` module.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = BossError;
+
+    fn from_str (name: &str) -> Result<ExportFormat, BossError> {
+        match name.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => Err(BossError::UnexpectedValue {field: "export format", value: name.to_string()}),
+        }
+    }
+}
+
+/// Writes the pitches to `path` in the format asked for, replacing the file if it's already there. Missing parent folders are
+/// created.
+pub fn export_pitches <P: AsRef<Path>> (pitches: &[Pitch], format: ExportFormat, path: P) -> Result<(), BossError> {
+    let path = path.as_ref();
+    create_parent(path)?;
+
+    match format {
+        ExportFormat::Csv => write_csv(pitches, path),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => crate::parquet_writer::write_pitches_parquet(pitches, path),
+    }
+}
+
+/// Writes only the selected columns of the pitches to `path`, in the format asked for. Otherwise the same as `export_pitches`.
+pub fn export_pitch_columns <P: AsRef<Path>> (pitches: &[Pitch], format: ExportFormat, columns: &PitchColumns, path: P) -> Result<(), BossError> {
+    let path = path.as_ref();
+    let names = columns.names()?;
+    create_parent(path)?;
+
+    match format {
+        ExportFormat::Csv => write_csv_columns(pitches, &names, path),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => crate::parquet_writer::write_pitch_columns_parquet(pitches, &names, path),
+    }
+}
+
+/// Writes the defense data to `path` in the format asked for, same as `export_pitches`.
+pub fn export_defense <P: AsRef<Path>> (defense: &[Defense], format: ExportFormat, path: P) -> Result<(), BossError> {
+    let path = path.as_ref();
+    create_parent(path)?;
+
+    match format {
+        ExportFormat::Csv => write_csv(defense, path),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => crate::parquet_writer::write_defense_parquet(defense, path),
+    }
+}
+
+fn create_parent (path: &Path) -> Result<(), BossError> {
+    if let Some (parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {std::fs::create_dir_all(parent)?};
+    };
+    Ok(())
+}
+
+fn write_csv <T: Serialize> (rows: &[T], path: &Path) -> Result<(), BossError> {
+    let mut csv_writer = WriterBuilder::new()
+        .has_headers(true)
+        .from_path(path)?;
+
+    for row in rows {
+        csv_writer.serialize(row)?;
+    };
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+// Cells serialize the same way as the fields they came from, so the values match the full CSV export.
+fn write_csv_columns <T: Serialize> (rows: &[T], names: &[&str], path: &Path) -> Result<(), BossError> {
+    let mut csv_writer = WriterBuilder::new()
+        .has_headers(false)
+        .from_path(path)?;
+    csv_writer.write_record(names)?;
+
+    for row in rows {
+        let cells: Vec<Cell> = columns::row(row)?.into_iter()
+            .filter(|(name, _)| names.contains(name))
+            .map(|(_, cell)| cell)
+            .collect();
+        csv_writer.serialize(cells)?;
+    };
+
+    csv_writer.flush()?;
+    Ok(())
+}
@@ -6,6 +6,8 @@
 //! 
 
 use serde::{Deserialize, Serialize};
+use crate::error::BossError;
+use std::convert::TryFrom;
 
 
 /// Contains the link for the GameDay xml folder as well as the official scorers.
@@ -19,20 +21,26 @@ pub struct FeedData {
     pub primary_datacaster_name: Option<String>,
 }
 
-impl From<Feed> for FeedData {
-    fn from(feed: Feed) -> FeedData {
+/// The game id looks like "2019/06/19/detmlb-nyamlb-1". Anything that doesn't follow that pattern can't be turned into a gameday folder.
+impl TryFrom<Feed> for FeedData {
+    type Error = BossError;
+
+    fn try_from(feed: Feed) -> Result<FeedData, BossError> {
         let base_url = "https://gd2.mlb.com/components/game/";
-        
-        let mut sport_code = feed.game_data.game.id
-            .clone()
+        let unexpected = || BossError::UnexpectedValue {field: "game.id", value: feed.game_data.game.id.clone()};
+
+        let year_month_day: Vec<&str> = feed.game_data.game.id.split("/").collect();
+        if year_month_day.len() < 4 {return Err(unexpected())};
+        let (year, month, day) = (year_month_day[0], year_month_day[1], year_month_day[2]);
+
+        let mut sport_code = year_month_day[3]
             .split("-")
-            .nth(0).unwrap()
+            .nth(0).unwrap_or("")
             .to_string()
             ;
+        if sport_code.len() < 3 {return Err(unexpected())};
         let sport_code: String = sport_code.drain(sport_code.len()-3 .. ).collect();
         let game_id = feed.game_data.game.id.clone().replace("/", "_").replace("-", "_");
-        let year_month_day: Vec<&str> = feed.game_data.game.id.split("/").collect();
-        let (year, month, day) = (year_month_day[0], year_month_day[1], year_month_day[2]);
 
         let folder = format! ("{}{}/year_{}/month_{}/day_{}/gid_{}/", base_url, sport_code, year, month, day, game_id);
 
@@ -45,14 +53,14 @@ impl From<Feed> for FeedData {
           _ => (None, None),
         };
 
-        FeedData {
+        Ok(FeedData {
             game_pk: feed.game_data.game.pk,
             gameday_xml_folder: folder,
             official_scorer_id,
             official_scorer_name,
             primary_datacaster_id,
             primary_datacaster_name,
-        }
+        })
     }
 }
 
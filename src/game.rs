@@ -12,10 +12,13 @@ use crate::venues::{SurfaceType, RoofType, TimeZone, VenueData, VenueXY, Venue};
 use crate::coaches::CoachData;
 use crate::feed_live::FeedData;
 use crate::team::{TeamData, Team};
+use crate::transactions::TransactionKind;
 use crate::metadata::MetaData;
 use crate::utils::Date;
+use crate::error::{BossError, MetaDataType};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
 
 pub (crate) struct GameData <'m> {
@@ -28,7 +31,7 @@ pub (crate) struct GameData <'m> {
 /// Pitch is the final serializable struct that we'll export from this module. It will flatten all the at-bat level
 /// data for easy use. This is intentionally de-normalized for ease of use. 
 /// For ease of sorting, all counts (inning, outs balls, strikes, pitches etc. will start wit num_).
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Pitch {
   
     //at_bat level meta-data
@@ -65,6 +68,10 @@ pub struct Pitch {
     pub hp_umpire_age: Option<f32>,
     pub hp_umpire_height: Option<u8>,
     pub hp_umpire_height_str: Option<String>,
+    pub first_base_umpire_id: Option<u32>,
+    pub second_base_umpire_id: Option<u32>,
+    pub third_base_umpire_id: Option<u32>,
+    pub crew_chief_umpire_id: Option<u32>,
 
     pub batting_coach: Option<u32>,
     pub batting_coach_name: Option<String>,
@@ -116,6 +123,9 @@ pub struct Pitch {
     pub pitcher_highschool_city: Option<String>,
     pub pitcher_highschool_prov_state: Option<String>,
     pub pitcher_college_name: Option<String>,
+    pub pitcher_days_since_il_return: Option<u16>,
+    pub pitcher_days_since_trade: Option<u16>,
+    pub pitcher_all_star_selections: u16,
 
     pub batter: u32,
     pub batter_name: String,
@@ -140,6 +150,9 @@ pub struct Pitch {
     pub batter_highschool_city: Option<String>,
     pub batter_highschool_prov_state: Option<String>,
     pub batter_college_name: Option<String>,
+    pub batter_days_since_il_return: Option<u16>,
+    pub batter_days_since_trade: Option<u16>,
+    pub batter_all_star_selections: u16,
 
 
     pub batter_bats: SideCode,
@@ -239,6 +252,14 @@ pub struct Pitch {
     //distance calculated from spray chart
     pub hit_data_calc_distance: Option<f32>,
 
+    // Statcast search data, only filled in when merged from Baseball Savant CSVs (see the `statcast` module)
+    pub statcast_spin_axis: Option<f32>,
+    pub statcast_hit_distance: Option<f32>,
+    pub statcast_launch_speed_angle: Option<u8>,
+    pub statcast_xba: Option<f32>,
+    pub statcast_xwoba: Option<f32>,
+    pub statcast_woba_value: Option<f32>,
+
 
 
 
@@ -379,10 +400,12 @@ fn get_ump (id: Option<u32>, game_date:Date, ump_map: &HashMap<u32, Player>) ->
 }
 
 
-///Convert all the data about the game into a vector of pitches
-impl <'m> From <GameData<'m>> for Vec<Pitch> {
+///Convert all the data about the game into a vector of pitches. Fails if we're missing the schedule, boxscore or player metadata
+///for the game, or if a pitch is missing a field we can't do without (such as the count).
+impl <'m> TryFrom <GameData<'m>> for Vec<Pitch> {
+    type Error = BossError;
 
-    fn from (data: GameData) -> Vec<Pitch> {
+    fn try_from (data: GameData) -> Result<Vec<Pitch>, BossError> {
 
         let plays = data.pitch_data;
 
@@ -390,16 +413,17 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
         let mut pitches: Vec<Pitch> = Vec::with_capacity(300);
         let game_pk = data.game_pk;
         
-        //we start with the schedule, so we can safely unwrap here
-        let sched_meta = data.meta_data.schedule.get(&game_pk).unwrap();
+        let missing = |meta_data: MetaDataType| BossError::MissingMetaData {game_pk, meta_data};
+        let missing_field = |field: &'static str| BossError::MissingField {id: game_pk, field};
+
+        let sched_meta = data.meta_data.schedule.get(&game_pk).ok_or(missing(MetaDataType::Schedule))?;
         let year: u16 = sched_meta.game_date.year;
 
         // We check here to make sure the game has a boxscore before processing. If we
         // don't have a boxscore, we'll be missing a LOT of critical information, such as the defense.
         // At some point, we may want to include games with no boxscore data, but for now we'll exclude them.
         // We handle all other possible missing metadata through Default impls, or explicitly in the code below.
-        if !data.meta_data.boxscore.contains_key(&game_pk) {return vec![]};
-        let box_meta = data.meta_data.boxscore.get(&game_pk).unwrap();
+        let box_meta = data.meta_data.boxscore.get(&game_pk).ok_or(missing(MetaDataType::BoxScore))?;
 
         // Handle the case where we don't have venue metadata
         let venue_meta = match data.meta_data.venue.get(&(sched_meta.game_venue_id, year)) {
@@ -447,16 +471,32 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
         let home_players: HashMap<u32, Option<u16>> = box_meta.home_players.iter().map(|p| (p.id, p.batting_order)).collect();
         let away_players: HashMap<u32, Option<u16>> = box_meta.away_players.iter().map(|p| (p.id, p.batting_order)).collect();
 
-        let hp_umpire_id = box_meta.hp_umpire_id;
-        let hp_details = get_ump(hp_umpire_id, sched_meta.game_date, &player_meta);
+        // The Gameday XML files fill in the home plate umpire for older games the boxscore doesn't have one for.
+        let gameday_meta = data.meta_data.gameday.get(&game_pk);
+        let gameday_hp_umpire = gameday_meta.and_then(|gameday| gameday.hp_umpire());
+
+        let hp_umpire_id = box_meta.hp_umpire_id.or_else(|| gameday_hp_umpire.and_then(|ump| ump.id));
+        let mut hp_details = get_ump(hp_umpire_id, sched_meta.game_date, &player_meta);
+        if hp_details.0.is_none() {
+            hp_details.0 = gameday_hp_umpire
+                .filter(|ump| ump.id == hp_umpire_id)
+                .map(|ump| ump.name.clone());
+        };
 
         let hp_umpire_dob = match hp_details.1 {
             Some (dob) => dob.to_string(),
             None => "".to_string(),
         };
 
+        // The base umpires, with the same fallback to the Gameday files.
+        let umpire_crew = box_meta.umpire_crew();
+        let gameday_umpire_id = |position: &str| gameday_meta.and_then(|gameday| gameday.umpire(position)).and_then(|ump| ump.id);
+        let first_base_umpire_id = umpire_crew.first_base.or_else(|| gameday_umpire_id("first"));
+        let second_base_umpire_id = umpire_crew.second_base.or_else(|| gameday_umpire_id("second"));
+        let third_base_umpire_id = umpire_crew.third_base.or_else(|| gameday_umpire_id("third"));
+
         let sport_id = sched_meta.sport_id;
-        let sport_details = crate::sports::get_sport(sport_id);
+        let sport_details = crate::sports::get_sport(sport_id)?;
 
         let (venue_home_plate_x, venue_home_plate_y) = match venue_x_y {
             Some (venue) => (venue.x.unwrap_or(crate::STADIUM_X), venue.y.unwrap_or(crate::STADIUM_Y)),
@@ -525,8 +565,20 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
 
 
 
-            let batter_details = player_meta.get(&batter).unwrap().clone();
-            let pitcher_details = player_meta.get(&pitcher).unwrap().clone();
+            let mut batter_details = player_meta.get(&batter).ok_or(missing(MetaDataType::Player(batter)))?.clone();
+            let mut pitcher_details = player_meta.get(&pitcher).ok_or(missing(MetaDataType::Player(pitcher)))?.clone();
+
+            // Weights and heights as they were listed on the day of the game, rather than today.
+            data.meta_data.player_history.apply(&mut batter_details, sched_meta.game_date);
+            data.meta_data.player_history.apply(&mut pitcher_details, sched_meta.game_date);
+
+            let transactions = &data.meta_data.transactions;
+            let batter_days_since_il_return = transactions.days_since(batter, TransactionKind::InjuredListActivation, sched_meta.game_date);
+            let batter_days_since_trade = transactions.days_since(batter, TransactionKind::Trade, sched_meta.game_date);
+            let pitcher_days_since_il_return = transactions.days_since(pitcher, TransactionKind::InjuredListActivation, sched_meta.game_date);
+            let pitcher_days_since_trade = transactions.days_since(pitcher, TransactionKind::Trade, sched_meta.game_date);
+            let batter_all_star_selections = data.meta_data.awards.all_star_selections_before(batter, sched_meta.game_date);
+            let pitcher_all_star_selections = data.meta_data.awards.all_star_selections_before(pitcher, sched_meta.game_date);
 
             let batter_dob = match batter_details.birth_date {
                 Some (dob) => dob.to_string(),
@@ -711,7 +763,7 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                                 // We should have player and position info for every defensive switch, however,
                                 // this will panic for DHs, who have no position. so we need to check for that.
                                 // If we don't have a position for the batter, we just assume they're a DH.
-                                let player_id = event.player.unwrap().id;
+                                let player_id = event.player.ok_or(missing_field("player"))?.id;
                                 let position = match event.position {
                                     Some (pos) => pos.abbreviation,
                                     _ => Pos::DesignatedHitter,
@@ -792,7 +844,9 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                             else {Pos::Bench}
                         };
 
-                        let (in_play_result, in_play_1b, in_play_2b, in_play_3b, in_play_hr) = match event.details.is_in_play.unwrap() {
+                        let is_in_play = event.details.is_in_play.ok_or(missing_field("isInPlay"))?;
+
+                        let (in_play_result, in_play_1b, in_play_2b, in_play_3b, in_play_hr) = match is_in_play {
                             true => {
                                 let in_play_result = plate_app.result.plate_appearance_result.unwrap_or(Event::Other);
                                 let in_play_1b = if in_play_result == Event::Single {1} else {0};
@@ -852,15 +906,15 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                         // There are in some cases extra pitches, so we always correct to the count given
                         // in the data. This *might* lead to double counting of walks and strikeouts, if this pitch
                         // is duplicated in the data. Should check for this at some point. 
-                        let balls_end = event.count.balls.unwrap();
-                        let strikes_end = event.count.strikes.unwrap();
+                        let balls_end = event.count.balls.ok_or(missing_field("balls"))?;
+                        let strikes_end = event.count.strikes.ok_or(missing_field("strikes"))?;
 
                         if strikes_end == 3 {strikeout = 1;};
                         if balls_end == 4 {walk = 1};
 
 
-                        //if our event type is a pitch, we can safely unwrap the pitch_data
-                        let pitch_data = event.pitch_data.unwrap();
+                        //every pitch event should have pitch_data
+                        let pitch_data = event.pitch_data.ok_or(missing_field("pitchData"))?;
                         
                         let (pitch_break_length, pitch_break_y, pitch_spin_rate, pitch_spin_direction) = match pitch_data.breaks {
                             Some (breaks) => 
@@ -993,6 +1047,10 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                                 hp_umpire_age: hp_details.2,
                                 hp_umpire_height: hp_details.3,
                                 hp_umpire_height_str: hp_details.4.clone(),
+                                first_base_umpire_id,
+                                second_base_umpire_id,
+                                third_base_umpire_id,
+                                crew_chief_umpire_id: umpire_crew.crew_chief,
 
                                 sport_id,
                                 sport_code: sport_details.code.into(),
@@ -1062,6 +1120,9 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                                 batter_highschool_city: batter_details.highschool_city.clone(),
                                 batter_highschool_prov_state: batter_details.highschool_prov_state.clone(),
                                 batter_college_name: batter_details.college_name.clone(),
+                                batter_days_since_il_return,
+                                batter_days_since_trade,
+                                batter_all_star_selections,
 
                                 batter_stands: batter_details.bat_side_code,
                                 batter_stands_desc: batter_details.bat_side_description,
@@ -1088,6 +1149,9 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                                 pitcher_highschool_city: pitcher_details.highschool_city.clone(),
                                 pitcher_highschool_prov_state: pitcher_details.highschool_prov_state.clone(),
                                 pitcher_college_name: pitcher_details.college_name.clone(),
+                                pitcher_days_since_il_return,
+                                pitcher_days_since_trade,
+                                pitcher_all_star_selections,
                                 
                                 
                                 pitch_num_plate_appearance,
@@ -1112,7 +1176,7 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                                 swing_and_miss,
                                 double_play_opportunity,
                                 
-                                in_play: event.details.is_in_play.unwrap().into(),
+                                in_play: is_in_play.into(),
                                 
                                 pitch_speed_start: pitch_data.start_speed,
                                 pitch_speed_end: pitch_data.end_speed,
@@ -1159,6 +1223,13 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                                 hit_data_spray_angle,
                                 hit_data_calc_distance,
 
+                                statcast_spin_axis: None,
+                                statcast_hit_distance: None,
+                                statcast_launch_speed_angle: None,
+                                statcast_xba: None,
+                                statcast_xwoba: None,
+                                statcast_woba_value: None,
+
                                 fielded_by_id,
                                 fielded_by_name,
                                 fielded_by_pos,
@@ -1204,6 +1275,6 @@ impl <'m> From <GameData<'m>> for Vec<Pitch> {
                 }
             }
         }
-        pitches
+        Ok(pitches)
     }
 }
\ No newline at end of file
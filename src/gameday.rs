@@ -0,0 +1,180 @@
+//! Reads the old Gameday XML files from a local mirror of gd2.mlb.com, using the folder `feed_live` builds for each game. The Stats API
+//! is missing the umpires for some older games, and its player weights are today's weights. The XML files were written on the day of the
+//! game, so we use them to fill in the umpires and get each player's weight and height as they were listed that day (see `player_history`).
+//!
+//! Point `BossConfig::gameday_mirror` at the folder holding the mirror (the one with `mlb/`, `aaa/` etc. in it) and the metadata pull
+//! will read it. Games that aren't in the mirror are skipped. Per game we read:
+//!
+//! * `game.xml`, to make sure the folder is for the game we think it is.
+//! * `players.xml`, for the umpires. Umpires that are only in these files have their bios pulled along with the players.
+//! * `batters/*.xml` and `pitchers/*.xml`, for each player's weight and height.
+//! * `inning/inning_all.xml`, for the batter heights of players who don't have a file of their own.
+//!
+//! The files are flat enough that we pick the elements and attributes out with a regex rather than pulling in an XML parser.
+
+use crate::error::BossError;
+use crate::feed_live::FeedData;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The umpires, weights and heights for one game, as the Gameday XML files had them.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct GamedayData {
+    pub game_pk: u32,
+    pub umpires: Vec<GamedayUmpire>,
+    /// Weight in pounds by player id.
+    pub weights: HashMap<u32, u16>,
+    /// Height by player id, such as "6-2".
+    pub heights: HashMap<u32, String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GamedayUmpire {
+    /// "home", "first", "second", "third", "left" or "right".
+    pub position: String,
+    pub id: Option<u32>,
+    pub name: String,
+}
+
+impl GamedayData {
+    /// The home plate umpire, if the files had one.
+    pub fn hp_umpire (&self) -> Option<&GamedayUmpire> {
+        self.umpire("home")
+    }
+
+    /// The umpire at `position`, such as "first".
+    pub fn umpire (&self, position: &str) -> Option<&GamedayUmpire> {
+        self.umpires.iter().find(|umpire| umpire.position == position)
+    }
+}
+
+/// Where the folder for a game lives in the mirror. The gameday folder is a full url, so we swap its host for the mirror folder.
+pub fn mirror_folder (mirror: &Path, feed: &FeedData) -> PathBuf {
+    let relative = feed.gameday_xml_folder
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .split_once('/')
+        .map(|(_host, path)| path)
+        .unwrap_or("")
+        .trim_start_matches("components/game/");
+
+    relative.split('/')
+        .filter(|part| !part.is_empty())
+        .fold(mirror.to_path_buf(), |path, part| path.join(part))
+}
+
+/// Reads one game's XML files from the mirror. Returns `Ok(None)` when the mirror doesn't have the game.
+pub fn read_gameday_game (mirror: &Path, feed: &FeedData) -> Result<Option<GamedayData>, BossError> {
+    let folder = mirror_folder(mirror, feed);
+    if !folder.is_dir() {return Ok (None)};
+    let xml = XmlScanner::shared()?;
+
+    if let Some (game) = read_optional(&folder.join("game.xml"))? {
+        let game_pk = xml.elements(&game, "game").into_iter().next().and_then(|game| game.get("game_pk").cloned());
+        if let Some (game_pk) = game_pk {
+            if game_pk.parse::<u32>().ok() != Some (feed.game_pk) {
+                return Err (BossError::UnexpectedValue {field: "game.xml game_pk", value: game_pk});
+            };
+        };
+    };
+
+    let mut data = GamedayData {game_pk: feed.game_pk, ..GamedayData::default()};
+
+    if let Some (players) = read_optional(&folder.join("players.xml"))? {
+        data.umpires = xml.elements(&players, "umpire").into_iter()
+            .map(|umpire| GamedayUmpire {
+                position: umpire.get("position").cloned().unwrap_or_default(),
+                id: umpire.get("id").and_then(|id| id.parse().ok()),
+                name: umpire.get("name").cloned().unwrap_or_default(),
+            })
+            .collect();
+    };
+
+    if let Some (innings) = read_optional(&folder.join("inning").join("inning_all.xml"))? {
+        for at_bat in xml.elements(&innings, "atbat") {
+            if let (Some (id), Some (height)) = (at_bat.get("batter").and_then(|id| id.parse::<u32>().ok()), at_bat.get("b_height")) {
+                data.heights.insert(id, height.clone());
+            };
+        };
+    };
+
+    // The per-player files have the weight, and the height as the roster listed it, which wins over the at bat height.
+    for kind in ["batters", "pitchers"] {
+        let dir = folder.join(kind);
+        if !dir.is_dir() {continue};
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some ("xml") {continue};
+            for player in xml.elements(&fs::read_to_string(&path)?, "Player") {
+                let id = match player.get("id").and_then(|id| id.parse::<u32>().ok()) {
+                    Some (id) => id,
+                    None => continue,
+                };
+                if let Some (weight) = player.get("weight").and_then(|weight| weight.parse::<u16>().ok()) {
+                    data.weights.insert(id, weight);
+                };
+                if let Some (height) = player.get("height").filter(|height| !height.is_empty()) {
+                    data.heights.insert(id, height.clone());
+                };
+            };
+        };
+    };
+
+    Ok (Some (data))
+}
+
+fn read_optional (path: &Path) -> Result<Option<String>, BossError> {
+    match fs::read(path) {
+        Ok (bytes) => Ok (Some (String::from_utf8_lossy(&bytes).into_owned())),
+        Err (error) if error.kind() == std::io::ErrorKind::NotFound => Ok (None),
+        Err (error) => Err (error.into()),
+    }
+}
+
+/// The element and attribute patterns, compiled the first time they're needed and shared by every game after that.
+struct XmlScanner {
+    element: Regex,
+    attribute: Regex,
+}
+
+impl XmlScanner {
+    fn new () -> Result<XmlScanner, regex::Error> {
+        Ok (XmlScanner {
+            element: Regex::new(r"<([\w:-]+)\b([^>]*)>")?,
+            attribute: Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#)?,
+        })
+    }
+
+    fn shared () -> Result<&'static XmlScanner, BossError> {
+        static SCANNER: OnceLock<Result<XmlScanner, regex::Error>> = OnceLock::new();
+        SCANNER.get_or_init(XmlScanner::new).as_ref().map_err(|error| error.clone().into())
+    }
+
+    /// The attributes of every `tag` element in the file.
+    fn elements (&self, xml: &str, tag: &str) -> Vec<HashMap<String, String>> {
+        self.element.captures_iter(xml)
+            .filter(|element| &element[1] == tag)
+            .map(|element| {
+                self.attribute.captures_iter(&element[2])
+                    .map(|attribute| {
+                        let value = attribute.get(2).or_else(|| attribute.get(3)).map(|value| value.as_str()).unwrap_or("");
+                        (attribute[1].to_string(), unescape(value))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn unescape (value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
@@ -6,61 +6,62 @@
 use crate::boxscore::{BoxScoreDe, BoxScoreData, fix_boxscore};
 use crate::defense::{Defense, DefenseData};
 use crate::cache::*;
+use crate::config::BossConfig;
+use crate::error::{BossError, Dataset, Failure, split_failures};
 use crate::coaches::{CoachData, Coaches, Roster};
 use crate::feed_live::{FeedData,Feed};
+use crate::gameday::{GamedayData, read_gameday_game};
 use crate::metadata::{VecMetaDataInputs, MetaData};
 use crate::play_by_play::Game;
+use crate::player_history::{PlayerAttributes, PlayerHistory, from_gameday, from_people};
 use crate::players::{People, Player};
-use crate::schedule::{YearRange, GameMetaData, Schedule, SeasonSportStatus, SeasonSportCache, SeasonStatus, AbstractGameState};
-use crate::sports;
+use crate::schedule::{GameMetaData, Schedule, SeasonSportStatus, SeasonSportCache, SeasonStatus, AbstractGameState};
 use crate::team::{TeamData, TeamJson};
-use crate::utils::stream;
+use crate::transactions::{Transaction, parse_transactions};
+use crate::awards::{Award, parse_awards};
+use crate::rosters::{RosterRequest, RosterSnapshot, parse_roster};
+use crate::utils::{stream, Date};
 use crate::venues::{VenueXY, Venues, VenueData};
 use crate::game::{Pitch, GameData};
+use crate::manifest::{Manifest, GameStatus};
+use crate::transport::Transport;
 
 use rayon::prelude::*;
-use std::collections::{BTreeSet};
-use isahc::prelude::*;
-use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::{TryFrom, TryInto};
 
 
 
 // We begin with the schedule. Getting the entire schedule can be quite time consuming, so we need to cache our data and only request year/level combinations that we haven't pulled already.
 // Between 2005 and 2020 (inclusive) there are roughly 300K games in the database.
+// For most uses, the `Boss` builder is the easiest way in. The functions here are the building blocks it uses.
 
 
-pub fn get_everything() {
-    let years = YearRange::from_range_inc(2005 ..= 2020);
-    let sport_ids = sports::get_all_sport_ids();
+/// Prints every item that failed during a pull. Failed items aren't cached, so they'll be retried on the next pull.
+pub fn report_failures (failures: &Vec<Failure>) {
+    if failures.is_empty() {return};
 
-    let meta = get_meta_data(years, sport_ids);
-    let schedule = meta.schedule.clone();
-    let meta_data = meta.into();
-
-    for _ in 0 .. 50 {
-        get_play_by_play(schedule.clone(), &meta_data);
-    }
-
-
-    // output_defense(&meta_data);
+    println!("{} items failed and will be retried on the next pull:", failures.len());
+    for failure in failures {
+        println!("  {}", failure);
+    };
 }
 
+
 #[allow(unused)]
 /// Converts the serialized pitch data into a defense database
 /// Only use this if you want to build the defense data from scratch
 /// Data are streamed so as to limit memory usage
-pub fn output_defense (meta_data: &MetaData) {
+pub fn output_defense (config: &BossConfig, meta_data: &MetaData) -> Result<(), BossError> {
 
     use csv::Reader;
     use crate::defense::{Defense, DefenseData};
 
-    const PLAY_BY_PLAY: &str = r#"F:\Baseball\baseball.csv"#;
-    
     let players = meta_data.players.clone();
 
     println!("Converting pitch data...");
 
-    let mut csv_reader = Reader::from_path(PLAY_BY_PLAY).unwrap();
+    let mut csv_reader = Reader::from_path(config.output_file(PLAY_BY_PLAY))?;
 
     for pitch in csv_reader.deserialize() {
         match pitch {
@@ -69,100 +70,53 @@ pub fn output_defense (meta_data: &MetaData) {
                     pitch: p,
                     players: &players,
                 }.into();
-                if defense.len() > 0 {write_defense(&defense)};
+                if defense.len() > 0 {write_defense(config, &defense)?};
             },
             Err (_) => {},
         }
     };
+
+    Ok(())
 }
 
-pub fn get_play_by_play (schedule: Vec<GameMetaData>, meta_data: &MetaData) {
+/// Pulls and processes the next batch of games that the manifest says still need processing: new games, games that hit a network
+/// error and games marked with `Manifest::retry`. The outcome for each game is recorded in the manifest, and every game that didn't
+/// make it is returned as a `Failure`.
+pub fn get_play_by_play (config: &BossConfig, transport: &dyn Transport, schedule: Vec<GameMetaData>, meta_data: &MetaData) -> Result<Vec<Failure>, BossError> {
 
-    #[derive(Serialize, Deserialize)]
-    struct GamesProcessed {
-        good: BTreeSet<u32>,
-        bad: BTreeSet<u32>,
-    }
-
-    let json = std::fs::read_to_string(r#"F:\Baseball\games_processed.json"#).unwrap_or("".to_string());
-    let games_processed: GamesProcessed = serde_json::from_str(&json).unwrap_or(
-        GamesProcessed {
-            good: BTreeSet::new(),
-            bad: BTreeSet::new(),
-        }
-    );
+    let mut manifest = Manifest::load(config)?;
 
     // let mut stored_pbp = load_play_by_play();
     // let games_loaded: BTreeSet<u32> = stored_pbp.iter().map(|pitch| pitch.game_pk).collect();
     
     // println!("Loaded {} games", games_loaded.len());
 
-    let mut good_games: BTreeSet<u32> = games_processed.good;
-    let mut bad_games: BTreeSet<u32> = games_processed.bad;
-
     //For some reason, we have duplicate game_pks in our schedule, so we make it a set to get rid of that problem.
     let pbp_urls: BTreeSet<(u32, String)> = schedule.iter()
         .filter (|game| game.game_status == AbstractGameState::Final)
-        .filter (|game| !good_games.contains(&game.game_pk))
-        .filter (|game| !bad_games.contains(&game.game_pk))
+        .filter (|game| manifest.needs_processing(game.game_pk))
         .map(|game| (game.game_pk, format!("http://statsapi.mlb.com/api/v1/game/{}/playByPlay", game.game_pk)))
         .take(5_000)
         .collect()
         ;
     
-     // we keep track of the requested games. Any that didn't return a result we'll package as a "bad" game
     let requested_games: BTreeSet<u32> = pbp_urls.iter().map(|game| game.0).collect();
-    dbg!(requested_games.len());
-
-    let http_client = isahc::HttpClient::new().unwrap();
 
-    let result: Vec<Pitch> = pbp_urls.into_par_iter()
-        // .inspect(|data| println!("{}", &data.1))
-        .map (|data| (data.0, http_client.get(data.1).unwrap().text().unwrap()))
-        .filter(|data| data.1.contains("allPlays"))
-        .map (|data| {
-            let pbp: Game = serde_json::from_str(&data.1).expect(&format!("Error with game_pk: {}", data.0));
-            let game_data = GameData {
-                pitch_data: pbp.all_plays,
-                meta_data: &meta_data,
-                game_pk: data.0,
-            };
-            let pitches: Vec<Pitch> = game_data.into();
-            pitches
-        })
-        .flatten()
-        .collect()
-        ;
-       
-    
-    let games_returned: BTreeSet<u32> = result.iter().map(|game|game.game_pk).collect();
-    let games_missed: BTreeSet<u32> = requested_games.into_iter().filter(|game| !games_returned.contains(game)).collect();
-
-    //They should equal the requested_games.len()
-    dbg!(games_missed.len());
-    dbg!(games_returned.len());
-
-    good_games.extend(games_returned);
-    bad_games.extend(games_missed);
+    let (result, failures) = process_games(transport, meta_data, pbp_urls)?;
 
     // stored_pbp.extend(result);
 
-    // let good_games: BTreeSet<u32> = stored_pbp.iter().map(|game| game.game_pk).collect();
-
-    let num_games_processed = good_games.len();
-
-    let games_processed = GamesProcessed {
-        good: good_games,
-        bad: bad_games,
-    };
-
-    let json = serde_json::to_string(&games_processed).unwrap();
-    std::fs::write(r#"F:\Baseball\games_processed.json"#, json).unwrap();
-
+    // The manifest is only saved once the pitches are on disk, so a write that fails leaves the games to be processed again.
     println!("Writing pitch by pitch data to CSV...");
-    crate::cache::append_play_by_play(&result);
+    crate::cache::append_play_by_play(config, &result)?;
+    crate::cache::cache_play_by_play(config, &result)?;
     println!("Added {} records.", result.len());
 
+    manifest.record_batch(&requested_games, &result, &failures);
+    manifest.save(config)?;
+
+    let num_games_processed = manifest.counts().get(&GameStatus::Ok).cloned().unwrap_or(0);
+
     println!("Converting to Defense Data...");
 
     let defense: Vec<Defense> = result.into_par_iter()
@@ -178,19 +132,74 @@ pub fn get_play_by_play (schedule: Vec<GameMetaData>, meta_data: &MetaData) {
         ;
     
     println!("Writing defense data to CSV...");
-    if defense.len() > 0 {write_defense(&defense)};
+    if defense.len() > 0 {write_defense(config, &defense)?};
 
     println!("Processed {} total games.", num_games_processed);
 
+    Ok(failures)
+
+}
+
+/// Pulls the play by play for each (game_pk, url) and stitches it together with the metadata. Games without any plays are skipped
+/// without a failure, since there's nothing wrong with them, there's just nothing there. Nothing is written to disk.
+pub (crate) fn process_games (transport: &dyn Transport, meta_data: &MetaData, pbp_urls: BTreeSet<(u32, String)>) -> Result<(Vec<Pitch>, Vec<Failure>), BossError> {
+
+
+    let results: Vec<Result<Option<Vec<Pitch>>, Failure>> = pbp_urls.into_par_iter()
+        // .inspect(|data| println!("{}", &data.1))
+        .map (|data| {
+            process_game(transport, meta_data, data.0, &data.1)
+                .map_err(|error| Failure {dataset: Dataset::PlayByPlay, key: data.0.to_string(), error})
+        })
+        .collect()
+        ;
+
+    let (processed, failures) = split_failures(results);
+    let pitches: Vec<Pitch> = processed.into_iter().flatten().flatten().collect();
+
+    Ok((pitches, failures))
+}
+
+/// Pulls and processes a single game. Returns None if the game has no plays.
+pub (crate) fn process_game (transport: &dyn Transport, meta_data: &MetaData, game_pk: u32, url: &str) -> Result<Option<Vec<Pitch>>, BossError> {
+    match pull_game(transport, meta_data, game_pk, url)? {
+        Some (game_data) => Ok(Some(Vec::<Pitch>::try_from(game_data)?)),
+        None => Ok(None),
+    }
+}
+
+/// Pulls the play by play for a single game and pairs it with the metadata, ready to be converted into pitches (or anything else
+/// that can be built from a `GameData`). Returns None if the game has no plays.
+pub (crate) fn pull_game <'m> (transport: &dyn Transport, meta_data: &'m MetaData, game_pk: u32, url: &str) -> Result<Option<GameData<'m>>, BossError> {
+    let json = transport.get(url)?;
+    // Games that were never played (or never tracked) have no plays, there's nothing to process.
+    if !json.contains("allPlays") {return Ok(None)};
+    let pbp: Game = serde_json::from_str(&json)?;
+    Ok(Some(GameData {
+        pitch_data: pbp.all_plays,
+        meta_data,
+        game_pk,
+    }))
 }
 
 /// Feed the get_data function a list of years and sport_ids and get back all the data for those combos. BOSS will cache anything it already has data for and try to fill in any missing pieces 
-/// it doesn't have.
-pub fn get_meta_data(years: Vec<u16>, sport_ids: Vec<u32>) -> VecMetaDataInputs {
+/// it doesn't have. Items that couldn't be pulled are returned as failures alongside the data.
+pub fn get_meta_data (config: &BossConfig, transport: &dyn Transport, years: Vec<u16>, sport_ids: Vec<u32>) -> Result<(VecMetaDataInputs, Vec<Failure>), BossError> {
+
+    let (schedule_data, mut failures) = get_schedule_data(config, transport, years, sport_ids)?;
+    let (meta, game_failures) = get_game_meta_data(config, transport, schedule_data)?;
+    failures.extend(game_failures);
+
+    Ok((meta, failures))
+}
+
+/// Gets all the metadata (boxscores, coaches, players, venues etc.) needed to process the games in the schedule passed in.
+pub (crate) fn get_game_meta_data (config: &BossConfig, transport: &dyn Transport, schedule_data: Vec<GameMetaData>) -> Result<(VecMetaDataInputs, Vec<Failure>), BossError> {
 
-    let schedule_data = get_schedule_data (years, sport_ids);
+    let mut failures: Vec<Failure> = Vec::new();
          
-    let teams_data = get_team_data(&schedule_data);
+    let (teams_data, team_failures) = get_team_data(config, transport, &schedule_data)?;
+    failures.extend(team_failures);
 
     dbg!(teams_data.len());
     
@@ -199,10 +208,12 @@ pub fn get_meta_data(years: Vec<u16>, sport_ids: Vec<u32>) -> VecMetaDataInputs
     // dbg! (boxscore_data.len());
     // }
 
-    let boxscore_data = get_boxscore_data(&schedule_data);
+    let (boxscore_data, boxscore_failures) = get_boxscore_data(config, transport, &schedule_data)?;
+    failures.extend(boxscore_failures);
     dbg! (boxscore_data.len());
 
-    let coaches_data = get_coach_data(&schedule_data);
+    let (coaches_data, coach_failures) = get_coach_data(config, transport, &schedule_data)?;
+    failures.extend(coach_failures);
     dbg!(coaches_data.len());
                 
     // for _ in 0 .. 100 {
@@ -210,39 +221,64 @@ pub fn get_meta_data(years: Vec<u16>, sport_ids: Vec<u32>) -> VecMetaDataInputs
     //     dbg!(player_data.len());
     // }
             
-    let player_data = get_player_data(&boxscore_data, &coaches_data);
-    dbg!(player_data.len());
-            
-    let feed_live_data = get_feed_live_data(&schedule_data);
+    let (feed_live_data, feed_failures) = get_feed_live_data(config, transport, &schedule_data)?;
+    failures.extend(feed_failures);
     dbg!(feed_live_data.len());
 
-    let venue_x_y_data = get_venue_xy_data(&schedule_data);
+    // Read before the players, since some umpires are only in the Gameday files.
+    let (gameday_data, gameday_failures) = get_gameday_data(config, &feed_live_data);
+    failures.extend(gameday_failures);
+
+    let (player_data, player_failures) = get_player_data(config, transport, &boxscore_data, &coaches_data, &gameday_data)?;
+    failures.extend(player_failures);
+    dbg!(player_data.len());
+
+    let transactions = load_transactions(config)?;
+
+    let awards = load_awards(config)?;
+
+    let player_history = get_player_history(config, &schedule_data, &gameday_data)?;
+
+    let (venue_x_y_data, venue_x_y_failures) = get_venue_xy_data(config, transport, &schedule_data)?;
+    failures.extend(venue_x_y_failures);
     dbg!(venue_x_y_data.len());
 
-    let venue_data = get_venue_data(&schedule_data);
+    let (venue_data, venue_failures) = get_venue_data(config, transport, &schedule_data)?;
+    failures.extend(venue_failures);
     dbg!(venue_data.len());
 
-    VecMetaDataInputs {
+    let meta = VecMetaDataInputs {
         schedule: schedule_data,
         boxscore: boxscore_data,
         venue: venue_data,
         venue_x_y: venue_x_y_data,
         coaches: coaches_data,
         feed_data: feed_live_data,
+        gameday: gameday_data,
+        player_history,
+        transactions,
+        awards,
         teams: teams_data,
         players: player_data,
-    }
+    };
 
-    
+    Ok((meta, failures))
 
 }
 
-fn get_team_data (sched: &Vec<GameMetaData>) -> Vec<TeamData> {
+fn get_team_data (config: &BossConfig, transport: &dyn Transport, sched: &Vec<GameMetaData>) -> Result<(Vec<TeamData>, Vec<Failure>), BossError> {
+
+    let teams_cache = CachedSet::new(load_teams_data(config)?, config.overwrite(Dataset::Teams), |t| t.team.id);
 
-    let mut teams_cache = load_teams_data();
+    // Teams are pulled a whole season at a time, so a season with any team due for a refresh is pulled again.
+    let team_seasons_due: BTreeSet<(u16, u32)> = teams_cache.due()
+        .map (|t| (t.year, t.team.sport_id))
+        .collect()
+        ;
 
-    let team_seaons_cached: BTreeSet<(u16, u32)>  = teams_cache.clone().iter()
+    let team_seaons_cached: BTreeSet<(u16, u32)>  = teams_cache.kept()
         .map (|t| (t.year, t.team.sport_id))
+        .filter (|season| !team_seasons_due.contains(season))
         .collect()
         ;
 
@@ -252,45 +288,105 @@ fn get_team_data (sched: &Vec<GameMetaData>) -> Vec<TeamData> {
         ;
 
 
-    let team_urls: Vec<(u16, String)> = team_seaons_needed.iter()
+    let team_urls: Vec<(u16, u32, String)> = team_seaons_needed.iter()
         .filter(|team| !team_seaons_cached.contains(&(team.0, team.1)))
-        .map (|team| (team.0, format!("http://statsapi.mlb.com/api/v1/teams/?season={}&sportId={}&hydrate=social", team.0, team.1)))
+        .map (|team| (team.0, team.1, format!("http://statsapi.mlb.com/api/v1/teams/?season={}&sportId={}&hydrate=social", team.0, team.1)))
         // .take(1)
         // .inspect(|url| println!("{:?}", url))
         .collect()
         ;
     
-    let http_client = isahc::HttpClient::new().unwrap();
 
-    let json_data: Vec<(u16, String)> = team_urls.into_par_iter()
-            .map (|url|  (url.0, http_client.get(url.1).unwrap().text().unwrap()))
-            .filter (|json| json.1.contains("teams"))
-            .collect();
-            
-    let new_team_data: Vec<TeamData> = json_data.into_par_iter()
-        .map (|json| {
-            let team: TeamJson = serde_json::from_str(&json.1).unwrap();
-            let teams: Vec<TeamData> = team.teams.into_iter()
-                .map(|team| TeamData {
-                    year: json.0,
-                    team: team.clone().into(),
-                })
-                .collect()
-                ;
-            teams
+    let results: Vec<Result<Vec<TeamData>, Failure>> = team_urls.into_par_iter()
+        .map (|url| {
+            let download = || -> Result<Vec<TeamData>, BossError> {
+                let json = transport.get(&url.2)?;
+                if !json.contains("teams") {return Ok(vec![])};
+                let team: TeamJson = serde_json::from_str(&json)?;
+                team.teams.into_iter()
+                    .map(|team| Ok(TeamData {
+                        year: url.0,
+                        team: team.try_into()?,
+                    }))
+                    .collect()
+            };
+            download().map_err(|error| Failure {dataset: Dataset::Teams, key: format!("{}/{}", url.0, url.1), error})
         })
-        .flatten()
         .collect()
         ;
 
-    teams_cache.extend (new_team_data);
-    cache_teams_data(&teams_cache);
-    teams_cache
+    let (new_team_data, failures) = split_failures(results);
+
+    let teams_cache = teams_cache.merge(new_team_data.into_iter().flatten().collect(), |t| (t.year, t.team.id));
+    cache_teams_data(config, &teams_cache)?;
+    Ok((records(&teams_cache), failures))
 }
 
-fn get_player_data (boxscore: &Vec<BoxScoreData>, coaches: &Vec<CoachData>) -> Vec<Player> {
+/// Pulls the roster snapshots asked for, along with any that couldn't be pulled. Snapshots already in a team's season cache aren't
+/// pulled again, unless the `Dataset::Rosters` refresh policy says so or the day hadn't finished when they were pulled. See the
+/// `rosters` module.
+pub fn get_roster_data (config: &BossConfig, transport: &dyn Transport, requests: &[RosterRequest]) -> Result<(Vec<RosterSnapshot>, Vec<Failure>), BossError> {
+
+    let requested: BTreeSet<RosterRequest> = requests.iter().copied().collect();
+    let team_seasons: BTreeSet<(u32, u16)> = requested.iter().map(|request| (request.team_id, request.date.year)).collect();
+
+    let mut caches: BTreeMap<(u32, u16), CachedSet<RosterSnapshot>> = BTreeMap::new();
+    for (team_id, season) in team_seasons {
+        let cache = CachedSet::new(load_rosters(config, team_id, season)?, config.overwrite(Dataset::Rosters), |roster| roster.team_id)
+            .refresh_if(|cached| (cached.fetched_at / 86_400) as i64 <= cached.record.date.day_number() as i64);
+        caches.insert((team_id, season), cache);
+    };
+
+    let cached: BTreeSet<RosterRequest> = caches.values().flat_map(|cache| cache.kept()).map(RosterSnapshot::request).collect();
+    let needed: Vec<RosterRequest> = requested.iter().filter(|request| !cached.contains(request)).copied().collect();
 
-    let mut players_cache = load_player_data();
+    let results: Vec<Result<RosterSnapshot, Failure>> = needed.into_par_iter()
+        .map(|request| {
+            let download = || -> Result<RosterSnapshot, BossError> {
+                parse_roster(request, &transport.get(&request.url())?)
+            };
+            download().map_err(|error| Failure {
+                dataset: Dataset::Rosters,
+                key: format!("{}/{}/{}", request.team_id, request.date.to_string(), request.roster_type.param()),
+                error,
+            })
+        })
+        .collect()
+        ;
+
+    let (new_rosters, failures) = split_failures(results);
+
+    let mut new_by_season: BTreeMap<(u32, u16), Vec<RosterSnapshot>> = BTreeMap::new();
+    for roster in new_rosters {
+        new_by_season.entry((roster.team_id, roster.date.year)).or_default().push(roster);
+    };
+
+    let mut rosters = Vec::new();
+    for ((team_id, season), cache) in caches {
+        let records = match new_by_season.remove(&(team_id, season)) {
+            Some (new) => {
+                let merged = cache.merge(new, RosterSnapshot::request);
+                cache_rosters(config, team_id, season, &merged)?;
+                records(&merged)
+            },
+            None => cache.into_records(),
+        };
+        rosters.extend(records.into_iter().filter(|roster| requested.contains(&roster.request())));
+    };
+
+    Ok((rosters, failures))
+}
+
+/// A player's bio, along with the transactions and awards from the same response.
+struct PulledPlayer {
+    player: Player,
+    transactions: Vec<Transaction>,
+    awards: Vec<Award>,
+}
+
+fn get_player_data (config: &BossConfig, transport: &dyn Transport, boxscore: &Vec<BoxScoreData>, coaches: &Vec<CoachData>, gameday: &[GamedayData]) -> Result<(Vec<Player>, Vec<Failure>), BossError> {
+
+    let players_cache = CachedSet::new(load_player_data(config)?, config.overwrite(Dataset::Players), |player| player.id);
 
     let mut players_needed: BTreeSet<u32> = boxscore.iter()
         .map(|b| 
@@ -305,7 +401,8 @@ fn get_player_data (boxscore: &Vec<BoxScoreData>, coaches: &Vec<CoachData>) -> V
         ;
     
     let umps_needed: BTreeSet<u32> = boxscore.iter()
-        .filter_map( |b| b.boxscore_data.hp_umpire_id)
+        .flat_map( |b| b.boxscore_data.hp_umpire_id.into_iter().chain(b.boxscore_data.officials.iter().map(|ump| ump.id)))
+        .chain(gameday.iter().flat_map(|game| game.umpires.iter().filter_map(|ump| ump.id)))
         .collect()
         ;
 
@@ -329,102 +426,119 @@ fn get_player_data (boxscore: &Vec<BoxScoreData>, coaches: &Vec<CoachData>) -> V
 
     // dbg!(players_needed.len());
 
-    let players_cached: BTreeSet<u32> = players_cache.clone().into_iter()
+    let players_cached: BTreeSet<u32> = players_cache.kept()
             .map(|player| player.id)
             .collect();
     
-    let player_urls: BTreeSet<String> = players_needed.iter()
+    let player_urls: BTreeSet<(u32, String)> = players_needed.iter()
             .filter (|player| !players_cached.contains(&player))
-            .map(|player| format!("http://statsapi.mlb.com/api/v1/people/{}?hydrate=xrefId,draft,transactions,awards,education",player))
+            .map(|player| (*player, format!("http://statsapi.mlb.com/api/v1/people/{}?hydrate=xrefId,draft,transactions,awards,education",player)))
             // .inspect(|url| println!("{}", url))
-            .take(1_000)
             .collect()
             ;
 
-    if player_urls.len() == 0 {return players_cache};
+    if player_urls.len() == 0 {return Ok((players_cache.into_records(), vec![]))};
 
-    let http_client = isahc::HttpClient::new().unwrap();
 
-    let json_data: Vec<String> = player_urls.into_par_iter()
-            .map (|url|  http_client.get(url).unwrap().text().unwrap())
-            .collect();
-
-    let new_player_data: Vec<crate::players::Player> = json_data.into_par_iter()
-        .filter (|json| json.contains("people"))
-        .map (|json| {
-            let player: People = serde_json::from_str(&json).unwrap();
-            player.people[0].clone().into()
+    let results: Vec<Result<Option<PulledPlayer>, Failure>> = player_urls.into_par_iter()
+        .map (|url| {
+            let download = || -> Result<Option<PulledPlayer>, BossError> {
+                let json = transport.get(&url.1)?;
+                if !json.contains("people") {return Ok(None)};
+                let player: People = serde_json::from_str(&json)?;
+                Ok(player.people.into_iter().nth(0).map(|mut player| {
+                    let transactions = parse_transactions(player.id, player.transactions.take().unwrap_or_default());
+                    let awards = parse_awards(player.id, player.awards.take().unwrap_or_default());
+                    PulledPlayer {player: player.into(), transactions, awards}
+                }))
+            };
+            download().map_err(|error| Failure {dataset: Dataset::Players, key: url.0.to_string(), error})
         })
         .collect()
         ;
-    players_cache.extend(new_player_data);
-    cache_player_data(&players_cache);
-    players_cache
+
+    let (new_player_data, failures) = split_failures(results);
+    let new_player_data: Vec<PulledPlayer> = new_player_data.into_iter().flatten().collect();
+
+    // A player that was pulled again brings their whole transaction and award history with them, so it replaces what we had.
+    let pulled: BTreeSet<u32> = new_player_data.iter().map(|pulled| pulled.player.id).collect();
+    let mut transactions: Vec<Transaction> = load_transactions(config)?.into_iter()
+        .filter(|transaction| !pulled.contains(&transaction.player_id))
+        .collect();
+    let mut awards: Vec<Award> = load_awards(config)?.into_iter()
+        .filter(|award| !pulled.contains(&award.player_id))
+        .collect();
+
+    let mut new_players = Vec::with_capacity(new_player_data.len());
+    for pulled in new_player_data {
+        new_players.push(pulled.player);
+        transactions.extend(pulled.transactions);
+        awards.extend(pulled.awards);
+    };
+    cache_transactions(config, &transactions)?;
+    cache_awards(config, &awards)?;
+
+    let players_cache = players_cache.merge(new_players, |player| player.id);
+    cache_player_data(config, &players_cache)?;
+    Ok((records(&players_cache), failures))
 }
 
-fn get_venue_data (schedule_data: &Vec<GameMetaData>) -> Vec<VenueData> {
+fn get_venue_data (config: &BossConfig, transport: &dyn Transport, schedule_data: &Vec<GameMetaData>) -> Result<(Vec<VenueData>, Vec<Failure>), BossError> {
     
-    let mut venue_cache = load_venue();
+    let venue_cache = CachedSet::new(load_venue(config)?, config.overwrite(Dataset::Venues), |venue_season| venue_season.venue.id);
 
-    let venues_cached: BTreeSet<(u32, u16)> = venue_cache.clone().into_iter()
+    let venues_cached: BTreeSet<(u32, u16)> = venue_cache.kept()
         .map(|venue_season| (venue_season.venue.id, venue_season.year))
         .collect();
 
     // If the year specific data isn't there, try the generic version
-    let venue_urls: BTreeSet<(u16,String, String)> = schedule_data.iter()
+    let venue_urls: BTreeSet<(u16, u32, String, String)> = schedule_data.iter()
         .filter(|game| !venues_cached.contains(&(game.game_venue_id, game.game_date.year)))
         .map ( |game| (
             game.game_date.year, 
+            game.game_venue_id,
             format!("https://statsapi.mlb.com/api/v1/venues/{}/?hydrate=location,fieldInfo,timezone,xrefId&season={}", game.game_venue_id, game.game_date.year),
             format!("https://statsapi.mlb.com/api/v1/venues/{}/?hydrate=location,fieldInfo,timezone,xrefId", game.game_venue_id)
         ))
         //VenueID 526 never has any data, so IGNORE IT
-        .filter(|game| !game.1.contains("/526"))
+        .filter(|game| game.1 != 526)
         .collect();
 
-    if venue_urls.len() == 0 {return venue_cache};
+    if venue_urls.len() == 0 {return Ok((venue_cache.into_records(), vec![]))};
     dbg!(&venue_urls);
     
-    let http_client = isahc::HttpClient::new()
-    .unwrap();
 
-    let json_data: Vec<(u16, String, String)> = venue_urls.into_par_iter()
-        .map(|url| (url.0, http_client.get(url.1).unwrap().text().unwrap(), http_client.get(url.2).unwrap().text().unwrap()))
-        .collect()
-        ;
-    
-    let new_venue_data: Vec<VenueData> = json_data.into_par_iter()
-        .map (|data| {
-            if data.1.contains("venues") {
-                (data.0, data.1)
-            }
-            else {
-                (data.0, data.2)
-            }
-        })
-        .filter (|json| json.1.contains("venues"))
-        .map (|json| {
-            let venue: Venues = serde_json::from_str(&json.1).unwrap();
-            VenueData {
-                year: json.0,
-                venue: venue.venues[0].clone().into(),
-            }
+    let results: Vec<Result<Option<VenueData>, Failure>> = venue_urls.into_par_iter()
+        .map(|url| {
+            let download = || -> Result<Option<VenueData>, BossError> {
+                let mut json = transport.get(&url.2)?;
+                if !json.contains("venues") {json = transport.get(&url.3)?};
+                if !json.contains("venues") {return Ok(None)};
+                let venue: Venues = serde_json::from_str(&json)?;
+                Ok(venue.venues.into_iter().nth(0).map(|venue| VenueData {
+                    year: url.0,
+                    venue: venue.into(),
+                }))
+            };
+            download().map_err(|error| Failure {dataset: Dataset::Venues, key: format!("{}/{}", url.1, url.0), error})
         })
         .collect()
         ;
     
-    venue_cache.extend(new_venue_data);
-    cache_venue(&venue_cache);
-    venue_cache       
+    let (new_venue_data, failures) = split_failures(results);
+
+    let venue_cache = venue_cache.merge(new_venue_data.into_iter().flatten().collect(), |venue_season| (venue_season.venue.id, venue_season.year));
+    cache_venue(config, &venue_cache)?;
+    Ok((records(&venue_cache), failures))
 
     
 }
 
-fn get_coach_data (schedule_data: &Vec<GameMetaData>) -> Vec<CoachData> {
+fn get_coach_data (config: &BossConfig, transport: &dyn Transport, schedule_data: &Vec<GameMetaData>) -> Result<(Vec<CoachData>, Vec<Failure>), BossError> {
 
 
-    let mut coaches_cache = load_coach_data();
-    let games_cached: BTreeSet<u32> = coaches_cache.clone().into_iter()
+    let coaches_cache = CachedSet::new(load_coach_data(config)?, config.overwrite(Dataset::Coaches), |coaches| coaches.game_pk);
+    let games_cached: BTreeSet<u32> = coaches_cache.kept()
         .map (|coaches| coaches.game_pk)
         .collect()
         ;
@@ -432,20 +546,24 @@ fn get_coach_data (schedule_data: &Vec<GameMetaData>) -> Vec<CoachData> {
     let coach_urls: Vec<(u32, String, String)> = schedule_data.iter()
         .filter(|game| !games_cached.contains(&game.game_pk) && game.game_status == AbstractGameState::Final)
         .map(|game| (game.game_pk, game.coaches_home_url.clone(), game.coaches_away_url.clone()))
-        .take(20_000)
         .collect()
         ;
     
-    if coach_urls.len() == 0 {return coaches_cache};
+    if coach_urls.len() == 0 {return Ok((coaches_cache.into_records(), vec![]))};
 
-    let http_client = isahc::HttpClient::new()
-        .unwrap();
     
-    let json_data: Vec<(u32,String, String)> = coach_urls.into_par_iter()
-    .map(|url| (url.0, http_client.get(url.1).unwrap().text().unwrap(),  http_client.get(url.2).unwrap().text().unwrap()))
+    let results: Vec<Result<(u32,String, String), Failure>> = coach_urls.into_par_iter()
+    .map(|url| {
+        let download = || -> Result<(u32, String, String), BossError> {
+            Ok((url.0, transport.get(&url.1)?, transport.get(&url.2)?))
+        };
+        download().map_err(|error| Failure {dataset: Dataset::Coaches, key: url.0.to_string(), error})
+    })
     .collect()
     ;    
 
+    let (json_data, failures) = split_failures(results);
+
     let new_coach_data: Vec<CoachData> = json_data.into_par_iter()
         .map(|json| 
             {
@@ -463,12 +581,13 @@ fn get_coach_data (schedule_data: &Vec<GameMetaData>) -> Vec<CoachData> {
                 manager: None,
              };
 
+            let mut staff = vec![];
             let home_coaches = match coaches.1 {
-                Ok (coaches) => coaches.into(),
+                Ok (coaches) => {staff.extend(coaches.staff()); coaches.into()},
                 _ => no_coaches.clone(),
             };
             let away_coaches = match coaches.2 {
-                Ok (coaches) => coaches.into(),
+                Ok (coaches) => {staff.extend(coaches.staff()); coaches.into()},
                 _ => no_coaches.clone(),
             };
 
@@ -476,58 +595,60 @@ fn get_coach_data (schedule_data: &Vec<GameMetaData>) -> Vec<CoachData> {
                 game_pk: coaches.0,
                 home_coaches,
                 away_coaches,
+                staff,
         }})
         .collect();
 
-        coaches_cache.extend(new_coach_data);
-        cache_coach_data(&coaches_cache);
-        coaches_cache
+        let coaches_cache = coaches_cache.merge(new_coach_data, |coaches| coaches.game_pk);
+        cache_coach_data(config, &coaches_cache)?;
+        Ok((records(&coaches_cache), failures))
 
 }
 
-fn get_venue_xy_data (schedule_data: &Vec<GameMetaData>) -> Vec<VenueXY> {
+fn get_venue_xy_data (config: &BossConfig, transport: &dyn Transport, schedule_data: &Vec<GameMetaData>) -> Result<(Vec<VenueXY>, Vec<Failure>), BossError> {
 
     let x_y_venues: BTreeSet<u32> = schedule_data.iter()
             .map(|game| game.game_venue_id)
             .collect();
     
-    let mut venues_x_y = load_venue_x_y();
+    let venues_x_y = CachedSet::new(load_venue_x_y(config)?, config.overwrite(Dataset::VenueXY), |venue| venue.id);
 
-    let venues_cached: BTreeSet<u32> = venues_x_y
-        .iter()
+    let venues_cached: BTreeSet<u32> = venues_x_y.kept()
         .map(|venue| venue.id)
         .collect()
         ;
 
 
-    let venues_x_y_new: Vec<VenueXY> = x_y_venues
+    let results: Vec<Result<VenueXY, Failure>> = x_y_venues
         .iter()
         .filter(|venue| !venues_cached.contains(&venue))
         .map(|id| 
             {
                 let id = *id;
-                let (x,y) = crate::venues::get_svg(id);
-                VenueXY {
-                    id, x, y
+                match crate::venues::get_svg(transport, id) {
+                    Ok ((x,y)) => Ok(VenueXY {id, x, y}),
+                    Err (error) => Err(Failure {dataset: Dataset::VenueXY, key: id.to_string(), error}),
                 }
             }
         )
         .collect()
         ;
+
+    let (venues_x_y_new, failures) = split_failures(results);
     
-    if venues_x_y_new.len() == 0 {return venues_x_y};
+    if venues_x_y_new.len() == 0 {return Ok((venues_x_y.into_records(), failures))};
 
-    venues_x_y.extend(venues_x_y_new);
+    let venues_x_y = venues_x_y.merge(venues_x_y_new, |venue| venue.id);
     
-    cache_venue_x_y(&venues_x_y);
+    cache_venue_x_y(config, &venues_x_y)?;
 
-    venues_x_y
+    Ok((records(&venues_x_y), failures))
 }
 
-fn get_boxscore_data (schedule_data: &Vec<GameMetaData>) -> Vec<BoxScoreData> {
+fn get_boxscore_data (config: &BossConfig, transport: &dyn Transport, schedule_data: &Vec<GameMetaData>) -> Result<(Vec<BoxScoreData>, Vec<Failure>), BossError> {
 
-    let mut boxscore_cache = load_boxscore_data();
-    let games_cached: BTreeSet<u32> = boxscore_cache.clone().into_iter()
+    let boxscore_cache = CachedSet::new(load_boxscore_data(config)?, config.overwrite(Dataset::BoxScore), |boxscore| boxscore.game_pk);
+    let games_cached: BTreeSet<u32> = boxscore_cache.kept()
         .map (|boxscore| boxscore.game_pk)
         .collect()
         ;
@@ -539,15 +660,13 @@ fn get_boxscore_data (schedule_data: &Vec<GameMetaData>) -> Vec<BoxScoreData> {
         .filter(|game| !games_cached.contains(&game.game_pk) && game.game_status == AbstractGameState::Final)
         .filter(|game| !error_games.contains(&game.game_pk))
         .map(|game| (game.game_pk, game.game_url_boxscore.clone()))
-        .take(50_000)
         // .take(0)
         .collect()
         ;
 
-    if boxscore_urls.len() == 0 {return  boxscore_cache};
+    if boxscore_urls.len() == 0 {return Ok((boxscore_cache.into_records(), vec![]))};
     
 
-    let http_client = isahc::HttpClient::new().unwrap();
 
     // let start_time = std::time::Instant::now();
     // let http_client = isahc::HttpClient::builder()
@@ -555,39 +674,36 @@ fn get_boxscore_data (schedule_data: &Vec<GameMetaData>) -> Vec<BoxScoreData> {
     //     .build()
     //     .unwrap();
 
-    let json_data: Vec<(u32,String)> = boxscore_urls.into_par_iter()
-        .map(|url| (url.0, http_client.get(url.1).unwrap().text().unwrap()))
+    let results: Vec<Result<BoxScoreData, Failure>> = boxscore_urls.into_par_iter()
+        .map(|url| {
+            let download = || -> Result<BoxScoreData, BossError> {
+                let json = transport.get(&url.1)?;
+                let fixed_box = fix_boxscore(&json);
+                let boxscore_json: BoxScoreDe = serde_json::from_str(&fixed_box)?;
+                Ok(BoxScoreData {
+                    game_pk: url.0,    
+                    boxscore_data: boxscore_json.into(),
+                })
+            };
+            download().map_err(|error| Failure {dataset: Dataset::BoxScore, key: url.0.to_string(), error})
+        })
         .collect()
         ;
 
     // println!("Took {} seconds to pull the boxscore data", start_time.elapsed().as_secs());
 
-    let new_boxscore_data: Vec<BoxScoreData> = json_data.into_par_iter()
-    .map(|json| 
-            {
-            let fixed_box = fix_boxscore(&json.1);
-            let boxscore_json: Result<BoxScoreDe, serde_json::Error> = serde_json::from_str(&fixed_box);
-            (json.0, boxscore_json)
-            }
-        )
-        .inspect(|game| if game.1.is_err() {println!("Game pk: {} \n Error: {:?} ", game.0, game.1)})
-        .filter(|boxscore| boxscore.1.is_ok())
-        .map(|boxscore| BoxScoreData {
-            game_pk: boxscore.0,    
-            boxscore_data: boxscore.1.unwrap().into(),
-        })
-        .collect()
-        ;
-    boxscore_cache.extend(new_boxscore_data);
-    cache_boxscore_data(&boxscore_cache);
-    boxscore_cache
+    let (new_boxscore_data, failures) = split_failures(results);
+
+    let boxscore_cache = boxscore_cache.merge(new_boxscore_data, |boxscore| boxscore.game_pk);
+    cache_boxscore_data(config, &boxscore_cache)?;
+    Ok((records(&boxscore_cache), failures))
 
 }
 
-fn get_feed_live_data (schedule_data: &Vec<GameMetaData>) -> Vec<FeedData> {
+fn get_feed_live_data (config: &BossConfig, transport: &dyn Transport, schedule_data: &Vec<GameMetaData>) -> Result<(Vec<FeedData>, Vec<Failure>), BossError> {
 
-    let mut feed_live_cache = load_feed_live_data();
-    let games_cached: BTreeSet<u32> = feed_live_cache.clone().into_iter()
+    let feed_live_cache = CachedSet::new(load_feed_live_data(config)?, config.overwrite(Dataset::FeedLive), |game| game.game_pk);
+    let games_cached: BTreeSet<u32> = feed_live_cache.kept()
             .map (|game| game.game_pk)
             .collect()
             ;
@@ -600,53 +716,97 @@ fn get_feed_live_data (schedule_data: &Vec<GameMetaData>) -> Vec<FeedData> {
             .filter(|game| !games_cached.contains(&game.game_pk) && game.game_status == AbstractGameState::Final)
             .filter(|game| !bad_games.contains(&game.game_pk))
             .map(|game| game.game_url_feed_live.clone())
-            .take(500)
             .collect()
             ;
 
-    if feed_urls.len() == 0 {return feed_live_cache};
+    if feed_urls.len() == 0 {return Ok((feed_live_cache.into_records(), vec![]))};
 
     // dbg!(&feed_urls);
 
     // let start_time = std::time::Instant::now();
 
-    // Split up the pull into chunks of 4 urls and then spawn a separate thread for each chunk.
-    // Each chunk will then do an asynchronous request so that we get multi-threaded asynchronous
-    // network requests. I don't know the optimal chunk_size to use here.
-    let json_data: Vec<Result<String, std::io::Error>> = feed_urls.into_par_iter()
-        .chunks(20)
-        .map(|url| stream(url))
-        .flatten()
-        // .inspect(|game| if game.is_err() {println!("Error: {:?}", game)})
-        .collect()
-        ;
+    let json_data: Vec<(String, Result<String, BossError>)> = stream(transport, feed_urls);
 
     // println!("Took {} seconds to pull the feed live data", start_time.elapsed().as_secs());
 
-    let new_feed_live_data: Vec<FeedData> = json_data.into_par_iter()
-        .map(|json| 
+    let results: Vec<Result<FeedData, Failure>> = json_data.into_par_iter()
+        .map(|(url, json)| 
             {
-                let json_data = json.unwrap_or("".to_string());
-                let feed_data: Result<Feed, serde_json::Error> = serde_json::from_str(&json_data);
-                feed_data
+                let parse = || -> Result<FeedData, BossError> {
+                    let feed_data: Feed = serde_json::from_str(&json?)?;
+                    feed_data.try_into()
+                };
+                parse().map_err(|error| Failure {dataset: Dataset::FeedLive, key: url, error})
             }
         )
         // .inspect(|game| if game.is_err() {println!("Error: {:?}", game)})
-        .filter(|feed_data| feed_data.is_ok())
-        .map(|feed_data| feed_data.unwrap().into())
         .collect()
         ;
+
+    let (new_feed_live_data, failures) = split_failures(results);
     
-    feed_live_cache.extend(new_feed_live_data);
-    cache_feed_live_data(&feed_live_cache);
+    let feed_live_cache = feed_live_cache.merge(new_feed_live_data, |game| game.game_pk);
+    cache_feed_live_data(config, &feed_live_cache)?;
+
+    Ok((records(&feed_live_cache), failures))
+
+}
+
+/// Reads the Gameday XML files for each game from the local mirror, if there is one. These are local files, so we don't cache them.
+fn get_gameday_data (config: &BossConfig, feed_live_data: &[FeedData]) -> (Vec<GamedayData>, Vec<Failure>) {
+
+    let mirror = match &config.gameday_mirror {
+        Some (mirror) => mirror,
+        None => return (vec![], vec![]),
+    };
+
+    let results: Vec<Result<GamedayData, Failure>> = feed_live_data.par_iter()
+        .filter_map(|feed| 
+            match read_gameday_game(mirror, feed) {
+                Ok (game) => game.map(Ok),
+                Err (error) => Some (Err (Failure {dataset: Dataset::Gameday, key: feed.gameday_xml_folder.clone(), error})),
+            }
+        )
+        .collect()
+        ;
+
+    split_failures(results)
+}
+
+/// Adds what we've learned about players' weights and heights on this pull to the history: a snapshot of each player as the people
+/// endpoint had them when they were fetched, and the listings from the Gameday XML files, dated by the game.
+fn get_player_history (config: &BossConfig, schedule_data: &[GameMetaData], gameday_data: &[GamedayData]) -> Result<Vec<PlayerAttributes>, BossError> {
+
+    let mut history = PlayerHistory::from(load_player_history(config)?);
+
+    // Players cached before we kept timestamps don't tell us when they were pulled, so they aren't a dated snapshot.
+    for cached in load_player_data(config)?.iter().filter(|cached| cached.fetched_at > 0) {
+        let fetched_on = Date::from_day_number((cached.fetched_at / 86_400) as i32);
+        history.insert(from_people(&cached.record, fetched_on));
+    };
+
+    let game_dates: HashMap<u32, Date> = schedule_data.iter()
+        .map(|game| (game.game_pk, game.game_date))
+        .collect()
+        ;
 
-    feed_live_cache
+    for gameday in gameday_data {
+        if let Some (game_date) = game_dates.get(&gameday.game_pk) {
+            for attributes in from_gameday(gameday, *game_date) {
+                history.insert(attributes);
+            };
+        };
+    };
+
+    let records = history.records();
+    cache_player_history(config, &records)?;
+    Ok(records)
 
 }
 
-fn get_schedule_data (years: Vec<u16>, sport_ids: Vec<u32>) -> Vec<GameMetaData> {
+pub (crate) fn get_schedule_data (config: &BossConfig, transport: &dyn Transport, years: Vec<u16>, sport_ids: Vec<u32>) -> Result<(Vec<GameMetaData>, Vec<Failure>), BossError> {
 
-    let schedule_cache = load_schedule();  
+    let schedule_cache = load_schedule(config)?;  
     
     // Figure out which seasons need to be re-pulled. Any season/sport_id combination
     // that has any items that aren't "Final" should be re-pulled. We first map all the
@@ -669,14 +829,29 @@ fn get_schedule_data (years: Vec<u16>, sport_ids: Vec<u32>) -> Vec<GameMetaData>
         .collect()
         ;
 
-    let sched = Schedule::get_data(years, sport_ids, &season_sports);
+    let pulled: BTreeSet<(u16, u32)> = years.iter()
+        .flat_map(|year| sport_ids.iter().map(move |sport_id| (*year, *sport_id)))
+        .filter(|season| season_sports.get(season) != Some(&SeasonStatus::Complete))
+        .collect()
+        ;
+
+    let (sched, failures) = Schedule::get_data(transport, years, sport_ids, &season_sports);
     let games: Vec<GameMetaData> = sched.games.into_iter()
                                         .map(|game| game.into())
                                         .collect()
                                         ;
 
+    // Cached games are only replaced for the seasons that were pulled again. A season that failed to pull (or isn't in a read only
+    // archive) keeps what we had for it.
+    let failed: BTreeSet<String> = failures.iter()
+        .filter(|failure| failure.dataset == Dataset::Schedule)
+        .map(|failure| failure.key.clone())
+        .collect()
+        ;
+    let replaced = |year: u16, sport_id: u32| pulled.contains(&(year, sport_id)) && !failed.contains(&format!("{}/{}", year, sport_id));
+
     let mut schedule_data: Vec<GameMetaData> = schedule_cache.into_iter()
-                    .filter(|sched| season_sports.get(&(sched.game_date.year, sched.sport_id)) == Some(&SeasonStatus::Complete))
+                    .filter(|sched| !replaced(sched.game_date.year, sched.sport_id))
                     .collect::<Vec<GameMetaData>>()
                     ;
 
@@ -684,9 +859,9 @@ fn get_schedule_data (years: Vec<u16>, sport_ids: Vec<u32>) -> Vec<GameMetaData>
     schedule_data.sort();
     schedule_data.dedup_by_key(|game| game.game_pk);
 
-    cache_schedule(&schedule_data);
+    cache_schedule(config, &schedule_data)?;
     
     dbg!(&schedule_data.len());
-    schedule_data
+    Ok((schedule_data, failures))
 
 }
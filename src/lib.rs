@@ -30,31 +30,53 @@
 // Look at this and add as prior art : https://github.com/toddrob99/MLB-StatsAPI/blob/master/statsapi/endpoints.py
 
 
+pub mod archive;
+pub mod awards;
 pub mod boxscore;
 pub mod cache;
 pub mod coaches;
+pub mod columns;
+pub mod config;
 pub mod defense;
-// pub mod error;
+pub mod error;
+pub mod export;
 pub mod feed_live;
 pub mod game;
+pub mod gameday;
 pub mod get_data;
+pub mod manifest;
 pub mod metadata;
+#[cfg(feature = "parquet")]
+pub mod parquet_writer;
+pub mod pitch_fields;
+pub mod pitch_store;
 pub mod play_by_play;
+pub mod player_history;
 pub mod players;
+pub mod query;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
+pub mod retrosheet;
+pub mod retrosheet_reader;
+pub mod rosters;
 pub mod run_expectancy;
 pub mod schedule;
+pub mod scheduler;
 pub mod sports;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod statcast;
 pub mod team;
+pub mod transactions;
+pub mod transport;
 pub mod utils;
 pub mod venues;
 
+pub use crate::query::{Boss, BossData, stream_pitches};
+
 pub (crate) const BASE_URL: &'static str = "https://statsapi.mlb.com/api/v1/";
 pub (crate) const BASE_URL_V11: &'static str = "https://statsapi.mlb.com/api/v1.1/";
 
-/// CHUNK_SIZE controls how many files we request from the network at a time. This is to reduce the probability of network timeouts from flooding too many requests at once.
-/// This value will be used in stream_chunked. Only use this if you get a network timeout error.
-pub const CHUNK_SIZE: usize = 5;
-
 /// Base "x" value for pixel coordinates tracked in the hitData field. This is the default value for all fields, as per the SVG files
 /// If we don't have an svg file for a particular venue_id - we'll fill in the x value with this constant
 #[allow(unused)]
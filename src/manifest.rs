@@ -0,0 +1,351 @@
+//! Manifest of every game we've tried to turn into pitches, and how it went. Each game gets a status, the error text if it failed,
+//! how many times we've tried it and when we last did.
+//!
+//! Games that hit a network error are picked up again by the next pull on their own. Every other failure (no boxscore, no plays, bad
+//! json...) stays put until it's asked for, since pulling it again will usually give the same answer. Once the cause is fixed, such as
+//! the boxscore finally showing up or a parsing bug getting patched, mark those games for another go with `retry`:
+//!
+//! ```no_run
+//! use boss::config::BossConfig;
+//! use boss::manifest::{Manifest, GameStatus};
+//!
+//! let config = BossConfig::default();
+//! let mut manifest = Manifest::load(&config)?;
+//! let count = manifest.retry(&[GameStatus::NoBoxScore, GameStatus::ParseError]);
+//! manifest.save(&config)?;
+//! println!("{} games will be retried on the next pull", count);
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! The manifest replaces the old `games_processed.json` good/bad lists. The good games are carried over the first time the manifest
+//! is loaded. The bad games are not, since we don't know why they failed, so they're tried once more to find out.
+
+use crate::cache::{load_manifest, cache_manifest, load_games_processed, now};
+use crate::config::BossConfig;
+use crate::error::{BossError, Dataset, Failure, MetaDataType};
+use crate::game::Pitch;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// How processing a game went.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum GameStatus {
+    #[serde(rename="ok")]
+    Ok,
+    /// The game has no boxscore, so we don't know who played in it.
+    #[serde(rename="no-boxscore")]
+    NoBoxScore,
+    /// The play by play came back without any plays.
+    #[serde(rename="no-plays")]
+    NoPlays,
+    /// The responses couldn't be turned into pitches: bad json, a missing field or a value we don't know how to map.
+    #[serde(rename="parse-error")]
+    ParseError,
+    /// One of the players, umpires or coaches in the game has no bio.
+    #[serde(rename="missing-player-metadata")]
+    MissingPlayerMetadata,
+    /// We couldn't get the play by play at all. These are retried on every pull.
+    #[serde(rename="network-error")]
+    NetworkError,
+}
+
+impl GameStatus {
+    /// Every status, in the order they're reported.
+    pub fn all () -> Vec<GameStatus> {
+        vec![
+            GameStatus::Ok,
+            GameStatus::NoBoxScore,
+            GameStatus::NoPlays,
+            GameStatus::ParseError,
+            GameStatus::MissingPlayerMetadata,
+            GameStatus::NetworkError,
+        ]
+    }
+
+    /// The name used in the manifest file, such as "no-boxscore".
+    pub fn name (&self) -> &'static str {
+        match self {
+            GameStatus::Ok => "ok",
+            GameStatus::NoBoxScore => "no-boxscore",
+            GameStatus::NoPlays => "no-plays",
+            GameStatus::ParseError => "parse-error",
+            GameStatus::MissingPlayerMetadata => "missing-player-metadata",
+            GameStatus::NetworkError => "network-error",
+        }
+    }
+}
+
+impl Display for GameStatus {
+    fn fmt (&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for GameStatus {
+    type Err = BossError;
+
+    fn from_str (name: &str) -> Result<GameStatus, BossError> {
+        GameStatus::all().into_iter()
+            .find(|status| status.name() == name)
+            .ok_or_else(|| BossError::UnexpectedValue {field: "game status", value: name.to_string()})
+    }
+}
+
+impl From<&BossError> for GameStatus {
+    fn from (error: &BossError) -> GameStatus {
+        match error {
+            BossError::Network (_)
+            | BossError::HttpStatus {..}
+            | BossError::NotArchived {..}
+            | BossError::IO (_) => GameStatus::NetworkError,
+            BossError::MissingMetaData {meta_data: MetaDataType::BoxScore, ..} => GameStatus::NoBoxScore,
+            BossError::MissingMetaData {meta_data: MetaDataType::Player (_), ..} => GameStatus::MissingPlayerMetadata,
+            BossError::Failed (failure) => (&failure.error).into(),
+            _ => GameStatus::ParseError,
+        }
+    }
+}
+
+/// Where a game stands.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GameEntry {
+    pub game_pk: u32,
+    pub status: GameStatus,
+    pub error: Option<String>,
+    pub attempts: u32,
+    /// Seconds since the Unix epoch. 0 for games carried over from the old good/bad lists.
+    pub last_attempt: u64,
+    /// Set by `Manifest::retry`, cleared on the next attempt.
+    #[serde(default)]
+    pub retry: bool,
+}
+
+impl GameEntry {
+    /// Whether the next pull should process this game.
+    pub fn needs_processing (&self) -> bool {
+        self.retry || self.status == GameStatus::NetworkError
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    games: BTreeMap<u32, GameEntry>,
+}
+
+impl Manifest {
+    /// Loads the manifest from the cache folder, or starts one from the old good/bad lists if there isn't one yet.
+    pub fn load (config: &BossConfig) -> Result<Manifest, BossError> {
+        let mut entries = load_manifest(config)?;
+
+        if entries.is_empty() {
+            entries = load_games_processed(config)?.into_iter()
+                .map(|game_pk| GameEntry {
+                    game_pk,
+                    status: GameStatus::Ok,
+                    error: None,
+                    attempts: 1,
+                    last_attempt: 0,
+                    retry: false,
+                })
+                .collect();
+        }
+
+        Ok(Manifest {
+            games: entries.into_iter().map(|entry| (entry.game_pk, entry)).collect(),
+        })
+    }
+
+    pub fn save (&self, config: &BossConfig) -> Result<(), BossError> {
+        let entries: Vec<&GameEntry> = self.games.values().collect();
+        cache_manifest(config, &entries)
+    }
+
+    pub fn get (&self, game_pk: u32) -> Option<&GameEntry> {
+        self.games.get(&game_pk)
+    }
+
+    pub fn entries (&self) -> impl Iterator<Item = &GameEntry> {
+        self.games.values()
+    }
+
+    /// Every game that didn't come out as `Ok`.
+    pub fn failed (&self) -> impl Iterator<Item = &GameEntry> {
+        self.games.values().filter(|entry| entry.status != GameStatus::Ok)
+    }
+
+    /// Number of games with each status.
+    pub fn counts (&self) -> BTreeMap<GameStatus, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in self.games.values() {
+            *counts.entry(entry.status).or_insert(0) += 1;
+        };
+        counts
+    }
+
+    /// Whether the next pull should process a game: it's new, it hit a network error, or it was marked for a retry.
+    pub fn needs_processing (&self, game_pk: u32) -> bool {
+        self.games.get(&game_pk).map(|entry| entry.needs_processing()).unwrap_or(true)
+    }
+
+    /// Marks every game with one of these statuses to be processed again on the next pull. Returns how many games were marked.
+    pub fn retry (&mut self, statuses: &[GameStatus]) -> usize {
+        self.games.values_mut()
+            .filter(|entry| statuses.contains(&entry.status))
+            .map(|entry| entry.retry = true)
+            .count()
+    }
+
+    /// Records one attempt at a game.
+    pub fn record (&mut self, game_pk: u32, status: GameStatus, error: Option<String>) {
+        let entry = self.games.entry(game_pk).or_insert(GameEntry {
+            game_pk,
+            status,
+            error: None,
+            attempts: 0,
+            last_attempt: 0,
+            retry: false,
+        });
+        entry.status = status;
+        entry.error = error;
+        entry.attempts += 1;
+        entry.last_attempt = now();
+        entry.retry = false;
+    }
+
+    /// Records the outcome of a batch of games: every game with pitches is `Ok`, every failure gets the status for its error and any
+    /// game that came back with neither had no plays.
+    pub fn record_batch (&mut self, requested: &BTreeSet<u32>, pitches: &[Pitch], failures: &[Failure]) {
+        let games_returned: BTreeSet<u32> = pitches.iter().map(|pitch| pitch.game_pk).collect();
+
+        let mut games_failed = BTreeSet::new();
+        for failure in failures.iter().filter(|failure| failure.dataset == Dataset::PlayByPlay) {
+            if let Ok (game_pk) = failure.key.parse::<u32>() {
+                self.record(game_pk, (&failure.error).into(), Some(failure.error.to_string()));
+                games_failed.insert(game_pk);
+            }
+        };
+
+        for game_pk in requested {
+            if games_returned.contains(game_pk) {
+                self.record(*game_pk, GameStatus::Ok, None);
+            }
+            else if !games_failed.contains(game_pk) {
+                self.record(*game_pk, GameStatus::NoPlays, None);
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config (name: &str) -> BossConfig {
+        let dir = std::env::temp_dir().join(format!("boss_manifest_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = BossConfig::new(dir);
+        std::fs::create_dir_all(&config.cache_dir).unwrap();
+        config
+    }
+
+    fn failure (game_pk: u32, error: BossError) -> Failure {
+        Failure {dataset: Dataset::PlayByPlay, key: game_pk.to_string(), error}
+    }
+
+    #[test]
+    fn status_from_error () {
+        let status = |error: BossError| GameStatus::from(&error);
+        let not_found = || std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+
+        assert_eq!(status(BossError::HttpStatus {url: "url".to_string(), status: 503}), GameStatus::NetworkError);
+        assert_eq!(status(BossError::NotArchived {url: "url".to_string()}), GameStatus::NetworkError);
+        assert_eq!(status(BossError::IO(not_found())), GameStatus::NetworkError);
+        assert_eq!(status(BossError::MissingMetaData {game_pk: 1, meta_data: MetaDataType::BoxScore}), GameStatus::NoBoxScore);
+        assert_eq!(status(BossError::MissingMetaData {game_pk: 1, meta_data: MetaDataType::Player(2)}), GameStatus::MissingPlayerMetadata);
+        assert_eq!(status(BossError::MissingMetaData {game_pk: 1, meta_data: MetaDataType::Schedule}), GameStatus::ParseError);
+        assert_eq!(status(serde_json::from_str::<u32>("x").unwrap_err().into()), GameStatus::ParseError);
+        assert_eq!(status(BossError::MissingField {id: 1, field: "balls"}), GameStatus::ParseError);
+        assert_eq!(status(BossError::UnexpectedValue {field: "code", value: "?".to_string()}), GameStatus::ParseError);
+        // A wrapped failure gets the status of the error inside it
+        assert_eq!(status(BossError::Failed(Box::new(failure(1, BossError::IO(not_found()))))), GameStatus::NetworkError);
+    }
+
+    #[test]
+    fn record_batch () {
+        let mut manifest = Manifest::default();
+        let requested: BTreeSet<u32> = vec![1, 2, 3, 4].into_iter().collect();
+        let pitches = vec![Pitch {game_pk: 1, ..Pitch::default()}, Pitch {game_pk: 1, ..Pitch::default()}];
+        let failures = vec![
+            failure(2, BossError::MissingMetaData {game_pk: 2, meta_data: MetaDataType::BoxScore}),
+            failure(3, BossError::HttpStatus {url: "url".to_string(), status: 500}),
+            // Only play by play failures are about a game
+            Failure {dataset: Dataset::BoxScore, key: "4".to_string(), error: BossError::MissingField {id: 4, field: "teams"}},
+        ];
+
+        manifest.record_batch(&requested, &pitches, &failures);
+
+        let status = |manifest: &Manifest, game_pk: u32| manifest.get(game_pk).map(|entry| entry.status);
+        assert_eq!(status(&manifest, 1), Some(GameStatus::Ok));
+        assert_eq!(status(&manifest, 2), Some(GameStatus::NoBoxScore));
+        assert_eq!(status(&manifest, 3), Some(GameStatus::NetworkError));
+        assert_eq!(status(&manifest, 4), Some(GameStatus::NoPlays));
+        assert_eq!(manifest.get(1).unwrap().error, None);
+        assert!(manifest.get(2).unwrap().error.is_some());
+
+        manifest.record_batch(&requested, &pitches, &[]);
+        assert_eq!(manifest.get(1).unwrap().attempts, 2);
+        assert_eq!(status(&manifest, 2), Some(GameStatus::NoPlays));
+    }
+
+    #[test]
+    fn retry_only_marks_the_statuses_asked_for () {
+        let mut manifest = Manifest::default();
+        manifest.record(1, GameStatus::Ok, None);
+        manifest.record(2, GameStatus::NoBoxScore, Some("no boxscore".to_string()));
+        manifest.record(3, GameStatus::ParseError, Some("bad json".to_string()));
+        manifest.record(4, GameStatus::NetworkError, Some("timeout".to_string()));
+
+        assert!(!manifest.needs_processing(1));
+        assert!(!manifest.needs_processing(2));
+        assert!(!manifest.needs_processing(3));
+        assert!(manifest.needs_processing(4));
+        assert!(manifest.needs_processing(5));
+
+        assert_eq!(manifest.retry(&[GameStatus::NoBoxScore]), 1);
+        assert!(!manifest.needs_processing(1));
+        assert!(manifest.needs_processing(2));
+        assert!(!manifest.needs_processing(3));
+
+        assert_eq!(manifest.retry(&[GameStatus::Ok, GameStatus::ParseError]), 2);
+        assert!(manifest.needs_processing(1));
+        assert!(manifest.needs_processing(3));
+
+        // The next attempt clears the flag
+        manifest.record(2, GameStatus::NoBoxScore, None);
+        assert!(!manifest.needs_processing(2));
+        assert_eq!(manifest.failed().map(|entry| entry.game_pk).collect::<Vec<u32>>(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn load_carries_over_the_good_list () {
+        let config = temp_config("legacy");
+        std::fs::write(config.cache_file("games_processed.json"), r#"{"good": [1, 2], "bad": [3]}"#).unwrap();
+
+        let mut manifest = Manifest::load(&config).unwrap();
+        assert_eq!(manifest.entries().map(|entry| entry.game_pk).collect::<Vec<u32>>(), vec![1, 2]);
+        let entry = manifest.get(1).unwrap();
+        assert_eq!((entry.status, entry.attempts, entry.last_attempt, entry.retry), (GameStatus::Ok, 1, 0, false));
+        // The bad games are tried again
+        assert!(manifest.needs_processing(3));
+
+        // Once there's a manifest, the old lists aren't read again
+        manifest.record(3, GameStatus::NoPlays, None);
+        manifest.save(&config).unwrap();
+        std::fs::write(config.cache_file("games_processed.json"), r#"{"good": [9], "bad": []}"#).unwrap();
+
+        let manifest = Manifest::load(&config).unwrap();
+        assert_eq!(manifest.entries().map(|entry| entry.game_pk).collect::<Vec<u32>>(), vec![1, 2, 3]);
+        assert_eq!(manifest.get(3).map(|entry| entry.status), Some(GameStatus::NoPlays));
+    }
+}
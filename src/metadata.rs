@@ -14,6 +14,10 @@ use crate::coaches::CoachData;
 use crate::team::{Team, TeamData};
 use crate::players::Player;
 use crate::feed_live::FeedData;
+use crate::gameday::GamedayData;
+use crate::player_history::{PlayerAttributes, PlayerHistory};
+use crate::transactions::{Transaction, PlayerTransactions};
+use crate::awards::{Award, PlayerAwards};
 
 
 
@@ -26,6 +30,10 @@ pub struct VecMetaDataInputs {
     pub schedule:   Vec<GameMetaData>,
     pub coaches:    Vec<CoachData>,
     pub feed_data:  Vec<FeedData>,
+    pub gameday:    Vec<GamedayData>,
+    pub player_history: Vec<PlayerAttributes>,
+    pub transactions: Vec<Transaction>,
+    pub awards:     Vec<Award>,
     pub teams:      Vec<TeamData>,
     pub players:    Vec<Player>,
 }
@@ -46,6 +54,10 @@ pub struct MetaData {
     pub teams:          HashMap<(ID, Year),         Team>,
     pub players:        HashMap<ID,                 Player>,
     pub feed:           HashMap<GamePK,             FeedData>,
+    pub gameday:        HashMap<GamePK,             GamedayData>,
+    pub player_history: PlayerHistory,
+    pub transactions:   PlayerTransactions,
+    pub awards:         PlayerAwards,
     pub re_288_default: HashMap<(u8, u8, u8, u8),   f32>,
 }
 
@@ -113,6 +125,17 @@ impl From<VecMetaDataInputs> for MetaData {
             .collect()
             ;
 
+        let gameday: HashMap<u32, GamedayData> = meta.gameday
+            .clone()
+            .into_iter()
+            .map (|g| (g.game_pk, g))
+            .collect()
+            ;
+
+        let player_history = PlayerHistory::from(meta.player_history.clone());
+        let transactions = PlayerTransactions::from(meta.transactions.clone());
+        let awards = PlayerAwards::from(meta.awards.clone());
+
         let re_288_default: HashMap<(u8, u8, u8, u8), f32> =
             crate::run_expectancy::RE288_DEFAULT.iter()
             .map (|re| ((re.balls, re.strikes, re.base_value, re.outs), re.run_expectancy))
@@ -128,6 +151,10 @@ impl From<VecMetaDataInputs> for MetaData {
             teams,
             players,
             feed,
+            gameday,
+            player_history,
+            transactions,
+            awards,
             re_288_default,
         }
     }
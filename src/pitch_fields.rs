@@ -0,0 +1,223 @@
+//! Named groups of `Pitch` fields, for exporting only part of the ~200 columns. A selection can mix whole groups with single
+//! columns, and the columns always come out in the same order as the full export:
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::export::{export_pitch_columns, ExportFormat};
+//! use boss::pitch_fields::{FieldGroup, PitchColumns};
+//!
+//! let data = Boss::new().years(2019 ..= 2019).sports(vec![1]).fetch()?;
+//! let columns = PitchColumns::new()
+//!     .groups(vec![FieldGroup::Core, FieldGroup::PitchTracking])
+//!     .columns(vec!["batter_age", "venue_name"]);
+//! export_pitch_columns(&data.pitches, ExportFormat::Csv, &columns, "nightly.csv")?;
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! Every field belongs to exactly one group. Core holds what most analysis starts from: the game, the count and base/out state,
+//! who's batting, pitching and fielding, and what happened on the pitch.
+
+use crate::columns;
+use crate::error::BossError;
+use crate::game::Pitch;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum FieldGroup {
+    /// Game, level of play, inning, count, base/out state, RE288, the batter/pitcher/fielders and the result of the pitch.
+    Core,
+    /// Velocity, movement, spin, release and location of the pitch, the pitch type and the batter's strike zone.
+    PitchTracking,
+    /// Batted ball data and who fielded the ball.
+    HitData,
+    /// Spin axis, hit distance and expected stats merged in from Baseball Savant.
+    Statcast,
+    BatterBio,
+    PitcherBio,
+    /// Managers and hitting/pitching coaches on both sides.
+    Coaches,
+    /// The home plate umpire, and who was working the bases.
+    Umpire,
+    Venue,
+    Weather,
+    /// Official scorer and datacaster.
+    Scorer,
+}
+
+impl FieldGroup {
+    pub fn all () -> Vec<FieldGroup> {
+        vec![
+            FieldGroup::Core,
+            FieldGroup::PitchTracking,
+            FieldGroup::HitData,
+            FieldGroup::Statcast,
+            FieldGroup::BatterBio,
+            FieldGroup::PitcherBio,
+            FieldGroup::Coaches,
+            FieldGroup::Umpire,
+            FieldGroup::Venue,
+            FieldGroup::Weather,
+            FieldGroup::Scorer,
+        ]
+    }
+
+    /// The name used on the command line, such as "pitch-tracking".
+    pub fn name (&self) -> &'static str {
+        match self {
+            FieldGroup::Core => "core",
+            FieldGroup::PitchTracking => "pitch-tracking",
+            FieldGroup::HitData => "hit-data",
+            FieldGroup::Statcast => "statcast",
+            FieldGroup::BatterBio => "batter-bio",
+            FieldGroup::PitcherBio => "pitcher-bio",
+            FieldGroup::Coaches => "coaches",
+            FieldGroup::Umpire => "umpire",
+            FieldGroup::Venue => "venue",
+            FieldGroup::Weather => "weather",
+            FieldGroup::Scorer => "scorer",
+        }
+    }
+
+    /// The `Pitch` fields in the group.
+    pub fn fields (&self) -> &'static [&'static str] {
+        match self {
+            FieldGroup::Core => &[
+                "half_inning", "num_plate_appearance", "num_inning", "start_time",
+                "catcher_id", "catcher_name", "first_base_id", "first_base_name", "second_base_id", "second_base_name",
+                "short_stop_id", "short_stop_name", "third_base_id", "third_base_name", "left_field_id", "left_field_name",
+                "center_field_id", "center_field_name", "right_field_id", "right_field_name",
+                "pitcher", "pitcher_team_id", "pitcher_team_name", "pitcher_parent_team_id", "pitcher_parent_team_name", "pitcher_throws",
+                "pitcher_name",
+                "batter", "batter_name", "batter_team_id", "batter_team_name", "batter_parent_team_id", "batter_parent_team_name",
+                "batter_stands", "batter_stands_desc", "batter_pos", "batter_batting_order",
+                "pitch_num_plate_appearance", "pitch_num_inning", "pitch_num_game", "preceded_by_pickoff", "double_play_opportunity",
+                "balls_start", "balls_end", "strikes_start", "strikes_end", "outs_start", "outs_end", "base_value_start", "base_value_end",
+                "runs_scored", "re_288_batter_responsible", "re_288_start", "re_288_end", "re_288_val",
+                "in_play", "swing", "swing_and_miss", "foul", "bunt",
+                "in_play_result", "in_play_1b", "in_play_2b", "in_play_3b", "in_play_hr", "strikeout", "walk",
+                "sport_id", "sport_code", "sport_name", "sport_abbr", "sport_affilliation", "sport_level_of_play",
+                "league_name_home", "league_name_away", "division_name_home", "division_name_away",
+                "game_pk", "game_type", "game_type_desc", "game_date", "game_year", "game_month", "game_status",
+                "game_attendance", "game_first_pitch",
+            ],
+            FieldGroup::PitchTracking => &[
+                "strike_zone_bottom", "strike_zone_top",
+                "pitch_speed_start", "pitch_speed_end", "pitch_break_length", "pitch_break_y", "pitch_spin_rate", "pitch_spin_direction",
+                "pitch_plate_time", "pitch_extension", "pitch_pixels_x", "pitch_pixels_y", "pitch_a_x", "pitch_a_y", "pitch_a_z",
+                "pitch_pfx_x", "pitch_pfx_z", "pitch_p_x", "pitch_p_z", "pitch_v_x0", "pitch_v_y0", "pitch_v_z0",
+                "pitch_x0", "pitch_y0", "pitch_z0", "pitch_type_code", "pitch_type_desc",
+            ],
+            FieldGroup::HitData => &[
+                "fielded_by_id", "fielded_by_pos", "fielded_by_name",
+                "hit_data_coord_x", "hit_data_coord_y", "hit_data_trajectory", "hit_data_contact_quality", "hit_data_launch_angle",
+                "hit_data_exit_velocity", "hit_data_total_distance", "hit_data_spray_angle", "hit_data_calc_distance",
+            ],
+            FieldGroup::Statcast => &[
+                "statcast_spin_axis", "statcast_hit_distance", "statcast_launch_speed_angle", "statcast_xba", "statcast_xwoba",
+                "statcast_woba_value",
+            ],
+            FieldGroup::BatterBio => &[
+                "batter_dob", "batter_mlb_debut_date", "batter_age", "batter_birth_city", "batter_birth_state_province",
+                "batter_birth_country", "batter_height_str", "batter_height_in", "batter_weight", "batter_draft_school_name",
+                "batter_draft_year", "batter_draft_pick_number", "batter_fangraphs_id", "batter_retrosheet_id",
+                "batter_highschool_city", "batter_highschool_prov_state", "batter_college_name", "batter_days_since_il_return",
+                "batter_days_since_trade", "batter_all_star_selections", "batter_bats", "batter_bats_desc",
+            ],
+            FieldGroup::PitcherBio => &[
+                "pitcher_throws_desc", "pitcher_dob", "pitcher_mlb_debut_date", "pitcher_age", "pitcher_birth_city",
+                "pitcher_birth_state_province", "pitcher_birth_country", "pitcher_height_str", "pitcher_height_in", "pitcher_weight",
+                "pitcher_draft_school_name", "pitcher_draft_year", "pitcher_draft_pick_number", "pitcher_fangraphs_id",
+                "pitcher_retrosheet_id", "pitcher_highschool_city", "pitcher_highschool_prov_state", "pitcher_college_name",
+                "pitcher_days_since_il_return", "pitcher_days_since_trade", "pitcher_all_star_selections",
+            ],
+            FieldGroup::Coaches => &[
+                "batting_coach", "batting_coach_name", "batting_coach_dob", "batting_coach_age", "batting_coach_mlb_exp",
+                "batting_manager", "batting_manager_name", "batting_manager_dob", "batting_manager_age", "batting_manager_mlb_exp",
+                "pitching_coach", "pitching_coach_name", "pitching_coach_dob", "pitching_coach_age", "pitching_coach_mlb_exp",
+                "pitching_manager", "pitching_manager_name", "pitching_manager_dob", "pitching_manager_age", "pitching_manager_mlb_exp",
+            ],
+            FieldGroup::Umpire => &[
+                "hp_umpire_id", "hp_umpire_name", "hp_umpire_dob", "hp_umpire_age", "hp_umpire_height", "hp_umpire_height_str",
+                "first_base_umpire_id", "second_base_umpire_id", "third_base_umpire_id", "crew_chief_umpire_id",
+            ],
+            FieldGroup::Venue => &[
+                "venue_id", "venue_home_plate_x", "venue_home_plate_y", "venue_name", "venue_city", "venue_state", "venue_state_abbr",
+                "venue_time_zone", "venue_time_zone_offset", "venue_capacity", "venue_surface", "venue_roof",
+                "venue_left_line", "venue_left", "venue_left_center", "venue_center", "venue_right_center", "venue_right",
+                "venue_right_line", "venue_retrosheet_id", "venue_latitude", "venue_longitude",
+            ],
+            FieldGroup::Weather => &[
+                "game_weather_temp_f", "game_weather_temp_c", "game_weather_condition", "game_wind_speed_mph", "game_wind_direction",
+            ],
+            FieldGroup::Scorer => &[
+                "official_scorer_id", "official_scorer_name", "primary_datacaster_id", "primary_datacaster_name",
+            ],
+        }
+    }
+}
+
+impl Display for FieldGroup {
+    fn fmt (&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for FieldGroup {
+    type Err = BossError;
+
+    fn from_str (name: &str) -> Result<FieldGroup, BossError> {
+        FieldGroup::all().into_iter()
+            .find(|group| group.name() == name)
+            .ok_or_else(|| BossError::UnexpectedValue {field: "field group", value: name.to_string()})
+    }
+}
+
+/// The `Pitch` columns to export: whole groups, single columns, or both.
+#[derive(Debug, Clone, Default)]
+pub struct PitchColumns {
+    groups: BTreeSet<FieldGroup>,
+    columns: BTreeSet<String>,
+}
+
+impl PitchColumns {
+    /// Nothing selected yet.
+    pub fn new () -> PitchColumns {
+        PitchColumns::default()
+    }
+
+    /// Every column, same as the full export.
+    pub fn all () -> PitchColumns {
+        PitchColumns::new().groups(FieldGroup::all())
+    }
+
+    pub fn groups <G: IntoIterator<Item = FieldGroup>> (mut self, groups: G) -> PitchColumns {
+        self.groups.extend(groups);
+        self
+    }
+
+    /// Single columns, by field name. Names are checked when the selection is used.
+    pub fn columns <S: Into<String>, C: IntoIterator<Item = S>> (mut self, columns: C) -> PitchColumns {
+        self.columns.extend(columns.into_iter().map(Into::into));
+        self
+    }
+
+    /// The selected field names, in `Pitch` order. Fails on a column name `Pitch` doesn't have, or if nothing is selected.
+    pub fn names (&self) -> Result<Vec<&'static str>, BossError> {
+        let schema = columns::schema_of::<Pitch>()?;
+
+        if let Some (unknown) = self.columns.iter().find(|column| !schema.iter().any(|(name, _)| name == column)) {
+            return Err(BossError::UnexpectedValue {field: "pitch column", value: unknown.to_string()});
+        };
+
+        let names: Vec<&'static str> = schema.into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| self.columns.contains(*name) || self.groups.iter().any(|group| group.fields().contains(name)))
+            .collect();
+
+        if names.is_empty() {
+            return Err(BossError::UnexpectedValue {field: "pitch column", value: "no columns selected".to_string()});
+        };
+        Ok(names)
+    }
+}
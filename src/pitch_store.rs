@@ -0,0 +1,657 @@
+//! Compact binary store for the pitch by pitch data. The CSV output repeats every player name, venue and enum label on every
+//! pitch; the store keeps the pitches column by column, one game per block, with enums as one byte codes and each block deflated.
+//! Each block starts with a small uncompressed header (game_pk, sport_id, season and the players in the game), so a scan for a
+//! handful of games or players skips straight past everything else without decompressing it.
+//!
+//! ```text
+//! file     = header block*
+//! header   = "BOSSPTCH" | version u16 | column count u16 | column*
+//! column   = name | kind u8 | variant count u16 | variant name*      (variants only for enum columns)
+//! block    = game_pk u32 | sport_id u32 | season u16 | rows u32 | player count u16 | player id u32* | body length u32 | body
+//! body     = deflate(for each column: null bitmap | each non-null value)
+//! ```
+//!
+//! Numbers are little endian and strings are a u32 length followed by UTF-8. Enum values are stored as their position in the
+//! variant list from the header, so an enum column can hold at most 256 different values.
+//!
+//! The header describes every column, and the reader decodes with the file's own header, so files from an older `Pitch` still read
+//! as long as the fields that were added since are `Option`s. Appending needs the file's schema to match the current one exactly,
+//! `migrate_pitch_store` rewrites an older store with the current schema first.
+//!
+//! ```no_run
+//! use boss::pitch_store::{PitchStore, PitchFilter};
+//!
+//! let store = PitchStore::open("cache/pitches.boss")?;
+//! let pitches = store.scan(&PitchFilter::new().years(2019 ..= 2019).sports(vec![1]).players(vec![545361]))?;
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+
+use crate::columns::{self, Cell, ColumnKind, ColumnValues};
+use crate::error::BossError;
+use crate::game::Pitch;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the file layout, bumped whenever the layout changes.
+pub const PITCH_STORE_VERSION: u16 = 1;
+
+const MAGIC: &[u8; 8] = b"BOSSPTCH";
+
+/// A column as described by the file header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreColumn {
+    pub name: String,
+    pub kind: ColumnKind,
+    /// The variant names for enum columns, in code order. Empty for every other column.
+    pub variants: Vec<String>,
+}
+
+/// The uncompressed header of one game's block.
+#[derive(Debug, Clone)]
+pub struct GameBlock {
+    pub game_pk: u32,
+    pub sport_id: u32,
+    pub season: u16,
+    pub rows: u32,
+    /// Every batter, pitcher and fielder in the game, sorted.
+    pub player_ids: Vec<u32>,
+}
+
+/// Which pitches a scan returns. Each part left unset matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct PitchFilter {
+    pub game_pks: Option<BTreeSet<u32>>,
+    pub years: Option<BTreeSet<u16>>,
+    pub sport_ids: Option<BTreeSet<u32>>,
+    pub player_ids: Option<BTreeSet<u32>>,
+}
+
+impl PitchFilter {
+    pub fn new () -> PitchFilter {
+        PitchFilter::default()
+    }
+
+    pub fn games <G: IntoIterator<Item = u32>> (mut self, game_pks: G) -> PitchFilter {
+        self.game_pks = Some(game_pks.into_iter().collect());
+        self
+    }
+
+    pub fn years <Y: IntoIterator<Item = u16>> (mut self, years: Y) -> PitchFilter {
+        self.years = Some(years.into_iter().collect());
+        self
+    }
+
+    pub fn sports <S: IntoIterator<Item = u32>> (mut self, sport_ids: S) -> PitchFilter {
+        self.sport_ids = Some(sport_ids.into_iter().collect());
+        self
+    }
+
+    /// Only pitches where one of these players was the batter, the pitcher or one of the fielders.
+    pub fn players <P: IntoIterator<Item = u32>> (mut self, player_ids: P) -> PitchFilter {
+        self.player_ids = Some(player_ids.into_iter().collect());
+        self
+    }
+
+    /// Whether a block could hold any matching pitches.
+    pub fn matches_game (&self, block: &GameBlock) -> bool {
+        in_set(&self.game_pks, block.game_pk)
+            && in_set(&self.years, block.season)
+            && in_set(&self.sport_ids, block.sport_id)
+            && self.player_ids.as_ref()
+                .map(|players| block.player_ids.iter().any(|id| players.contains(id)))
+                .unwrap_or(true)
+    }
+
+    pub fn matches_pitch (&self, pitch: &Pitch) -> bool {
+        match &self.player_ids {
+            Some (players) => player_ids(pitch).iter().flatten().any(|id| players.contains(id)),
+            None => true,
+        }
+    }
+}
+
+// A block header, with its compressed body if it was read.
+type Block = (GameBlock, Option<Vec<u8>>);
+
+/// A pitch store on disk. Opening it only reads the header, every scan reads through the file again.
+#[derive(Debug, Clone)]
+pub struct PitchStore {
+    path: PathBuf,
+    version: u16,
+    columns: Vec<StoreColumn>,
+    data_start: u64,
+}
+
+impl PitchStore {
+    pub fn open <P: AsRef<Path>> (path: P) -> Result<PitchStore, BossError> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+
+        let magic: [u8; 8] = read_bytes(&mut reader)?;
+        if &magic != MAGIC {return Err(corrupt("not a pitch store"))};
+
+        let version = u16::from_le_bytes(read_bytes(&mut reader)?);
+        if version == 0 || version > PITCH_STORE_VERSION {
+            return Err(BossError::UnexpectedValue {field: "pitch store version", value: version.to_string()});
+        };
+
+        let count = u16::from_le_bytes(read_bytes(&mut reader)?);
+        let mut columns = Vec::with_capacity(count as usize);
+        for _ in 0 .. count {
+            let name = read_string(&mut reader)?;
+            let kind = kind_from_code(u8::from_le_bytes(read_bytes(&mut reader)?))?;
+            let variant_count = u16::from_le_bytes(read_bytes(&mut reader)?);
+            let variants = (0 .. variant_count).map(|_| read_string(&mut reader)).collect::<Result<Vec<String>, BossError>>()?;
+            columns.push(StoreColumn {name, kind, variants});
+        };
+
+        let data_start = reader.stream_position()?;
+        Ok(PitchStore {path, version, columns, data_start})
+    }
+
+    pub fn version (&self) -> u16 {
+        self.version
+    }
+
+    pub fn columns (&self) -> &[StoreColumn] {
+        &self.columns
+    }
+
+    /// The header of every game in the store, without decompressing any of them.
+    pub fn games (&self) -> Result<Vec<GameBlock>, BossError> {
+        Ok(self.blocks(|_| false)?.into_iter().map(|(block, _)| block).collect())
+    }
+
+    /// Every pitch that matches the filter, in the order the games were written.
+    pub fn scan (&self, filter: &PitchFilter) -> Result<Vec<Pitch>, BossError> {
+        let bodies: Vec<(GameBlock, Vec<u8>)> = self.blocks(|block| filter.matches_game(block))?.into_iter()
+            .filter_map(|(block, body)| body.map(|body| (block, body)))
+            .collect();
+
+        let games = bodies.into_par_iter()
+            .map(|(block, body)| self.decode_block(&block, &body))
+            .collect::<Result<Vec<Vec<Pitch>>, BossError>>()?;
+
+        Ok(games.into_iter().flatten().filter(|pitch| filter.matches_pitch(pitch)).collect())
+    }
+
+    // Reads every block header, along with the compressed body of the blocks that are wanted. The rest are skipped over.
+    fn blocks <F: Fn(&GameBlock) -> bool> (&self, wanted: F) -> Result<Vec<Block>, BossError> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(self.data_start))?;
+
+        let mut blocks = Vec::new();
+        while !reader.fill_buf()?.is_empty() {
+            let block = read_block_header(&mut reader)?;
+            let length = u32::from_le_bytes(read_bytes(&mut reader)?);
+
+            let body = if wanted(&block) {
+                let mut body = vec![0; length as usize];
+                reader.read_exact(&mut body)?;
+                Some(body)
+            }
+            else {
+                reader.seek_relative(length as i64)?;
+                None
+            };
+            blocks.push((block, body));
+        };
+
+        Ok(blocks)
+    }
+
+    fn decode_block (&self, block: &GameBlock, body: &[u8]) -> Result<Vec<Pitch>, BossError> {
+        let mut decoder = DeflateDecoder::new(body);
+        let rows = block.rows as usize;
+
+        let mut columns = Vec::with_capacity(self.columns.len());
+        for column in self.columns.iter() {
+            columns.push(decode_column(&mut decoder, column, rows)?.into_iter());
+        };
+
+        (0 .. rows)
+            .map(|_| {
+                let row = self.columns.iter().zip(columns.iter_mut())
+                    .map(|(column, cells)| (column.name.as_str(), cells.next().unwrap_or(Cell::Null)))
+                    .collect();
+                columns::from_row(row)
+            })
+            .collect()
+    }
+}
+
+/// Writes the pitches to a new store at `path`, replacing anything already there.
+pub fn write_pitch_store <P: AsRef<Path>> (path: P, pitches: &[Pitch]) -> Result<(), BossError> {
+    let columns = current_columns()?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer, &columns)?;
+    write_games(&mut writer, &columns, pitches)
+}
+
+/// Adds the pitches to the end of the store at `path`, starting a new store if there isn't one. A game already in the store isn't
+/// replaced, so only append games that haven't been stored yet.
+pub fn append_pitch_store <P: AsRef<Path>> (path: P, pitches: &[Pitch]) -> Result<(), BossError> {
+    let path = path.as_ref();
+    let columns = current_columns()?;
+
+    if !path.exists() {return write_pitch_store(path, pitches)};
+
+    let store = PitchStore::open(path)?;
+    if store.version != PITCH_STORE_VERSION || store.columns != columns {
+        return Err(BossError::UnexpectedValue {
+            field: "pitch store schema",
+            value: format!("{} was written with a different schema, write a new store", path.display()),
+        });
+    };
+
+    let mut writer = BufWriter::new(OpenOptions::new().append(true).open(path)?);
+    write_games(&mut writer, &columns, pitches)
+}
+
+/// Rewrites the store at `path` with the current schema if it was written with an older one, so it can be appended to. Every game is
+/// read with the file's own header and written back. A store whose rows can't be read as a `Pitch` anymore (a field was added that
+/// isn't an `Option`) is moved aside to `<path>.old` and the next append starts a new store. Returns whether the store was changed.
+pub fn migrate_pitch_store <P: AsRef<Path>> (path: P) -> Result<bool, BossError> {
+    let path = path.as_ref();
+    if !path.exists() {return Ok(false)};
+
+    let store = PitchStore::open(path)?;
+    if store.version == PITCH_STORE_VERSION && store.columns == current_columns()? {return Ok(false)};
+
+    let mut old = path.as_os_str().to_owned();
+    old.push(".old");
+
+    match store.scan(&PitchFilter::new()) {
+        Ok (pitches) => {
+            let mut migrated = path.as_os_str().to_owned();
+            migrated.push(".migrating");
+            write_pitch_store(&migrated, &pitches)?;
+            std::fs::rename(&migrated, path)?;
+        },
+        Err (_) => std::fs::rename(path, &old)?,
+    };
+
+    Ok(true)
+}
+
+/// The columns of `Pitch` as this version of BOSS stores them.
+fn current_columns () -> Result<Vec<StoreColumn>, BossError> {
+    let variants: BTreeMap<&str, &[&str]> = columns::enum_variants_of::<Pitch>()?.into_iter().collect();
+
+    columns::schema_of::<Pitch>()?.into_iter()
+        .map(|(name, kind)| {
+            let variants: Vec<String> = variants.get(name).map(|v| v.iter().map(|v| v.to_string()).collect()).unwrap_or_default();
+            if variants.len() > 256 {
+                return Err(BossError::UnexpectedValue {field: "pitch store schema", value: format!("{} has more than 256 variants", name)});
+            };
+            Ok(StoreColumn {name: name.to_string(), kind: kind.unwrap_or(ColumnKind::Utf8), variants})
+        })
+        .collect()
+}
+
+fn write_header <W: Write> (writer: &mut W, columns: &[StoreColumn]) -> Result<(), BossError> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&PITCH_STORE_VERSION.to_le_bytes())?;
+    writer.write_all(&(columns.len() as u16).to_le_bytes())?;
+    for column in columns {
+        write_string(writer, &column.name)?;
+        writer.write_all(&[kind_code(column.kind)])?;
+        writer.write_all(&(column.variants.len() as u16).to_le_bytes())?;
+        for variant in column.variants.iter() {
+            write_string(writer, variant)?;
+        };
+    };
+    Ok(())
+}
+
+fn write_games <W: Write> (writer: &mut W, columns: &[StoreColumn], pitches: &[Pitch]) -> Result<(), BossError> {
+    let mut games: BTreeMap<u32, Vec<&Pitch>> = BTreeMap::new();
+    for pitch in pitches {
+        games.entry(pitch.game_pk).or_default().push(pitch);
+    };
+
+    let blocks = games.into_par_iter()
+        .map(|(_, pitches)| encode_block(columns, &pitches))
+        .collect::<Result<Vec<Vec<u8>>, BossError>>()?;
+
+    for block in blocks {
+        writer.write_all(&block)?;
+    };
+    writer.flush()?;
+    Ok(())
+}
+
+fn encode_block (columns: &[StoreColumn], pitches: &[&Pitch]) -> Result<Vec<u8>, BossError> {
+    let first = pitches[0];
+    let players: BTreeSet<u32> = pitches.iter().flat_map(|pitch| player_ids(pitch)).flatten().collect();
+
+    let schema: Vec<(&'static str, Option<ColumnKind>)> = columns::schema_of::<Pitch>()?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    for (values, column) in columns::columns(&schema, pitches.iter().copied())?.into_iter().zip(columns.iter()) {
+        encode_column(&mut encoder, values, column)?;
+    };
+    let body = encoder.finish()?;
+
+    let mut block = Vec::with_capacity(body.len() + 32 + players.len() * 4);
+    block.extend_from_slice(&first.game_pk.to_le_bytes());
+    block.extend_from_slice(&first.sport_id.to_le_bytes());
+    block.extend_from_slice(&season(&first.game_date).to_le_bytes());
+    block.extend_from_slice(&(pitches.len() as u32).to_le_bytes());
+    block.extend_from_slice(&(players.len() as u16).to_le_bytes());
+    for id in players {
+        block.extend_from_slice(&id.to_le_bytes());
+    };
+    block.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    block.extend_from_slice(&body);
+    Ok(block)
+}
+
+fn read_block_header <R: Read> (reader: &mut R) -> Result<GameBlock, BossError> {
+    let game_pk = u32::from_le_bytes(read_bytes(reader)?);
+    let sport_id = u32::from_le_bytes(read_bytes(reader)?);
+    let season = u16::from_le_bytes(read_bytes(reader)?);
+    let rows = u32::from_le_bytes(read_bytes(reader)?);
+    let player_count = u16::from_le_bytes(read_bytes(reader)?);
+    let player_ids = (0 .. player_count)
+        .map(|_| read_bytes(reader).map(u32::from_le_bytes))
+        .collect::<Result<Vec<u32>, BossError>>()?;
+    Ok(GameBlock {game_pk, sport_id, season, rows, player_ids})
+}
+
+fn encode_column <W: Write> (writer: &mut W, values: ColumnValues, column: &StoreColumn) -> Result<(), BossError> {
+    match values {
+        ColumnValues::Bool (values) => write_values(writer, &values, |value| vec![*value as u8]),
+        ColumnValues::Int32 (values) => write_values(writer, &values, |value| value.to_le_bytes().to_vec()),
+        ColumnValues::Int64 (values) => write_values(writer, &values, |value| value.to_le_bytes().to_vec()),
+        ColumnValues::Float (values) => write_values(writer, &values, |value| value.to_le_bytes().to_vec()),
+        ColumnValues::Double (values) => write_values(writer, &values, |value| value.to_le_bytes().to_vec()),
+        ColumnValues::Utf8 (values) => write_values(writer, &values, |value| {
+            let mut bytes = (value.len() as u32).to_le_bytes().to_vec();
+            bytes.extend_from_slice(value.as_bytes());
+            bytes
+        }),
+        ColumnValues::Enum (values) => {
+            let codes = values.iter()
+                .map(|value| match value {
+                    Some (variant) => column.variants.iter().position(|v| v == variant)
+                        .map(|code| Some(code as u8))
+                        .ok_or_else(|| BossError::UnexpectedValue {field: "pitch store variant", value: format!("{}: {}", column.name, variant)}),
+                    None => Ok(None),
+                })
+                .collect::<Result<Vec<Option<u8>>, BossError>>()?;
+            write_values(writer, &codes, |code| vec![*code])
+        },
+    }
+}
+
+// A bitmap of which rows have a value, then the values themselves.
+fn write_values <W: Write, T, F: Fn(&T) -> Vec<u8>> (writer: &mut W, values: &[Option<T>], encode: F) -> Result<(), BossError> {
+    let mut bitmap = vec![0u8; values.len().div_ceil(8)];
+    for (i, value) in values.iter().enumerate() {
+        if value.is_some() {bitmap[i / 8] |= 1 << (i % 8)};
+    };
+    writer.write_all(&bitmap)?;
+
+    for value in values.iter().flatten() {
+        writer.write_all(&encode(value))?;
+    };
+    Ok(())
+}
+
+fn decode_column <R: Read> (reader: &mut R, column: &StoreColumn, rows: usize) -> Result<Vec<Cell>, BossError> {
+    let mut bitmap = vec![0u8; rows.div_ceil(8)];
+    reader.read_exact(&mut bitmap)?;
+
+    (0 .. rows)
+        .map(|i| {
+            if bitmap[i / 8] & (1 << (i % 8)) == 0 {return Ok(Cell::Null)};
+
+            Ok(match column.kind {
+                ColumnKind::Bool => Cell::Bool(read_bytes::<_, 1>(reader)?[0] != 0),
+                ColumnKind::Int32 => Cell::Int32(i32::from_le_bytes(read_bytes(reader)?)),
+                ColumnKind::Int64 => Cell::Int64(i64::from_le_bytes(read_bytes(reader)?)),
+                ColumnKind::Float => Cell::Float(f32::from_le_bytes(read_bytes(reader)?)),
+                ColumnKind::Double => Cell::Double(f64::from_le_bytes(read_bytes(reader)?)),
+                ColumnKind::Utf8 => Cell::Utf8(read_string(reader)?),
+                ColumnKind::Enum => {
+                    let code = read_bytes::<_, 1>(reader)?[0];
+                    let variant = column.variants.get(code as usize).ok_or_else(|| corrupt(&column.name))?;
+                    Cell::Utf8(variant.clone())
+                },
+            })
+        })
+        .collect()
+}
+
+fn in_set <T: Ord> (set: &Option<BTreeSet<T>>, value: T) -> bool {
+    set.as_ref().map(|set| set.contains(&value)).unwrap_or(true)
+}
+
+fn kind_code (kind: ColumnKind) -> u8 {
+    match kind {
+        ColumnKind::Bool => 0,
+        ColumnKind::Int32 => 1,
+        ColumnKind::Int64 => 2,
+        ColumnKind::Float => 3,
+        ColumnKind::Double => 4,
+        ColumnKind::Utf8 => 5,
+        ColumnKind::Enum => 6,
+    }
+}
+
+fn kind_from_code (code: u8) -> Result<ColumnKind, BossError> {
+    match code {
+        0 => Ok(ColumnKind::Bool),
+        1 => Ok(ColumnKind::Int32),
+        2 => Ok(ColumnKind::Int64),
+        3 => Ok(ColumnKind::Float),
+        4 => Ok(ColumnKind::Double),
+        5 => Ok(ColumnKind::Utf8),
+        6 => Ok(ColumnKind::Enum),
+        _ => Err(corrupt("column kind")),
+    }
+}
+
+fn player_ids (pitch: &Pitch) -> [Option<u32>; 10] {
+    [
+        Some(pitch.batter), Some(pitch.pitcher), pitch.catcher_id, pitch.first_base_id, pitch.second_base_id, pitch.short_stop_id,
+        pitch.third_base_id, pitch.left_field_id, pitch.center_field_id, pitch.right_field_id,
+    ]
+}
+
+// Dates are "yyyy-mm-dd"
+fn season (game_date: &str) -> u16 {
+    game_date.get(.. 4).and_then(|year| year.parse().ok()).unwrap_or(0)
+}
+
+fn read_bytes <R: Read, const N: usize> (reader: &mut R) -> Result<[u8; N], BossError> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_string <R: Read> (reader: &mut R) -> Result<String, BossError> {
+    let length = u32::from_le_bytes(read_bytes(reader)?);
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| corrupt("string"))
+}
+
+fn write_string <W: Write> (writer: &mut W, value: &str) -> Result<(), BossError> {
+    writer.write_all(&(value.len() as u32).to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn corrupt (what: &str) -> BossError {
+    BossError::UnexpectedValue {field: "pitch store", value: format!("corrupt or unreadable: {}", what)}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::play_by_play::{Event, PitchTypeCode, PitchTypeDescription, Trajectory};
+    use crate::schedule::GameType;
+
+    fn temp_path (name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boss_pitch_store_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("pitches.boss")
+    }
+
+    fn pitch (game_pk: u32, sport_id: u32, game_date: &str, batter: u32, pitcher: u32) -> Pitch {
+        Pitch {game_pk, sport_id, game_date: game_date.to_string(), batter, pitcher, ..Pitch::default()}
+    }
+
+    // Two pitches in a 2019 MLB game, one with every enum and a few optional fields set and one left null, and one pitch in a 2018
+    // Triple-A game.
+    fn pitches () -> Vec<Pitch> {
+        let mut first = pitch(1, 1, "2019-04-01", 10, 20);
+        first.batter_name = "First Batter".to_string();
+        first.catcher_id = Some(30);
+        first.pitch_type_code = Some(PitchTypeCode::CH);
+        first.pitch_type_desc = Some(PitchTypeDescription::Changeup);
+        first.in_play_result = Some(Event::Double);
+        first.hit_data_trajectory = Some(Trajectory::LineDrive);
+        first.statcast_spin_axis = Some(212.5);
+
+        let second = pitch(1, 1, "2019-04-01", 11, 20);
+
+        let mut third = pitch(2, 11, "2018-07-04", 12, 21);
+        third.game_type = GameType::S;
+        third.catcher_id = Some(31);
+
+        vec![first, second, third]
+    }
+
+    fn json (pitches: &[Pitch]) -> Vec<serde_json::Value> {
+        pitches.iter().map(|pitch| serde_json::to_value(pitch).unwrap()).collect()
+    }
+
+    fn game_and_batter (pitches: &[Pitch]) -> Vec<(u32, u32)> {
+        pitches.iter().map(|pitch| (pitch.game_pk, pitch.batter)).collect()
+    }
+
+    #[test]
+    fn round_trip () {
+        let path = temp_path("round_trip");
+        write_pitch_store(&path, &pitches()).unwrap();
+
+        let store = PitchStore::open(&path).unwrap();
+        assert_eq!(store.version(), PITCH_STORE_VERSION);
+        assert_eq!(store.columns(), current_columns().unwrap().as_slice());
+
+        let read = store.scan(&PitchFilter::new()).unwrap();
+        assert_eq!(json(&read), json(&pitches()));
+        assert!(read[1].pitch_type_code.is_none());
+        assert_eq!(read[1].catcher_id, None);
+        assert_eq!(read[0].in_play_result, Some(Event::Double));
+
+        let games = store.games().unwrap();
+        assert_eq!(games.iter().map(|game| (game.game_pk, game.sport_id, game.season, game.rows)).collect::<Vec<_>>(),
+            vec![(1, 1, 2019, 2), (2, 11, 2018, 1)]);
+        assert_eq!(games[0].player_ids, vec![10, 11, 20, 30]);
+    }
+
+    #[test]
+    fn filters () {
+        let path = temp_path("filters");
+        write_pitch_store(&path, &pitches()).unwrap();
+        let store = PitchStore::open(&path).unwrap();
+        let scan = |filter: PitchFilter| game_and_batter(&store.scan(&filter).unwrap());
+
+        assert_eq!(scan(PitchFilter::new().games(vec![2])), vec![(2, 12)]);
+        assert_eq!(scan(PitchFilter::new().years(vec![2019])), vec![(1, 10), (1, 11)]);
+        assert_eq!(scan(PitchFilter::new().sports(vec![11])), vec![(2, 12)]);
+        assert_eq!(scan(PitchFilter::new().players(vec![11])), vec![(1, 11)]);
+        // The catcher counts as one of the players
+        assert_eq!(scan(PitchFilter::new().players(vec![30])), vec![(1, 10)]);
+        assert_eq!(scan(PitchFilter::new().players(vec![20])), vec![(1, 10), (1, 11)]);
+        assert_eq!(scan(PitchFilter::new().years(vec![2019]).sports(vec![11])), vec![]);
+    }
+
+    #[test]
+    fn append () {
+        let path = temp_path("append");
+        let _ = std::fs::remove_file(&path);
+        let all = pitches();
+
+        append_pitch_store(&path, &all[.. 2]).unwrap();
+        append_pitch_store(&path, &all[2 ..]).unwrap();
+
+        let store = PitchStore::open(&path).unwrap();
+        assert_eq!(store.games().unwrap().len(), 2);
+        assert_eq!(json(&store.scan(&PitchFilter::new()).unwrap()), json(&all));
+    }
+
+    #[test]
+    fn append_rejects_other_schema () {
+        let path = temp_path("other_schema");
+        let mut columns = current_columns().unwrap();
+        columns.pop();
+        let mut file = File::create(&path).unwrap();
+        write_header(&mut file, &columns).unwrap();
+        drop(file);
+
+        match append_pitch_store(&path, &pitches()) {
+            Err (BossError::UnexpectedValue {field, ..}) => assert_eq!(field, "pitch store schema"),
+            other => panic!("expected a schema error, got {:?}", other),
+        };
+    }
+
+    #[test]
+    fn migrate_rewrites_other_schema () {
+        let path = temp_path("migrate");
+        // A store from before the last field was added: the blocks only hold the columns its header lists.
+        let mut columns = current_columns().unwrap();
+        let dropped = columns.pop().unwrap();
+        let mut old = pitches();
+        old[0].primary_datacaster_name = Some("Dropped".to_string());
+        let mut file = File::create(&path).unwrap();
+        write_header(&mut file, &columns).unwrap();
+        write_games(&mut file, &columns, &old).unwrap();
+        drop(file);
+
+        assert!(migrate_pitch_store(&path).unwrap());
+        assert!(!migrate_pitch_store(&path).unwrap());
+
+        let store = PitchStore::open(&path).unwrap();
+        assert_eq!(store.columns(), current_columns().unwrap().as_slice());
+        assert!(store.columns().contains(&dropped));
+
+        let read = store.scan(&PitchFilter::new()).unwrap();
+        assert_eq!(game_and_batter(&read), game_and_batter(&pitches()));
+        assert!(read.iter().all(|pitch| pitch.primary_datacaster_name.is_none()));
+        assert_eq!(read[0].catcher_id, Some(30));
+        assert_eq!(read[0].in_play_result, Some(Event::Double));
+
+        append_pitch_store(&path, &[pitch(3, 1, "2019-05-01", 13, 22)]).unwrap();
+        assert_eq!(PitchStore::open(&path).unwrap().games().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn open_rejects_bad_magic_and_version () {
+        let path = temp_path("bad_header");
+
+        std::fs::write(&path, b"NOTBOSS!\x01\x00\x00\x00").unwrap();
+        match PitchStore::open(&path) {
+            Err (BossError::UnexpectedValue {field, ..}) => assert_eq!(field, "pitch store"),
+            other => panic!("expected a bad magic error, got {:?}", other),
+        };
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(PITCH_STORE_VERSION + 1).to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        std::fs::write(&path, bytes).unwrap();
+        match PitchStore::open(&path) {
+            Err (BossError::UnexpectedValue {field, ..}) => assert_eq!(field, "pitch store version"),
+            other => panic!("expected a version error, got {:?}", other),
+        };
+    }
+}
@@ -238,6 +238,12 @@ pub enum Code {
   ///Pickoff Attempt
   #[serde(rename="1")]
   PO,
+  ///Pickoff Attempt at Second
+  #[serde(rename="2")]
+  PO2,
+  ///Pickoff Attempt at Third
+  #[serde(rename="3")]
+  PO3,
   /// Ball in dirt
   #[serde(rename="*B")]
   BD,
@@ -257,6 +263,24 @@ pub enum Code {
   L,
   /// In Play - Outs
   X,
+  /// Hit By Pitch
+  H,
+  /// Foul Tip
+  T,
+  /// Missed Bunt
+  M,
+  /// Swinging Strike (Blocked)
+  W,
+  /// Pitchout
+  P,
+  /// Swinging Pitchout
+  Q,
+  /// Foul Pitchout
+  R,
+  /// Intentional Ball
+  I,
+  /// Automatic Ball
+  V,
   #[serde(other)]
   Other,
 }
@@ -289,9 +313,10 @@ pub(crate) struct PlateAppearanceData {
     // plate_appearance_result_description: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Eq, PartialEq, Default)]
 #[serde(rename_all="camelCase")]
 pub enum HalfInning {
+    #[default]
     Top,
     Bottom,
 }
@@ -333,12 +358,13 @@ pub(crate) struct Side {
     description: Option<SideDescription>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Copy, Clone)]
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Default)]
 pub enum SideCode {
     L,
     R,
     S,
     #[serde(other)]
+    #[default]
     Other,
 }
 
@@ -0,0 +1,200 @@
+//! Player weights and heights over time. The Stats API only has a player's current bio, so a 2006 pitch would otherwise show the weight
+//! the player is listed at today. Each time we see a player's size we keep it along with the date it was listed:
+//!
+//! * From the Gameday XML files (see `gameday`), dated the day of the game.
+//! * From the people endpoint, dated the day the player was pulled. Refreshing the players every so often
+//!   (`BossConfig::refresh(Dataset::Players, Overwrite::OlderThan(30))`) builds up snapshots over time.
+//!
+//! When a game is processed, the batter and pitcher get the weight and height listed on or most recently before the game date. The
+//! history is cached in `player_history.json`.
+
+use crate::gameday::GamedayData;
+use crate::players::Player;
+use crate::utils::Date;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Where a listing came from. When both have a listing for the same day, the Gameday one wins, since it was written for that game.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AttributeSource {
+    People,
+    Gameday,
+}
+
+/// A player's size as listed on one day.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PlayerAttributes {
+    pub player_id: u32,
+    pub date: Date,
+    pub weight: Option<u16>,
+    /// The height as the Stats API writes it, such as `6' 2"`.
+    pub height_str: Option<String>,
+    pub height_in: Option<u8>,
+    pub source: AttributeSource,
+}
+
+/// Every listing we have, by player and date.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerHistory {
+    players: HashMap<u32, BTreeMap<Date, PlayerAttributes>>,
+}
+
+impl PlayerHistory {
+    /// Adds a listing. A listing from a lower ranked source doesn't replace one we already have for that day.
+    pub fn insert (&mut self, attributes: PlayerAttributes) {
+        let dates = self.players.entry(attributes.player_id).or_default();
+        match dates.get(&attributes.date) {
+            Some (existing) if existing.source > attributes.source => {},
+            _ => {dates.insert(attributes.date, attributes);},
+        };
+    }
+
+    /// The listing in effect on `date`: the latest one on or before it. Games from before our first listing get the earliest one we
+    /// have, which is still closer to the truth than today's bio.
+    pub fn as_of (&self, player_id: u32, date: Date) -> Option<&PlayerAttributes> {
+        let dates = self.players.get(&player_id)?;
+        dates.range(..= date).next_back()
+            .or_else(|| dates.iter().next())
+            .map(|(_, attributes)| attributes)
+    }
+
+    /// Swaps the player's current weight and height for the ones in effect on `date`, where we have them.
+    pub fn apply (&self, player: &mut Player, date: Date) {
+        let attributes = match self.as_of(player.id, date) {
+            Some (attributes) => attributes,
+            None => return,
+        };
+        if let Some (weight) = attributes.weight {
+            player.weight = Some (weight);
+        };
+        if let (Some (height_str), Some (height_in)) = (&attributes.height_str, attributes.height_in) {
+            player.height_str = Some (height_str.clone());
+            player.height_in = height_in;
+        };
+    }
+
+    /// Every listing, sorted by player and date. This is what we cache.
+    pub fn records (&self) -> Vec<PlayerAttributes> {
+        let mut records: Vec<PlayerAttributes> = self.players.values()
+            .flat_map(|dates| dates.values().cloned())
+            .collect();
+        records.sort_by_key(|attributes| (attributes.player_id, attributes.date));
+        records
+    }
+
+    pub fn len (&self) -> usize {
+        self.players.values().map(|dates| dates.len()).sum()
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.players.is_empty()
+    }
+}
+
+impl From<Vec<PlayerAttributes>> for PlayerHistory {
+    fn from (records: Vec<PlayerAttributes>) -> PlayerHistory {
+        let mut history = PlayerHistory::default();
+        for attributes in records {
+            history.insert(attributes);
+        };
+        history
+    }
+}
+
+/// The listings from one game's Gameday XML files.
+pub fn from_gameday (gameday: &GamedayData, game_date: Date) -> Vec<PlayerAttributes> {
+    let mut ids: Vec<u32> = gameday.weights.keys().chain(gameday.heights.keys()).copied().collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    ids.into_iter()
+        .map(|player_id| {
+            let height = gameday.heights.get(&player_id).and_then(|height| parse_height(height));
+            PlayerAttributes {
+                player_id,
+                date: game_date,
+                weight: gameday.weights.get(&player_id).copied(),
+                height_str: height.map(|(feet, inches)| format!("{}' {}\"", feet, inches)),
+                height_in: height.map(|(feet, inches)| feet * 12 + inches),
+                source: AttributeSource::Gameday,
+            }
+        })
+        .collect()
+}
+
+/// A snapshot of a player's bio as the people endpoint had them on `date`.
+pub fn from_people (player: &Player, date: Date) -> PlayerAttributes {
+    PlayerAttributes {
+        player_id: player.id,
+        date,
+        weight: player.weight,
+        height_str: player.height_str.clone(),
+        height_in: if player.height_in > 0 {Some (player.height_in)} else {None},
+        source: AttributeSource::People,
+    }
+}
+
+/// Gameday heights look like "6-2".
+fn parse_height (height: &str) -> Option<(u8, u8)> {
+    let (feet, inches) = height.split_once('-')?;
+    let (feet, inches) = (feet.trim().parse::<u8>().ok()?, inches.trim().parse::<u8>().ok()?);
+    if feet < 10 && inches < 12 {Some ((feet, inches))} else {None}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing (year: u16, month: u8, day: u8, weight: u16, source: AttributeSource) -> PlayerAttributes {
+        PlayerAttributes {
+            player_id: 1,
+            date: Date {year, month, day},
+            weight: Some (weight),
+            height_str: None,
+            height_in: None,
+            source,
+        }
+    }
+
+    fn weight_on (history: &PlayerHistory, year: u16, month: u8, day: u8) -> Option<u16> {
+        history.as_of(1, Date {year, month, day}).and_then(|attributes| attributes.weight)
+    }
+
+    #[test]
+    fn as_of_picks_the_listing_in_effect () {
+        let history = PlayerHistory::from(vec![
+            listing(2015, 4, 10, 200, AttributeSource::Gameday),
+            listing(2017, 6, 1, 210, AttributeSource::People),
+            listing(2019, 7, 4, 220, AttributeSource::Gameday),
+        ]);
+
+        // Before the first listing we fall back to the earliest one.
+        assert_eq!(weight_on(&history, 2010, 1, 1), Some (200));
+        assert_eq!(weight_on(&history, 2015, 4, 10), Some (200));
+        assert_eq!(weight_on(&history, 2016, 9, 30), Some (200));
+        assert_eq!(weight_on(&history, 2017, 6, 1), Some (210));
+        assert_eq!(weight_on(&history, 2019, 7, 3), Some (210));
+        assert_eq!(weight_on(&history, 2023, 1, 1), Some (220));
+        assert!(history.as_of(2, Date {year: 2019, month: 7, day: 4}).is_none());
+    }
+
+    #[test]
+    fn gameday_listing_wins_the_same_day () {
+        let mut history = PlayerHistory::default();
+        history.insert(listing(2019, 7, 4, 220, AttributeSource::Gameday));
+        history.insert(listing(2019, 7, 4, 230, AttributeSource::People));
+        assert_eq!(weight_on(&history, 2019, 7, 4), Some (220));
+
+        history.insert(listing(2019, 7, 4, 225, AttributeSource::Gameday));
+        assert_eq!(weight_on(&history, 2019, 7, 4), Some (225));
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn parses_gameday_heights () {
+        assert_eq!(parse_height("6-2"), Some ((6, 2)));
+        assert_eq!(parse_height("5 - 11"), Some ((5, 11)));
+        assert_eq!(parse_height("6-12"), None);
+        assert_eq!(parse_height("74"), None);
+    }
+}
@@ -8,6 +8,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::HashMap;
 use crate::utils::*;
+use crate::transactions::TransactionDe;
+use crate::awards::AwardDe;
+use std::convert::TryInto;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Player {
@@ -79,13 +82,14 @@ impl From <PlayerDeserialize> for Player {
             _ => (Some(drafts[0].school.name.clone().unwrap_or("".to_string())),Some(drafts[0].pick_number),Some(drafts[0].pick_round.clone())),
         };
         
+        // A date we can't parse is treated the same as a missing date
         let mlb_debut_date = match player.mlb_debut_date {
-            Some (debut) => Some(debut.into()),
+            Some (debut) => debut.try_into().ok(),
             None => None,
         };
 
         let birth_date = match player.birth_date {
-            Some (date) => Some(date.into()),
+            Some (date) => date.try_into().ok(),
             None => None,
         };
 
@@ -169,6 +173,8 @@ pub (crate) struct PlayerDeserialize {
     pub (crate) mlb_debut_date: Option<String>,
     #[serde(alias="draftYear")]
     pub (crate) draft_year: Option<u16>,
+    pub (crate) transactions: Option<Vec<TransactionDe>>,
+    pub (crate) awards: Option<Vec<AwardDe>>,
 }
 
 #[serde(rename_all="camelCase")]
@@ -235,10 +241,11 @@ pub enum SideDescription {
 
 // Player Awards. 
 // We want to see if the number of player awards a player gets in the minors is predictive of future success. I'm not sure yet
-// what the best way to aggregate this is, so we'll seprate them out first before baking them into the data.
+// what the best way to aggregate this is, so we'll seprate them out first before baking them into the data. See the `awards` module.
 
 
 // Player Transactions
 // Transaction data are critical as they contain injury history. This is essential if we want to include injury history in a projection system.
+// They're parsed out of the same response as the bio and kept in their own file, see the `transactions` module.
 // In order to limit the number of times we pull a player's data, we'll need two separate pulls - one that updates all missing players without transaction data, as well
 // as one that pulls in ALL the players, including transaction and award data, minus players who have retired.
\ No newline at end of file
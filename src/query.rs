@@ -0,0 +1,334 @@
+//! The main entry point for library users. Build up a query with the `Boss` builder, then call `fetch` to get back every pitch
+//! that matches along with all the metadata used to build them.
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::schedule::GameType;
+//!
+//! let data = Boss::new()
+//!     .years(2018 ..= 2019)
+//!     .sports(vec![1])
+//!     .game_types(vec![GameType::R])
+//!     .fetch()?;
+//!
+//! println!("{} pitches, {} items failed", data.pitches.len(), data.failures.len());
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! Metadata is cached in the `BossConfig` cache folder as it's pulled, so asking for the same games again is much faster the second
+//! time around. Use `.refresh` to pull some of it again, e.g. to pick up player weight changes. Nothing is written to the output
+//! folder, what you do with the pitches is up to you.
+//!
+//! To reprocess games without downloading them again, pull them once with `.archive(ArchiveMode::Write)`, then run the same query
+//! with `.archive(ArchiveMode::ReadOnly)`. See the `archive` module.
+
+use crate::archive::{Archive, ArchiveMode, ArchiveTransport};
+use crate::cache::Overwrite;
+use crate::config::BossConfig;
+use crate::error::{BossError, Dataset, Failure};
+use crate::game::{Pitch, GameData};
+use crate::manifest::{Manifest, GameStatus};
+use crate::get_data::{get_schedule_data, get_game_meta_data, process_games, pull_game};
+use crate::metadata::MetaData;
+use crate::retrosheet::EventGame;
+use crate::schedule::{GameMetaData, GameType, AbstractGameState};
+use crate::scheduler::{RequestScheduler, RequestLimits, FailedRequest};
+use crate::sports;
+use crate::transport::{Transport, LiveTransport};
+use crate::utils::Date;
+#[cfg(feature = "arrow")]
+use arrow::record_batch::RecordBatch;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, Stream};
+use rayon::prelude::*;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+/// Builder for a pull of pitch by pitch data. By default, every sport and every game type is included, but you need to
+/// give it either `years` or a `date_range` so it knows which seasons to look at.
+#[derive(Clone)]
+pub struct Boss {
+    config: BossConfig,
+    transport: Option<Arc<dyn Transport>>,
+    limits: RequestLimits,
+    archive: ArchiveMode,
+    years: Vec<u16>,
+    sport_ids: Vec<u32>,
+    game_types: Vec<GameType>,
+    date_range: Option<(Date, Date)>,
+}
+
+/// The request scheduler, and the transport a fetch goes through (the scheduler, possibly behind the archive).
+type Transports = (Arc<RequestScheduler<Arc<dyn Transport>>>, Arc<dyn Transport>);
+
+/// The metadata for a fetch, the games to process as (game_pk, play by play url), and the items that failed along the way.
+type Games = (MetaData, BTreeSet<(u32, String)>, Vec<Failure>);
+
+/// Everything returned by `Boss::fetch`. Failures are the items (games, players, boxscores...) that couldn't be pulled or parsed.
+/// Since they weren't cached, running the same fetch again will retry them. Failed requests are the urls that were still failing after
+/// every retry.
+pub struct BossData {
+    pub pitches: Vec<Pitch>,
+    pub meta_data: MetaData,
+    pub failures: Vec<Failure>,
+    pub failed_requests: Vec<FailedRequest>,
+}
+
+impl Boss {
+    pub fn new () -> Boss {
+        Boss {
+            config: BossConfig::default(),
+            transport: None,
+            limits: RequestLimits::default(),
+            archive: ArchiveMode::Off,
+            years: Vec::new(),
+            sport_ids: sports::get_all_sport_ids(),
+            game_types: Vec::new(),
+            date_range: None,
+        }
+    }
+
+    /// Where to cache the metadata. Defaults to `BossConfig::default()`.
+    pub fn config (mut self, config: BossConfig) -> Boss {
+        self.config = config;
+        self
+    }
+
+    /// Refresh policy for one of the cached data sets, such as `.refresh(Dataset::Players, Overwrite::OlderThan(30))`. Shorthand
+    /// for setting it on the config. See the `cache` module.
+    pub fn refresh (mut self, dataset: Dataset, overwrite: Overwrite) -> Boss {
+        self.config = self.config.refresh(dataset, overwrite);
+        self
+    }
+
+    /// Where to get the raw data from. Defaults to a `LiveTransport`, use a `FixtureTransport` to run against recorded responses.
+    pub fn transport <T: Transport + 'static> (mut self, transport: T) -> Boss {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Concurrency, rate limit and retry settings for the requests. See the `scheduler` module.
+    pub fn limits (mut self, limits: RequestLimits) -> Boss {
+        self.limits = limits;
+        self
+    }
+
+    /// Whether to save the raw responses to the config's archive folder, or read from it instead of the network. Off by default.
+    pub fn archive (mut self, mode: ArchiveMode) -> Boss {
+        self.archive = mode;
+        self
+    }
+
+    /// Seasons to pull, such as `2019 ..= 2020` or `vec![2015, 2017]`.
+    pub fn years <Y: IntoIterator<Item = u16>> (mut self, years: Y) -> Boss {
+        self.years = years.into_iter().collect();
+        self
+    }
+
+    /// Levels of play to pull, using the Stats API sport ids (1 = MLB, 11 = AAA etc.). See the `sports` module for the full list.
+    pub fn sports <S: IntoIterator<Item = u32>> (mut self, sport_ids: S) -> Boss {
+        self.sport_ids = sport_ids.into_iter().collect();
+        self
+    }
+
+    /// Only keep games of these types, such as `GameType::R` for the regular season. An empty list keeps everything.
+    pub fn game_types <G: IntoIterator<Item = GameType>> (mut self, game_types: G) -> Boss {
+        self.game_types = game_types.into_iter().collect();
+        self
+    }
+
+    /// Only keep games played between `start` and `end`, inclusive. If no years were set, the seasons are taken from the range.
+    pub fn date_range (mut self, start: Date, end: Date) -> Boss {
+        self.date_range = Some((start, end));
+        self
+    }
+
+    fn seasons (&self) -> Vec<u16> {
+        match (self.years.len(), self.date_range) {
+            (0, Some((start, end))) => (start.year ..= end.year).collect(),
+            _ => self.years.clone(),
+        }
+    }
+
+    fn keep (&self, game: &GameMetaData) -> bool {
+        let game_type_ok = self.game_types.is_empty() || self.game_types.contains(&game.game_type);
+        let date_ok = match self.date_range {
+            Some ((start, end)) => game.game_date >= start && game.game_date <= end,
+            None => true,
+        };
+        game.game_status == AbstractGameState::Final && game_type_ok && date_ok
+    }
+
+    /// Pulls the schedule, then the metadata and play by play for every completed game that matches the query. How each game went is
+    /// recorded in the manifest (see the `manifest` module).
+    pub fn fetch (&self) -> Result<BossData, BossError> {
+
+        let (scheduler, transport) = self.transports()?;
+        let (meta_data, pbp_urls, mut failures) = self.games(transport.as_ref())?;
+        let requested: BTreeSet<u32> = pbp_urls.iter().map(|game| game.0).collect();
+
+        let (pitches, pitch_failures) = process_games(transport.as_ref(), &meta_data, pbp_urls)?;
+
+        let mut manifest = Manifest::load(&self.config)?;
+        manifest.record_batch(&requested, &pitches, &pitch_failures);
+        manifest.save(&self.config)?;
+
+        failures.extend(pitch_failures);
+
+        Ok(BossData {
+            pitches,
+            meta_data,
+            failures,
+            failed_requests: scheduler.failed_requests(),
+        })
+    }
+
+    // Builds the transport for a fetch: the scheduler, with the archive in front of it if it's turned on. The scheduler is returned
+    // as well so we can ask it which requests failed.
+    fn transports (&self) -> Result<Transports, BossError> {
+        let inner: Arc<dyn Transport> = match &self.transport {
+            Some (transport) => transport.clone(),
+            None => Arc::new(LiveTransport::new()?),
+        };
+        let scheduler = Arc::new(RequestScheduler::new(inner, self.limits));
+
+        let transport: Arc<dyn Transport> = match self.archive {
+            ArchiveMode::Off => scheduler.clone(),
+            _ => Arc::new(ArchiveTransport::new(scheduler.clone(), Archive::from_config(&self.config, self.archive))),
+        };
+
+        Ok((scheduler, transport))
+    }
+
+    // Pulls the schedule and metadata, and works out which games need to be processed.
+    fn games (&self, transport: &dyn Transport) -> Result<Games, BossError> {
+
+        let years = self.seasons();
+        if years.is_empty() {return Err(BossError::InvalidQuery("no years or date range to pull"))};
+        if self.sport_ids.is_empty() {return Err(BossError::InvalidQuery("no sports to pull"))};
+
+        let (schedule, mut failures) = get_schedule_data(&self.config, transport, years, self.sport_ids.clone())?;
+
+        let schedule: Vec<GameMetaData> = schedule.into_iter()
+            .filter(|game| self.keep(game))
+            .collect()
+            ;
+
+        let (meta, meta_failures) = get_game_meta_data(&self.config, transport, schedule)?;
+        failures.extend(meta_failures);
+
+        //For some reason, we have duplicate game_pks in our schedule, so we make it a set to get rid of that problem.
+        let pbp_urls: BTreeSet<(u32, String)> = meta.schedule.iter()
+            .map(|game| (game.game_pk, game.game_url_play_by_play.clone()))
+            .collect()
+            ;
+
+        Ok((meta.into(), pbp_urls, failures))
+    }
+}
+
+/// How many processed games can be waiting on the consumer of a `stream_pitches` stream before we stop processing more.
+const STREAM_BUFFER: usize = 16;
+
+/// Streams the pitches for a query one game at a time, as soon as each game is processed, so a multi-season pull never has to fit
+/// in memory. The schedule and metadata are pulled first (on a background thread, so this doesn't block the executor), then games
+/// are processed in parallel. Only a handful of games are ever buffered: if the consumer falls behind, processing waits for it,
+/// and dropping the stream stops the pull.
+///
+/// A game that fails comes through as an `Err` and the stream carries on with the next game. Items from the schedule or metadata
+/// that couldn't be pulled come through first, each as a `BossError::Failed`, just as `fetch` returns them as failures. An error
+/// that stops the schedule or metadata being pulled at all ends the stream. Games without any plays are skipped. How each game went
+/// is recorded in the manifest, like `fetch` does.
+///
+/// ```no_run
+/// use boss::{Boss, stream_pitches};
+/// use futures::StreamExt;
+///
+/// futures::executor::block_on(async {
+///     let mut games = stream_pitches(Boss::new().years(2019 ..= 2019).sports(vec![1])).await;
+///     while let Some (game) = games.next().await {
+///         match game {
+///             Ok (pitches) => println!("{} pitches", pitches.len()),
+///             Err (error) => println!("{}", error),
+///         }
+///     }
+/// });
+/// ```
+pub async fn stream_pitches (query: Boss) -> impl Stream<Item = Result<Vec<Pitch>, BossError>> + Send + Unpin {
+    stream_games(query, |game| Vec::<Pitch>::try_from(game))
+}
+
+/// Same as `stream_pitches`, but each game comes through as an Arrow `RecordBatch` with one row per pitch. Every batch has the same
+/// schema, see the `record_batch` module.
+#[cfg(feature = "arrow")]
+pub async fn stream_record_batches (query: Boss) -> impl Stream<Item = Result<RecordBatch, BossError>> + Send + Unpin {
+    stream_games(query, |game| RecordBatch::try_from(game))
+}
+
+/// Same as `stream_pitches`, but each game comes through as Retrosheet event records, ready for `retrosheet::write_event_files`.
+pub async fn stream_event_games (query: Boss) -> impl Stream<Item = Result<EventGame, BossError>> + Send + Unpin {
+    stream_games(query, |game| EventGame::try_from(game))
+}
+
+// Runs the query, converting each game with `convert` on the rayon pool and sending it down the stream.
+fn stream_games <T: Send + 'static> (query: Boss, convert: fn (GameData) -> Result<T, BossError>) -> mpsc::Receiver<Result<T, BossError>> {
+
+    let (mut sender, receiver) = mpsc::channel(STREAM_BUFFER);
+
+    std::thread::spawn(move || {
+        let games = query.transports()
+            .and_then(|(_, transport)| query.games(transport.as_ref()).map(|games| (transport, games)));
+
+        let manifest = games.and_then(|games| Manifest::load(&query.config).map(|manifest| (games, Mutex::new(manifest))));
+        let ((transport, (meta_data, pbp_urls, failures)), manifest) = match manifest {
+            Ok (games) => games,
+            Err (error) => {
+                let _ = block_on(sender.send(Err(error)));
+                return;
+            },
+        };
+
+        // The items the metadata couldn't be pulled for come first, the same ones `fetch` returns as failures.
+        for failure in failures {
+            if block_on(sender.send(Err(BossError::Failed(Box::new(failure))))).is_err() {return};
+        };
+
+        // try_for_each_with stops handing out games once a send fails, which only happens when the stream was dropped.
+        let _ = pbp_urls.into_par_iter().try_for_each_with(sender.clone(), |sender, (game_pk, url)| {
+            let record = |status: GameStatus, error: Option<String>| if let Ok (mut manifest) = manifest.lock() {manifest.record(game_pk, status, error)};
+
+            match pull_game(transport.as_ref(), &meta_data, game_pk, &url).and_then(|game| game.map(convert).transpose()) {
+                Ok (Some (game)) => {
+                    block_on(sender.send(Ok(game)))?;
+                    record(GameStatus::Ok, None);
+                    Ok(())
+                },
+                Ok (None) => {
+                    record(GameStatus::NoPlays, None);
+                    Ok(())
+                },
+                Err (error) => {
+                    record((&error).into(), Some(error.to_string()));
+                    block_on(sender.send(Err(BossError::Failed(Box::new(Failure {dataset: Dataset::PlayByPlay, key: game_pk.to_string(), error})))))
+                },
+            }
+        });
+
+        // Only the games that were tried are recorded, so a stream dropped partway leaves the rest for next time.
+        if let Ok (manifest) = manifest.into_inner() {
+            if let Err (error) = manifest.save(&query.config) {
+                let _ = block_on(sender.send(Err(error)));
+            };
+        };
+    });
+
+    receiver
+}
+
+impl Default for Boss {
+    fn default() -> Self {
+        Boss::new()
+    }
+}
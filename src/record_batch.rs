@@ -0,0 +1,91 @@
+//! Arrow `RecordBatch`es of pitches, behind the `arrow` feature. A batch holds the same columns as the CSV output, but column by
+//! column in Arrow's memory layout, so it can be handed to anything that speaks Arrow (DataFusion, Polars, pyarrow...) without
+//! copying or re-parsing it.
+//!
+//! * Enum columns (`Event`, `PitchTypeCode`, `Pos`, `WeatherCondition`...) are dictionary arrays with `Int16` keys, so a column of
+//!   "Four-Seam Fastball"s is a handful of strings and a key per row.
+//! * Every column is nullable.
+//! * The schema comes from the `Pitch` struct rather than the data (see `columns::schema_of`), so every batch has the same schema,
+//!   even a batch where a column happens to be null in every row.
+//!
+//! A game converts straight from its `GameData`, and `stream_record_batches` streams a whole pull one game at a time:
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::query::stream_record_batches;
+//! use futures::StreamExt;
+//!
+//! futures::executor::block_on(async {
+//!     let mut games = stream_record_batches(Boss::new().years(2019 ..= 2019).sports(vec![1])).await;
+//!     while let Some (Ok (batch)) = games.next().await {
+//!         println!("{} pitches", batch.num_rows());
+//!     }
+//! });
+//! ```
+
+use crate::columns::{self, ColumnKind, ColumnValues};
+use crate::error::BossError;
+use crate::game::{GameData, Pitch};
+use arrow::array::{ArrayRef, BooleanArray, DictionaryArray, Float32Array, Float64Array, Int32Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Int16Type, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// The Arrow schema shared by every batch of pitches.
+pub fn pitch_schema () -> Result<SchemaRef, BossError> {
+    Ok(arrow_schema(&columns::schema_of::<Pitch>()?))
+}
+
+/// Puts the pitches into a single `RecordBatch`, one row per pitch.
+pub fn pitches_to_record_batch (pitches: &[Pitch]) -> Result<RecordBatch, BossError> {
+    to_record_batch(&columns::schema_of::<Pitch>()?, pitches)
+}
+
+/// Puts any flat struct into a `RecordBatch`, using a schema from `columns::schema` or `columns::schema_of`. Columns without a kind
+/// are stored as strings.
+pub fn to_record_batch <'a, T: Serialize + 'a, I: IntoIterator<Item = &'a T>> (schema: &[(&'static str, Option<ColumnKind>)], rows: I) -> Result<RecordBatch, BossError> {
+    let arrays: Vec<ArrayRef> = columns::columns(schema, rows)?.into_iter().map(arrow_array).collect();
+    Ok(RecordBatch::try_new(arrow_schema(schema), arrays)?)
+}
+
+/// Converts a game straight into a batch of its pitches.
+impl <'m> TryFrom<GameData<'m>> for RecordBatch {
+    type Error = BossError;
+
+    fn try_from (data: GameData<'m>) -> Result<RecordBatch, BossError> {
+        pitches_to_record_batch(&Vec::<Pitch>::try_from(data)?)
+    }
+}
+
+fn arrow_schema (schema: &[(&'static str, Option<ColumnKind>)]) -> SchemaRef {
+    let fields: Vec<Field> = schema.iter()
+        .map(|(name, kind)| Field::new(*name, data_type(*kind), true))
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+fn data_type (kind: Option<ColumnKind>) -> DataType {
+    match kind {
+        Some (ColumnKind::Bool) => DataType::Boolean,
+        Some (ColumnKind::Int32) => DataType::Int32,
+        Some (ColumnKind::Int64) => DataType::Int64,
+        Some (ColumnKind::Float) => DataType::Float32,
+        Some (ColumnKind::Double) => DataType::Float64,
+        Some (ColumnKind::Enum) => DataType::Dictionary(Box::new(DataType::Int16), Box::new(DataType::Utf8)),
+        Some (ColumnKind::Utf8) | None => DataType::Utf8,
+    }
+}
+
+fn arrow_array (values: ColumnValues) -> ArrayRef {
+    match values {
+        ColumnValues::Bool (values) => Arc::new(BooleanArray::from(values)),
+        ColumnValues::Int32 (values) => Arc::new(Int32Array::from(values)),
+        ColumnValues::Int64 (values) => Arc::new(Int64Array::from(values)),
+        ColumnValues::Float (values) => Arc::new(Float32Array::from(values)),
+        ColumnValues::Double (values) => Arc::new(Float64Array::from(values)),
+        ColumnValues::Utf8 (values) => Arc::new(StringArray::from(values)),
+        ColumnValues::Enum (values) => Arc::new(values.into_iter().collect::<DictionaryArray<Int16Type>>()),
+    }
+}
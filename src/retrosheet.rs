@@ -0,0 +1,760 @@
+//! Retrosheet event files (`.EVA`/`.EVN`) built from the play by play, so Chadwick (`cwevent`, `cwbox`...) can be run on games
+//! Retrosheet doesn't cover, such as the minor leagues.
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::query::stream_event_games;
+//! use boss::retrosheet::write_event_files;
+//! use futures::StreamExt;
+//!
+//! let games = futures::executor::block_on(async {
+//!     stream_event_games(Boss::new().years(2019 ..= 2019).sports(vec![11])).await
+//!         .filter_map(|game| async move {game.ok()})
+//!         .collect::<Vec<_>>().await
+//! });
+//! write_event_files(games, "retrosheet/2019")?;
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! Each game becomes `id`, `version`, `info`, `start`, `sub`, `play` and `com` records, and games are grouped into one file per home
+//! team and season, such as `2019DUR.EVN`. A few things differ from the files Retrosheet publishes:
+//!
+//! * Players without a Retrosheet id (most minor leaguers) are written with their MLBAM id, and teams with the Stats API team code,
+//!   such as "NYA" or "DUR". Files get the `.EVA` extension when the home team is in the American League, `.EVN` otherwise.
+//! * The play by play only keeps the first fielder credited on a play, so a ground out to short is written as `6/G` rather than
+//!   `63/G`, and runners thrown out don't have the base they were out at, so they're written as out at the next base.
+//! * There are no `data,er` records.
+
+use crate::boxscore::{BoxScore, Player as BoxScorePlayer, Pos, WeatherCondition, WindDirection};
+use crate::error::{BossError, MetaDataType};
+use crate::game::GameData;
+use crate::metadata::MetaData;
+use crate::play_by_play::{AllPlays, Code, Event, HalfInning, PlayEvent, PlayEventType, RunnerData};
+use crate::utils::Date;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Stats API league id of the American League. Home teams in it get `.EVA` files.
+const AMERICAN_LEAGUE: u32 = 103;
+
+/// The records for one game, except for the `id` and `info,number` records, which depend on the other games the home team played that
+/// day and are filled in by `write`.
+#[derive(Debug, Clone)]
+pub struct EventGame {
+    pub game_pk: u32,
+    pub game_date: Date,
+    /// Team codes, such as "NYA".
+    pub home_team: String,
+    pub away_team: String,
+    /// "EVA" or "EVN".
+    pub extension: &'static str,
+    pub records: Vec<String>,
+}
+
+impl EventGame {
+    /// The event file the game belongs in, such as "2019NYA.EVA".
+    pub fn file_name (&self) -> String {
+        format!("{}{}.{}", self.game_date.year, self.home_team, self.extension)
+    }
+
+    /// The Retrosheet game id, such as "NYA201904010". The number is 0 for a single game, 1 or 2 for the games of a doubleheader.
+    pub fn game_id (&self, number: u8) -> String {
+        format!("{}{:04}{:02}{:02}{}", self.home_team, self.game_date.year, self.game_date.month, self.game_date.day, number)
+    }
+
+    /// Writes the game, one record per line.
+    pub fn write <W: Write> (&self, number: u8, writer: &mut W) -> Result<(), BossError> {
+        writeln!(writer, "id,{}", self.game_id(number))?;
+        writeln!(writer, "version,2")?;
+        writeln!(writer, "info,number,{}", number)?;
+        for record in self.records.iter() {
+            writeln!(writer, "{}", record)?;
+        };
+        Ok(())
+    }
+}
+
+/// Writes the games into `dir`, one event file per home team and season, replacing any files that are already there. Games are written
+/// in date order, and when a team has more than one game on the same day they're numbered in game_pk order. Returns the files written.
+pub fn write_event_files <I: IntoIterator<Item = EventGame>, P: AsRef<Path>> (games: I, dir: P) -> Result<Vec<PathBuf>, BossError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let mut files: BTreeMap<String, Vec<EventGame>> = BTreeMap::new();
+    for game in games {
+        files.entry(game.file_name()).or_default().push(game);
+    };
+
+    let mut paths = Vec::with_capacity(files.len());
+    for (file_name, mut games) in files {
+        games.sort_by_key(|game| (game.game_date, game.game_pk));
+
+        let path = dir.join(file_name);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for (i, game) in games.iter().enumerate() {
+            let number = match games.iter().filter(|other| other.game_date == game.game_date).count() {
+                1 => 0,
+                _ => games[.. i].iter().filter(|other| other.game_date == game.game_date).count() as u8 + 1,
+            };
+            game.write(number, &mut writer)?;
+        };
+        writer.flush()?;
+        paths.push(path);
+    };
+
+    Ok(paths)
+}
+
+/// Builds the records for a game. Fails if we're missing its schedule or boxscore, since the boxscore has the starting lineups.
+impl <'m> TryFrom<GameData<'m>> for EventGame {
+    type Error = BossError;
+
+    fn try_from (data: GameData<'m>) -> Result<EventGame, BossError> {
+        let game_pk = data.game_pk;
+        let missing = |meta_data: MetaDataType| BossError::MissingMetaData {game_pk, meta_data};
+
+        let sched_meta = data.meta_data.schedule.get(&game_pk).ok_or(missing(MetaDataType::Schedule))?;
+        let box_meta = data.meta_data.boxscore.get(&game_pk).ok_or(missing(MetaDataType::BoxScore))?;
+        let year = sched_meta.game_date.year;
+
+        let team_code = |code: &Option<String>, id: u32| {
+            let name = data.meta_data.teams.get(&(id, year)).map(|team| team.team_city_name.as_str()).unwrap_or("");
+            team_code(code.as_deref(), name)
+        };
+        let home_team = team_code(&box_meta.home_team_code, box_meta.home_team_id);
+        let away_team = team_code(&box_meta.away_team_code, box_meta.away_team_id);
+
+        let mut game = GameRecords::new(data.meta_data, box_meta);
+        game.info(&home_team, &away_team, sched_meta.game_date, sched_meta.game_venue_id);
+        game.records.push(format!("com,\"game_pk {}, sport_id {}\"", game_pk, sched_meta.sport_id));
+        game.starters();
+        for play in data.pitch_data.iter() {
+            game.plate_appearance(play);
+        };
+
+        Ok(EventGame {
+            game_pk,
+            game_date: sched_meta.game_date,
+            home_team,
+            away_team,
+            extension: if box_meta.home_league_id == Some(AMERICAN_LEAGUE) {"EVA"} else {"EVN"},
+            records: game.records,
+        })
+    }
+}
+
+// Three upper case letters, from the team code if we have one, otherwise from the team name.
+fn team_code (code: Option<&str>, name: &str) -> String {
+    let code: String = match code {
+        Some (code) if !code.is_empty() => code.to_string(),
+        _ => name.chars().filter(|c| c.is_ascii_alphabetic()).collect(),
+    };
+    format!("{:X<3}", code.to_uppercase().chars().take(3).collect::<String>())
+}
+
+// Retrosheet position numbers: 1-9 for the fielders, 10 for the DH and 11 for a pinch hitter.
+fn fielder (pos: Pos) -> u8 {
+    match pos {
+        Pos::Pitcher | Pos::StartingPitcher | Pos::ReliefPitcher => 1,
+        Pos::Catcher => 2,
+        Pos::FirstBase => 3,
+        Pos::SecondBase => 4,
+        Pos::ThirdBase => 5,
+        Pos::ShortStop => 6,
+        Pos::LeftField => 7,
+        Pos::CenterField => 8,
+        Pos::RightField => 9,
+        Pos::DesignatedHitter => 10,
+        Pos::Bench => 11,
+    }
+}
+
+// 0 for the visitors, 1 for the home team.
+fn batting_team (half_inning: HalfInning) -> u8 {
+    match half_inning {
+        HalfInning::Top => 0,
+        HalfInning::Bottom => 1,
+    }
+}
+
+fn pitch_code (code: &Option<Code>) -> &'static str {
+    match code {
+        Some (Code::B) => "B",
+        Some (Code::BD) => "*B",
+        Some (Code::C) => "C",
+        Some (Code::S) => "S",
+        Some (Code::W) => "*S",
+        Some (Code::F) => "F",
+        Some (Code::T) => "T",
+        Some (Code::L) => "L",
+        Some (Code::M) => "M",
+        Some (Code::D) | Some (Code::E) | Some (Code::X) => "X",
+        Some (Code::H) => "H",
+        Some (Code::P) => "P",
+        Some (Code::Q) => "Q",
+        Some (Code::R) => "R",
+        Some (Code::I) => "I",
+        Some (Code::V) => "V",
+        Some (Code::PO) => "1",
+        Some (Code::PO2) => "2",
+        Some (Code::PO3) => "3",
+        Some (Code::Other) | None => "U",
+    }
+}
+
+// Events that only involve the runners, so the batter stays at the plate.
+fn is_runner_event (event: Event) -> bool {
+    matches!(event,
+        Event::StolenBase | Event::CaughtStealing | Event::PickOff | Event::PickoffCaughtStealing | Event::PickOffError |
+        Event::WildPitch | Event::PassedBall | Event::Balk | Event::DefensiveIndifference | Event::RunnerOut
+    )
+}
+
+/// Where a runner (or the batter) started and finished on a play. Bases are 0 for the batter, 1-3, and 4 for home.
+struct Advance {
+    start: u8,
+    end: u8,
+    out: bool,
+    event: Event,
+    fielder: Option<u8>,
+}
+
+impl Advance {
+    // A runner can have more than one entry on a play, one for each leg of the trip, so we take the start of the first and the end
+    // of the last. Runners that are out don't have the base they were out at, so we use the next one.
+    fn from_runner_data (legs: &[&RunnerData]) -> Option<Advance> {
+        let first = legs.first()?;
+        let last = legs.last()?;
+        let start = base(first.start_base_value);
+        let out = legs.iter().any(|leg| leg.outs > 0);
+
+        let end = if last.runs > 0 {4}
+            else if out {start + 1}
+            else {base(last.end_base_value)};
+
+        // Runners taken off the bases without being put out (a pinch runner coming in, say) aren't a movement.
+        if end == 0 {return None};
+
+        Some(Advance {
+            start,
+            end,
+            out,
+            event: last.event,
+            fielder: legs.iter().filter_map(|leg| leg.fielded_by_pos).map(fielder).next(),
+        })
+    }
+
+    fn moved (&self) -> bool {
+        self.out || self.end != self.start
+    }
+}
+
+impl std::fmt::Display for Advance {
+    fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let start = match self.start {
+            0 => "B".to_string(),
+            base => base.to_string(),
+        };
+        let end = match self.end {
+            4 => "H".to_string(),
+            base => base.to_string(),
+        };
+        match (self.out, self.fielder) {
+            (true, Some (fielder)) => write!(f, "{}X{}({})", start, end, fielder),
+            (true, None) => write!(f, "{}X{}", start, end),
+            (false, _) => write!(f, "{}-{}", start, end),
+        }
+    }
+}
+
+fn base (base_value: u8) -> u8 {
+    match base_value {
+        0b001 => 1,
+        0b010 => 2,
+        0b100 => 3,
+        _ => 0,
+    }
+}
+
+// Combines the runner entries for a play into one advance per runner, lead runner first.
+fn advances (runners: &[&RunnerData]) -> Vec<Advance> {
+    let mut by_runner: Vec<(u32, Vec<&RunnerData>)> = Vec::new();
+    for runner in runners.iter() {
+        match by_runner.iter_mut().find(|(id, _)| *id == runner.runner_id) {
+            Some ((_, legs)) => legs.push(runner),
+            None => by_runner.push((runner.runner_id, vec![runner])),
+        };
+    };
+
+    let mut advances: Vec<Advance> = by_runner.iter()
+        .filter_map(|(_, legs)| Advance::from_runner_data(legs))
+        .collect();
+    advances.sort_by_key(|advance| std::cmp::Reverse(advance.start));
+    advances
+}
+
+fn with_advances (event: String, advances: &[&Advance]) -> String {
+    match advances.len() {
+        0 => event,
+        _ => format!("{}.{}", event, advances.iter().map(|advance| advance.to_string()).collect::<Vec<String>>().join(";")),
+    }
+}
+
+// A play by the runners alone: steals, pickoffs, wild pitches and so on.
+fn runner_play (advances: &[Advance]) -> String {
+    let mut events: Vec<String> = Vec::new();
+    let mut explicit: Vec<&Advance> = Vec::new();
+    let fielders = |advance: &Advance| advance.fielder.map(|fielder| fielder.to_string()).unwrap_or_default();
+
+    for advance in advances.iter().filter(|advance| advance.moved()) {
+        match (advance.event, advance.out) {
+            (Event::StolenBase, false) => events.push(format!("SB{}", if advance.end == 4 {"H".to_string()} else {advance.end.to_string()})),
+            (Event::CaughtStealing, true) => events.push(format!("CS{}({})", base_name(advance.end), fielders(advance))),
+            (Event::PickoffCaughtStealing, true) => events.push(format!("POCS{}({})", base_name(advance.end), fielders(advance))),
+            (Event::PickOff, true) => events.push(format!("PO{}({})", advance.start, fielders(advance))),
+            (Event::PickOffError, false) => {
+                events.push(format!("PO{}(E{})", advance.start, fielders(advance)));
+                explicit.push(advance);
+            },
+            _ => explicit.push(advance),
+        };
+    };
+
+    if events.is_empty() {
+        let event = match advances.first().map(|advance| advance.event) {
+            Some (Event::WildPitch) => "WP",
+            Some (Event::PassedBall) => "PB",
+            Some (Event::Balk) => "BK",
+            Some (Event::DefensiveIndifference) => "DI",
+            _ => "OA",
+        };
+        events.push(event.to_string());
+    };
+
+    with_advances(events.join(";"), &explicit)
+}
+
+fn base_name (base: u8) -> String {
+    match base {
+        4 => "H".to_string(),
+        base => base.to_string(),
+    }
+}
+
+// The batter's part of the play, along with where the batter ends up if nothing else is said.
+fn batter_play (result: Event, fielder: Option<u8>) -> (String, Option<u8>) {
+    let fielded = |prefix: &str| format!("{}{}", prefix, fielder.map(|fielder| fielder.to_string()).unwrap_or_default());
+    let out = |modifier: &str| match fielder {
+        Some (fielder) => format!("{}{}", fielder, modifier),
+        None => "99".to_string(),
+    };
+
+    match result {
+        Event::Single => (fielded("S"), Some(1)),
+        Event::Double => (fielded("D"), Some(2)),
+        Event::Triple => (fielded("T"), Some(3)),
+        Event::HomeRun => ("HR".to_string(), Some(4)),
+        Event::Walk => ("W".to_string(), Some(1)),
+        Event::IntentionalWalk => ("IW".to_string(), Some(1)),
+        Event::HitByPitch => ("HP".to_string(), Some(1)),
+        Event::CatcherInterference => ("C/E2".to_string(), Some(1)),
+        Event::FieldError => match fielder {
+            Some (fielder) => (format!("E{}", fielder), Some(1)),
+            None => ("99".to_string(), None),
+        },
+        Event::FieldersChoice | Event::ForceOut => (fielded("FC"), Some(1)),
+        Event::StrikeOut => ("K".to_string(), None),
+        Event::GroundOut => (out("/G"), None),
+        Event::FlyOut => (out("/F"), None),
+        Event::LineOut => (out("/L"), None),
+        Event::PopOut => (out("/P"), None),
+        Event::BuntGroundOut => (out("/BG"), None),
+        Event::BuntPopOut => (out("/BP"), None),
+        Event::SacFly => (out("/SF"), None),
+        Event::SacBunt => (out("/SH"), None),
+        Event::GroundedIntoDoublePlay => (out("/GDP"), None),
+        Event::DoublePlay => (out("/DP"), None),
+        Event::TriplePlay => (out("/TP"), None),
+        Event::SacFlyDoublePlay => (out("/SF/DP"), None),
+        Event::SacrificeBuntDoublePlay => (out("/SH/DP"), None),
+        Event::BatterInterference => (out("/BINT"), None),
+        _ => (out(""), None),
+    }
+}
+
+/// Records for a game as they're built up, along with the lineups, which we need to know who a substitute is replacing.
+struct GameRecords <'m> {
+    meta_data: &'m MetaData,
+    box_meta: &'m BoxScore,
+    records: Vec<String>,
+    // Lineup slot (1-9, 0 for a pitcher who isn't batting) of everyone on each team.
+    slots: HashMap<u32, u16>,
+    // Who's in each (team, slot) right now.
+    lineup: HashMap<(u8, u16), u32>,
+}
+
+impl <'m> GameRecords<'m> {
+    fn new (meta_data: &'m MetaData, box_meta: &'m BoxScore) -> GameRecords<'m> {
+        let slots = box_meta.away_players.iter().chain(box_meta.home_players.iter())
+            .map(|player| (player.id, player.batting_order.map(|order| order / 100).unwrap_or(0)))
+            .collect();
+
+        GameRecords {
+            meta_data,
+            box_meta,
+            records: Vec::with_capacity(600),
+            slots,
+            lineup: HashMap::new(),
+        }
+    }
+
+    // The Retrosheet id if the player has one, otherwise the MLBAM id.
+    fn player_id (&self, id: u32) -> String {
+        match self.meta_data.players.get(&id).and_then(|player| player.retrosheet_id.as_ref()) {
+            Some (retrosheet_id) if !retrosheet_id.is_empty() => retrosheet_id.to_string(),
+            _ => id.to_string(),
+        }
+    }
+
+    fn player_name (&self, id: u32) -> String {
+        self.meta_data.players.get(&id).map(|player| player.name.replace('"', "")).unwrap_or_default()
+    }
+
+    fn info (&mut self, home_team: &str, away_team: &str, game_date: Date, venue_id: u32) {
+        let box_meta = self.box_meta;
+        let mut info = vec![
+            ("visteam", away_team.to_string()),
+            ("hometeam", home_team.to_string()),
+        ];
+
+        let venue = self.meta_data.venue.get(&(venue_id, game_date.year));
+        match venue.map(|venue| venue.venue_retrosheet_id.as_str()) {
+            Some (site) if !site.is_empty() => info.push(("site", site.to_string())),
+            _ => info.push(("site", venue_id.to_string())),
+        };
+
+        info.push(("date", format!("{:04}/{:02}/{:02}", game_date.year, game_date.month, game_date.day)));
+
+        // First pitch is in hours, so 19.0833 is 7:05PM. A 12:05 pm start comes through as 24.0833.
+        let first_pitch = box_meta.first_pitch.map(|first_pitch| if first_pitch >= 24.0 {first_pitch - 12.0} else {first_pitch});
+        let night = first_pitch.map(|first_pitch| first_pitch >= 17.0);
+        if let Some (first_pitch) = first_pitch {
+            let hours = first_pitch as u8;
+            let minutes = ((first_pitch - first_pitch.floor()) * 60.0).round() as u8;
+            let hours_12 = match hours % 12 {0 => 12, hours => hours};
+            info.push(("starttime", format!("{}:{:02}{}", hours_12, minutes, if hours >= 12 {"PM"} else {"AM"})));
+        };
+        if let Some (night) = night {
+            info.push(("daynight", if night {"night"} else {"day"}.to_string()));
+        };
+
+        let used_dh = box_meta.home_defense.designated_hitter.is_some() || box_meta.away_defense.designated_hitter.is_some();
+        info.push(("usedh", used_dh.to_string()));
+
+        let crew = box_meta.umpire_crew();
+        let umpires = [
+            ("umphome", crew.home_plate), ("ump1b", crew.first_base), ("ump2b", crew.second_base), ("ump3b", crew.third_base),
+            ("umplf", crew.left_field), ("umprf", crew.right_field),
+        ];
+        for (field, umpire) in umpires.iter() {
+            if let Some (umpire) = umpire {
+                info.push((field, self.player_id(*umpire)));
+            };
+        };
+        if let Some (temp) = box_meta.game_weather_temp_f {
+            info.push(("temp", temp.to_string()));
+        };
+        if let Some (direction) = box_meta.game_wind_direction {
+            info.push(("winddir", wind_direction(direction).to_string()));
+        };
+        if let Some (speed) = box_meta.game_wind_speed_mph {
+            info.push(("windspeed", speed.to_string()));
+        };
+        if let Some (condition) = box_meta.game_weather_condition {
+            let (sky, precip) = sky(condition, night.unwrap_or(false));
+            info.push(("sky", sky.to_string()));
+            info.push(("precip", precip.to_string()));
+        };
+        if let Some (attendance) = box_meta.attendance {
+            info.push(("attendance", attendance.to_string()));
+        };
+
+        self.records.extend(info.into_iter().map(|(key, value)| format!("info,{},{}", key, value)));
+    }
+
+    // The starters are the players whose batting order ends in 00, plus the starting pitcher if there's a DH.
+    fn starters (&mut self) {
+        let box_meta = self.box_meta;
+        for (team, players) in [(0u8, &box_meta.away_players), (1u8, &box_meta.home_players)].iter() {
+            let mut starters: Vec<(u16, u8, u32)> = players.iter()
+                .filter_map(|player: &BoxScorePlayer| match (player.batting_order, player.position) {
+                    (Some (order), position) if order % 100 == 0 => Some((order / 100, fielder(position), player.id)),
+                    (None, Pos::StartingPitcher) => Some((0, 1, player.id)),
+                    _ => None,
+                })
+                .collect();
+            starters.sort_by_key(|(slot, _, _)| if *slot == 0 {10} else {*slot});
+
+            for (slot, position, id) in starters {
+                self.lineup.insert((*team, slot), id);
+                self.records.push(format!("start,{},\"{}\",{},{},{}", self.player_id(id), self.player_name(id), team, slot, position));
+            };
+        };
+    }
+
+    fn plate_appearance (&mut self, play: &AllPlays) {
+        let inning = play.about.inning_num;
+        let team = batting_team(play.about.half_inning);
+        let batter = play.matchup.batter_id;
+
+        // The runners that moved on the plate appearance itself. The rest moved on steals, wild pitches etc. before it, and get
+        // their own play records as we go.
+        let result = play.result.plate_appearance_result;
+        let final_index: i8 = play.runners.iter()
+            .filter(|runner| Some(runner.event) == result)
+            .map(|runner| runner.play_index)
+            .max()
+            .or_else(|| play.play_events.last().map(|event| event.index as i8))
+            .unwrap_or(-1);
+        let event_indexes: Vec<i8> = play.play_events.iter().map(|event| event.index as i8).collect();
+
+        let mut pitches = String::new();
+        let mut count = (0u8, 0u8);
+        let mut count_before = (0u8, 0u8);
+
+        for event in play.play_events.iter() {
+            match event.play_event_type {
+                PlayEventType::Pitch => {
+                    count_before = count;
+                    pitches.push_str(pitch_code(&event.details.code));
+                    count = (event.count.balls.unwrap_or(count.0), event.count.strikes.unwrap_or(count.1));
+                },
+                PlayEventType::Pickoff => pitches.push_str(pitch_code(&event.details.code)),
+                PlayEventType::Action => self.action(play, event, count, &pitches),
+                PlayEventType::NoPitch => {},
+            };
+
+            let index = event.index as i8;
+            if index != final_index {
+                let runners: Vec<&RunnerData> = play.runners.iter().filter(|runner| runner.play_index == index).collect();
+                let advances = advances(&runners);
+                if advances.iter().any(|advance| advance.moved()) {
+                    self.play(inning, team, batter, count, &pitches, runner_play(&advances));
+                    pitches.push('.');
+                };
+            };
+        };
+
+        let runners: Vec<&RunnerData> = play.runners.iter()
+            .filter(|runner| runner.play_index == final_index || !event_indexes.contains(&runner.play_index))
+            .collect();
+        let advances = advances(&runners);
+
+        let event = match result {
+            Some (result) if !is_runner_event(result) => {
+                let batter_advance = advances.iter().find(|advance| advance.start == 0);
+                let fielder = batter_advance.and_then(|advance| advance.fielder)
+                    .or_else(|| advances.iter().filter_map(|advance| advance.fielder).next());
+                let (event, batter_default) = batter_play(result, fielder);
+
+                // The batter is only written out when they don't end up where the event puts them, such as taking second on a throw.
+                let explicit: Vec<&Advance> = advances.iter()
+                    .filter(|advance| match advance.start {
+                        0 => match batter_default {
+                            Some (end) => advance.out || advance.end != end,
+                            None => !advance.out,
+                        },
+                        _ => advance.moved(),
+                    })
+                    .collect();
+                with_advances(event, &explicit)
+            },
+            Some (_) if advances.iter().any(|advance| advance.moved()) => runner_play(&advances),
+            // The game ended, or the runner event was already written, without anything else happening.
+            _ => return,
+        };
+
+        let count = if pitches.is_empty() {count} else {count_before};
+        self.play(inning, team, batter, count, &pitches, event);
+    }
+
+    fn play (&mut self, inning: u8, team: u8, batter: u32, count: (u8, u8), pitches: &str, event: String) {
+        self.records.push(format!("play,{},{},{},{}{},{},{}", inning, team, self.player_id(batter), count.0, count.1, pitches, event));
+    }
+
+    // Substitutions get a `sub` record, after an NP play for them to hang off. Ejections are noted in a comment.
+    fn action (&mut self, play: &AllPlays, event: &PlayEvent, count: (u8, u8), pitches: &str) {
+        let batting = batting_team(play.about.half_inning);
+        let fielding = 1 - batting;
+
+        let (team, position) = match event.details.event {
+            Some (Event::OffensiveSubstitution) => (batting, if event.base.is_some() {12} else {11}),
+            Some (Event::DefensiveSubstitution) | Some (Event::DefensiveSwitch) =>
+                (fielding, event.position.map(|position| fielder(position.abbreviation)).unwrap_or(10)),
+            Some (Event::PitchingSubstitution) => (fielding, 1),
+            Some (Event::Ejection) => {
+                if let Some (player) = &event.player {
+                    self.records.push(format!("com,\"ejection: {}\"", self.player_id(player.id)));
+                };
+                return;
+            },
+            _ => return,
+        };
+
+        let player = match &event.player {
+            Some (player) => player.id,
+            None => return,
+        };
+
+        // The NP play names whoever is in the batter's lineup slot before the substitution, which is the pinch hitter's predecessor
+        // if this is a pinch hitter coming in.
+        let batter = play.matchup.batter_id;
+        let current_batter = self.slots.get(&batter)
+            .and_then(|slot| self.lineup.get(&(batting, *slot)))
+            .copied()
+            .unwrap_or(batter);
+        self.play(play.about.inning_num, batting, current_batter, count, pitches, "NP".to_string());
+
+        let slot = self.slots.get(&player).copied().unwrap_or(0);
+        self.lineup.insert((team, slot), player);
+        self.records.push(format!("sub,{},\"{}\",{},{},{}", self.player_id(player), self.player_name(player), team, slot, position));
+    }
+}
+
+fn wind_direction (direction: WindDirection) -> &'static str {
+    match direction {
+        WindDirection::InFromCF => "fromcf",
+        WindDirection::InFromLF => "fromlf",
+        WindDirection::InFromRF => "fromrf",
+        WindDirection::LTOR => "ltor",
+        WindDirection::RTOL => "rtol",
+        WindDirection::OutToCF => "tocf",
+        WindDirection::OutToLF => "tolf",
+        WindDirection::OutToRF => "torf",
+        _ => "unknown",
+    }
+}
+
+fn sky (condition: WeatherCondition, night: bool) -> (&'static str, &'static str) {
+    let clear = if night {"night"} else {"sunny"};
+    match condition {
+        WeatherCondition::Clear | WeatherCondition::Sunny => (clear, "none"),
+        WeatherCondition::Cloudy | WeatherCondition::PartlyCloudy => ("cloudy", "none"),
+        WeatherCondition::Overcast => ("overcast", "none"),
+        WeatherCondition::Drizzle => ("overcast", "drizzle"),
+        WeatherCondition::Rain => ("overcast", "rain"),
+        WeatherCondition::Snow => ("overcast", "snow"),
+        WeatherCondition::Dome | WeatherCondition::RoofClosed => ("dome", "none"),
+        WeatherCondition::Other => ("unknown", "unknown"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance (start: u8, end: u8, out: bool, event: Event, fielder: Option<u8>) -> Advance {
+        Advance {start, end, out, event, fielder}
+    }
+
+    #[test]
+    fn pitch_codes () {
+        let sequence: String = [Some (Code::B), Some (Code::C), Some (Code::F), Some (Code::PO), Some (Code::BD), Some (Code::W), Some (Code::X)]
+            .iter()
+            .map(pitch_code)
+            .collect();
+        assert_eq!(sequence, "BCF1*B*SX");
+        assert_eq!(pitch_code(&Some (Code::D)), "X");
+        assert_eq!(pitch_code(&Some (Code::E)), "X");
+        assert_eq!(pitch_code(&Some (Code::PO3)), "3");
+        assert_eq!(pitch_code(&None), "U");
+    }
+
+    #[test]
+    fn advance_notation () {
+        assert_eq!(advance(0, 1, false, Event::Single, None).to_string(), "B-1");
+        assert_eq!(advance(3, 4, false, Event::Single, None).to_string(), "3-H");
+        assert_eq!(advance(1, 2, true, Event::ForceOut, Some (6)).to_string(), "1X2(6)");
+        assert_eq!(advance(2, 3, true, Event::ForceOut, None).to_string(), "2X3");
+        assert!(!advance(2, 2, false, Event::Single, None).moved());
+    }
+
+    #[test]
+    fn runner_plays () {
+        assert_eq!(runner_play(&[advance(1, 2, false, Event::StolenBase, None)]), "SB2");
+        assert_eq!(runner_play(&[advance(3, 4, false, Event::StolenBase, None)]), "SBH");
+        assert_eq!(runner_play(&[advance(1, 2, true, Event::CaughtStealing, Some (2))]), "CS2(2)");
+        assert_eq!(runner_play(&[advance(2, 3, true, Event::PickOff, Some (1))]), "PO2(1)");
+        assert_eq!(runner_play(&[advance(1, 2, true, Event::PickoffCaughtStealing, Some (1))]), "POCS2(1)");
+        assert_eq!(runner_play(&[advance(1, 3, false, Event::PickOffError, Some (1))]), "PO1(E1).1-3");
+
+        // Plays without a runner event of their own take their name from the first runner, and every runner is written out.
+        assert_eq!(runner_play(&[advance(3, 4, false, Event::WildPitch, None), advance(1, 2, false, Event::WildPitch, None)]), "WP.3-H;1-2");
+        assert_eq!(runner_play(&[advance(2, 3, false, Event::PassedBall, None)]), "PB.2-3");
+        assert_eq!(runner_play(&[advance(2, 3, false, Event::Balk, None)]), "BK.2-3");
+        assert_eq!(runner_play(&[advance(1, 2, false, Event::DefensiveIndifference, None)]), "DI.1-2");
+        assert_eq!(runner_play(&[advance(2, 3, true, Event::RunnerOut, Some (5))]), "OA.2X3(5)");
+
+        // A steal with another runner moving on the same play.
+        assert_eq!(runner_play(&[advance(3, 4, false, Event::StolenBase, None), advance(1, 2, false, Event::Single, None)]), "SBH.1-2");
+    }
+
+    #[test]
+    fn batter_plays () {
+        assert_eq!(batter_play(Event::Single, Some (8)), ("S8".to_string(), Some (1)));
+        assert_eq!(batter_play(Event::Double, None), ("D".to_string(), Some (2)));
+        assert_eq!(batter_play(Event::HomeRun, Some (7)), ("HR".to_string(), Some (4)));
+        assert_eq!(batter_play(Event::GroundOut, Some (6)), ("6/G".to_string(), None));
+        assert_eq!(batter_play(Event::GroundOut, None), ("99".to_string(), None));
+        assert_eq!(batter_play(Event::FieldError, Some (5)), ("E5".to_string(), Some (1)));
+        assert_eq!(batter_play(Event::StrikeOut, None), ("K".to_string(), None));
+        assert_eq!(batter_play(Event::SacFly, Some (9)), ("9/SF".to_string(), None));
+
+        let advances = [advance(0, 2, false, Event::Single, None), advance(2, 4, false, Event::Single, None)];
+        let explicit: Vec<&Advance> = advances.iter().collect();
+        assert_eq!(with_advances("S9".to_string(), &explicit), "S9.B-2;2-H");
+    }
+
+    #[test]
+    fn team_codes () {
+        assert_eq!(team_code(Some ("nya"), "New York"), "NYA");
+        assert_eq!(team_code(Some (""), "Durham"), "DUR");
+        assert_eq!(team_code(None, "St. Lucie"), "STL");
+        assert_eq!(team_code(None, "Al"), "ALX");
+    }
+
+    fn event_game (game_pk: u32, day: u8, home_team: &str) -> EventGame {
+        EventGame {
+            game_pk,
+            game_date: Date {year: 2019, month: 6, day},
+            home_team: home_team.to_string(),
+            away_team: "BOS".to_string(),
+            extension: "EVA",
+            records: vec![format!("com,\"game_pk {}\"", game_pk)],
+        }
+    }
+
+    #[test]
+    fn event_files_number_doubleheaders () {
+        let dir = std::env::temp_dir().join(format!("boss_retrosheet_{}_doubleheader", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let games = vec![event_game(3, 2, "NYA"), event_game(2, 1, "NYA"), event_game(1, 1, "NYA"), event_game(4, 1, "TOR")];
+        let paths = write_event_files(games, &dir).unwrap();
+        assert_eq!(paths, vec![dir.join("2019NYA.EVA"), dir.join("2019TOR.EVA")]);
+
+        let ids: Vec<String> = fs::read_to_string(&paths[0]).unwrap().lines()
+            .filter(|line| line.starts_with("id,") || line.starts_with("info,number") || line.starts_with("com,"))
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(ids, vec![
+            "id,NYA201906011", "info,number,1", "com,\"game_pk 1\"",
+            "id,NYA201906012", "info,number,2", "com,\"game_pk 2\"",
+            "id,NYA201906020", "info,number,0", "com,\"game_pk 3\"",
+        ]);
+        assert!(fs::read_to_string(&paths[1]).unwrap().starts_with("id,TOR201906010\nversion,2\ninfo,number,0\n"));
+    }
+}
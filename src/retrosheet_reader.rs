@@ -0,0 +1,1449 @@
+//! Reads Retrosheet event files (`.EVA`/`.EVN`), rosters (`.ROS`) and team files (`TEAM2004`) from a local folder, so seasons the
+//! Stats API doesn't have pitch data for can go through the same RE288 and defense code as everything else.
+//!
+//! ```no_run
+//! use boss::players::Player;
+//! use boss::retrosheet_reader::read_retrosheet;
+//! use std::collections::HashMap;
+//!
+//! // Usually the players from a `Boss` pull, so Retrosheet ids can be matched to MLBAM ids.
+//! let players: HashMap<u32, Player> = HashMap::new();
+//! let data = read_retrosheet("retrosheet/1998", &players)?;
+//! println!("{} pitches, {} plate appearances", data.pitches.len(), data.plate_appearances.len());
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! Games where every play has its pitch sequence come out as `game::Pitch` rows, one per pitch. Older games, where Retrosheet only
+//! has the result of each plate appearance, come out as `PlateAppearance` rows instead. A few things to keep in mind:
+//!
+//! * Players are matched to their MLBAM id through the `retrosheet_id` of the players passed in. Players we can't match get an id of
+//!   0, but keep their name and Retrosheet id.
+//! * Retrosheet games don't have a game_pk, so we make one up from the date, the home team's place in the team file and the game
+//!   number. These all have the top bit set, so they can't clash with real ones.
+//! * Balls in play only have the fielder who first handled the ball, and there's no pitch tracking or hit location data.
+
+use crate::boxscore::{Pos, WeatherCondition, WindDirection};
+use crate::error::{BossError, Dataset, Failure};
+use crate::game::Pitch;
+use crate::play_by_play::{Event, HalfInning, SideCode, Trajectory};
+use crate::players::{Player, SideCode as BatSideCode};
+use crate::schedule::{AbstractGameState, GameType, GameTypeDescription};
+use crate::utils::Date;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Everything read from a folder of Retrosheet files. Games that couldn't be read are in `failures`, keyed by their Retrosheet game id,
+/// such as "NYA201904010".
+#[derive(Debug, Default)]
+pub struct RetrosheetData {
+    pub pitches: Vec<Pitch>,
+    pub plate_appearances: Vec<PlateAppearance>,
+    pub failures: Vec<Failure>,
+}
+
+/// A plate appearance from a game without pitch sequences. The base/out state covers everything from the end of the previous plate
+/// appearance, so stolen bases and wild pitches along the way are included. When a half inning ends on a play by the runners, such as
+/// a caught stealing, we add a row for it with `re_24_batter_responsible` set to false.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlateAppearance {
+    pub game_pk: u32,
+    pub retrosheet_game_id: String,
+    pub game_date: String,
+    pub game_year: u16,
+    pub game_month: u8,
+    pub venue_retrosheet_id: String,
+
+    pub half_inning: HalfInning,
+    pub num_inning: u8,
+    pub num_plate_appearance: u8,
+
+    pub batter: u32,
+    pub batter_retrosheet_id: String,
+    pub batter_name: String,
+    pub batter_bats: SideCode,
+    pub batter_pos: Pos,
+    pub batter_batting_order: Option<u16>,
+    pub batter_team_name: String,
+
+    pub pitcher: u32,
+    pub pitcher_retrosheet_id: String,
+    pub pitcher_name: String,
+    pub pitcher_throws: SideCode,
+    pub pitcher_team_name: String,
+
+    /// The count when the plate appearance ended, when Retrosheet has it.
+    pub balls: Option<u8>,
+    pub strikes: Option<u8>,
+
+    pub outs_start: u8,
+    pub outs_end: u8,
+    pub base_value_start: u8,
+    pub base_value_end: u8,
+    pub runs_scored: u8,
+    pub re_24_batter_responsible: bool,
+    pub re_24_start: f32,
+    pub re_24_end: f32,
+    pub re_24_val: f32,
+
+    pub result: Event,
+    /// The event as it's written in the event file, such as "64(1)3/GDP".
+    pub retrosheet_event: String,
+    pub fielded_by_id: Option<u32>,
+    pub fielded_by_pos: Option<Pos>,
+    pub fielded_by_name: String,
+    pub hit_data_trajectory: Option<Trajectory>,
+    pub bunt: Option<bool>,
+}
+
+/// Reads every event file in `dir`, using the roster and team files next to them. Files are read in parallel, and a game that can't
+/// be read ends up in `failures` without stopping the rest.
+pub fn read_retrosheet <P: AsRef<Path>> (dir: P, players: &HashMap<u32, Player>) -> Result<RetrosheetData, BossError> {
+
+    let mut event_files: Vec<PathBuf> = Vec::new();
+    let mut rosters: HashMap<(u16, String), RosterPlayer> = HashMap::new();
+    let mut teams: HashMap<u16, Vec<TeamInfo>> = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some (name) => name.to_uppercase(),
+            None => continue,
+        };
+
+        if name.ends_with(".EVA") || name.ends_with(".EVN") {
+            event_files.push(path);
+        } else if name.ends_with(".ROS") {
+            let year = name.get(name.len().saturating_sub(8) .. name.len() - 4).and_then(|year| year.parse::<u16>().ok());
+            let year = year.ok_or_else(|| BossError::UnexpectedValue {field: "retrosheet roster file", value: name.clone()})?;
+            for fields in read_records(&path)? {
+                if fields.len() < 5 {continue};
+                rosters.insert((year, fields[0].clone()), RosterPlayer {
+                    name: format!("{} {}", fields[2], fields[1]),
+                    bats: fields[3].chars().next().unwrap_or('?'),
+                    throws: fields[4].chars().next().unwrap_or('?'),
+                });
+            };
+        } else if let Some (year) = name.strip_prefix("TEAM").and_then(|year| year.parse::<u16>().ok()) {
+            let year_teams = read_records(&path)?
+                .into_iter()
+                .filter(|fields| fields.len() >= 4)
+                .map(|fields| TeamInfo {
+                    code: fields[0].clone(),
+                    league: fields[1].clone(),
+                    name: format!("{} {}", fields[2], fields[3]),
+                })
+                .collect();
+            teams.insert(year, year_teams);
+        };
+    };
+
+    let context = Context {
+        players: players.values()
+            .filter_map(|player| player.retrosheet_id.as_deref().map(|id| (id, player)))
+            .collect(),
+        rosters,
+        teams,
+    };
+
+    // Keep the output in file order, so the same folder always gives the same rows.
+    event_files.sort();
+    let files: Vec<Result<Vec<Result<GameRows, Failure>>, BossError>> = event_files.par_iter()
+        .map(|path| {
+            Ok (split_games(read_records(path)?)
+                .into_iter()
+                .map(|records| read_game(&context, &records))
+                .collect())
+        })
+        .collect();
+
+    let mut data = RetrosheetData::default();
+    for file in files {
+        for game in file? {
+            match game {
+                Ok (rows) => {
+                    data.pitches.extend(rows.pitches);
+                    data.plate_appearances.extend(rows.plate_appearances);
+                },
+                Err (failure) => data.failures.push(failure),
+            };
+        };
+    };
+
+    Ok (data)
+}
+
+/// Retrosheet's first day of play. Synthetic game_pks count days from here.
+const FIRST_DAY: Date = Date {year: 1871, month: 1, day: 1};
+
+struct RosterPlayer {
+    name: String,
+    bats: char,
+    throws: char,
+}
+
+struct TeamInfo {
+    code: String,
+    league: String,
+    name: String,
+}
+
+struct Context <'p> {
+    /// MLBAM players by their Retrosheet id.
+    players: HashMap<&'p str, &'p Player>,
+    rosters: HashMap<(u16, String), RosterPlayer>,
+    teams: HashMap<u16, Vec<TeamInfo>>,
+}
+
+#[derive(Default)]
+struct GameRows {
+    pitches: Vec<Pitch>,
+    plate_appearances: Vec<PlateAppearance>,
+}
+
+/// Reads a Retrosheet file into its comma separated records. Names can be quoted and have commas in them, such as "Ripken, Jr.".
+fn read_records (path: &Path) -> Result<Vec<Vec<String>>, BossError> {
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    Ok (text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut fields = Vec::new();
+            let mut field = String::new();
+            let mut quoted = false;
+            for c in line.chars() {
+                match c {
+                    '"' => quoted = !quoted,
+                    ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                    _ => field.push(c),
+                };
+            };
+            fields.push(field);
+            fields
+        })
+        .collect())
+}
+
+/// Splits an event file into its games, each starting with an `id` record.
+fn split_games (records: Vec<Vec<String>>) -> Vec<Vec<Vec<String>>> {
+    let mut games: Vec<Vec<Vec<String>>> = Vec::new();
+    for record in records {
+        if record[0] == "id" {
+            games.push(Vec::new());
+        };
+        if let Some (game) = games.last_mut() {
+            game.push(record);
+        };
+    };
+    games
+}
+
+fn read_game (context: &Context, records: &[Vec<String>]) -> Result<GameRows, Failure> {
+    let game_id = records[0].get(1).cloned().unwrap_or_default();
+
+    let failure = |error: BossError| Failure {dataset: Dataset::Retrosheet, key: game_id.clone(), error};
+
+    let mut reader = GameReader::new(context, records).map_err(failure)?;
+    for record in records {
+        reader.record(record).map_err(failure)?;
+    };
+    Ok (reader.finish())
+}
+
+/// Where a play left the batter's plate appearance.
+#[derive(Debug, Default)]
+struct Play {
+    /// Set when the play ends the plate appearance.
+    batter_event: Option<Event>,
+    /// Set for plays by the runners, such as stolen bases and wild pitches.
+    runner_event: Option<Event>,
+    /// Where each runner ended up, keyed by the base they started on (0 for the batter). 4 is home and None is out.
+    moves: BTreeMap<u8, Option<u8>>,
+    /// The runners move up only if they're forced, like on a walk.
+    forced: bool,
+    fielder: Option<u8>,
+    trajectory: Option<Trajectory>,
+    bunt: bool,
+}
+
+fn unexpected (field: &'static str, value: &str) -> BossError {
+    BossError::UnexpectedValue {field, value: value.to_string()}
+}
+
+/// Splits on `sep`, but not inside parentheses, so "CS2(2E4/TH)" stays together.
+fn split_top (text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0u8;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c == sep && depth == 0 => {
+                parts.push(&text[start .. i]);
+                start = i + c.len_utf8();
+            },
+            _ => {},
+        };
+    };
+    parts.push(&text[start ..]);
+    parts
+}
+
+/// Base numbers as the event files write them: B is the batter and H is home.
+fn base (c: char) -> Option<u8> {
+    match c {
+        'B' => Some (0),
+        '1' => Some (1),
+        '2' => Some (2),
+        '3' => Some (3),
+        'H' => Some (4),
+        _ => None,
+    }
+}
+
+fn fielder_pos (fielder: u8) -> Pos {
+    match fielder {
+        1 => Pos::Pitcher,
+        2 => Pos::Catcher,
+        3 => Pos::FirstBase,
+        4 => Pos::SecondBase,
+        5 => Pos::ThirdBase,
+        6 => Pos::ShortStop,
+        7 => Pos::LeftField,
+        8 => Pos::CenterField,
+        9 => Pos::RightField,
+        10 => Pos::DesignatedHitter,
+        _ => Pos::Bench,
+    }
+}
+
+/// Parses the event field of a play record, such as "S8/L.2-H;1-3" or "K+SB2".
+fn parse_play (event: &str) -> Result<Play, BossError> {
+    // Uncertainty markers don't change what happened.
+    let text: String = event.chars().filter(|c| !matches!(c, '#' | '!' | '?' | ' ')).collect();
+    let pieces = split_top(&text, '.');
+    let parts = split_top(pieces[0], '/');
+    let basic = parts[0];
+
+    let codes: Vec<String> = parts[1 ..].iter()
+        .map(|modifier| modifier.chars().take_while(|c| c.is_ascii_uppercase()).collect())
+        .collect();
+    let has = |code: &str| codes.iter().any(|c| c == code);
+
+    let mut play = Play {
+        trajectory: codes.iter().find_map(|code| trajectory(code)),
+        bunt: has("SH") || codes.iter().any(|code| code == "B" || code.starts_with("BG") || code.starts_with("BP") || code == "BL"),
+        ..Play::default()
+    };
+
+    let fielder = |text: &str| text.chars().find_map(|c| c.to_digit(10)).map(|d| d as u8);
+    let reaches = |play: &mut Play, event: Event, base: u8| {
+        play.batter_event = Some (event);
+        play.moves.insert(0, Some (base));
+    };
+    let mut fielding_out = false;
+
+    if basic.starts_with(|c: char| c.is_ascii_digit()) {
+        fielding_outs(basic, &mut play);
+        fielding_out = true;
+    } else if basic == "NP" || basic.starts_with("FLE") {
+        // No play, or an error on a foul fly that only extends the plate appearance.
+    } else if let Some (rest) = basic.strip_prefix('K') {
+        play.batter_event = Some (Event::StrikeOut);
+        play.moves.insert(0, None);
+        if let Some ((_, extra)) = rest.split_once('+') {
+            runner_plays(extra, &mut play).map_err(|_| unexpected("retrosheet event", event))?;
+        };
+    } else if basic.starts_with("IW") || basic == "I" || basic.starts_with("I+") {
+        reaches(&mut play, Event::IntentionalWalk, 1);
+        play.forced = true;
+        if let Some ((_, extra)) = basic.split_once('+') {
+            runner_plays(extra, &mut play).map_err(|_| unexpected("retrosheet event", event))?;
+        };
+    } else if basic.starts_with('W') && !basic.starts_with("WP") {
+        reaches(&mut play, Event::Walk, 1);
+        play.forced = true;
+        if let Some ((_, extra)) = basic.split_once('+') {
+            runner_plays(extra, &mut play).map_err(|_| unexpected("retrosheet event", event))?;
+        };
+    } else if basic == "HP" {
+        reaches(&mut play, Event::HitByPitch, 1);
+        play.forced = true;
+    } else if basic.starts_with('H') {
+        reaches(&mut play, Event::HomeRun, 4);
+        play.fielder = fielder(basic);
+    } else if basic.starts_with('D') && !basic.starts_with("DI") {
+        reaches(&mut play, Event::Double, 2);
+        play.fielder = fielder(basic);
+    } else if basic.starts_with('S') && !basic.starts_with("SB") {
+        reaches(&mut play, Event::Single, 1);
+        play.fielder = fielder(basic);
+    } else if basic.starts_with('T') {
+        reaches(&mut play, Event::Triple, 3);
+        play.fielder = fielder(basic);
+    } else if basic.starts_with('E') {
+        reaches(&mut play, Event::FieldError, 1);
+        play.fielder = fielder(basic);
+    } else if basic.starts_with("FC") {
+        reaches(&mut play, Event::FieldersChoice, 1);
+        play.fielder = fielder(basic);
+    } else if basic == "C" {
+        reaches(&mut play, Event::CatcherInterference, 1);
+        play.forced = true;
+    } else {
+        runner_plays(basic, &mut play).map_err(|_| unexpected("retrosheet event", event))?;
+    };
+
+    if let Some (advances) = pieces.get(1) {
+        for advance in split_top(advances, ';').into_iter().filter(|advance| !advance.is_empty()) {
+            let chars: Vec<char> = advance.chars().take(3).collect();
+            let (from, to) = match (chars.first().copied().and_then(base), chars.get(2).copied().and_then(base)) {
+                (Some (from), Some (to)) => (from, to),
+                _ => return Err (unexpected("retrosheet advance", advance)),
+            };
+            // An error in the parentheses means the runner was put out, then safe on the error.
+            let out = match chars[1] {
+                '-' => false,
+                'X' => !advance[3 ..].contains('E'),
+                _ => return Err (unexpected("retrosheet advance", advance)),
+            };
+            play.moves.insert(from, if out {None} else {Some (to)});
+        };
+    };
+
+    if fielding_out {
+        let batter_out = play.moves.get(&0) == Some (&None);
+        let outs = play.moves.values().filter(|to| to.is_none()).count();
+        play.batter_event = Some (
+            if has("TP") || has("GTP") || has("LTP") || outs >= 3 {Event::TriplePlay}
+            else if has("GDP") {Event::GroundedIntoDoublePlay}
+            else if codes.iter().any(|code| code.ends_with("DP")) || outs == 2 {
+                if has("SF") {Event::SacFlyDoublePlay}
+                else if has("SH") {Event::SacrificeBuntDoublePlay}
+                else {Event::DoublePlay}
+            }
+            else if has("SF") {Event::SacFly}
+            else if has("SH") {Event::SacBunt}
+            else if !batter_out && has("FO") {Event::ForceOut}
+            else if !batter_out {Event::FieldersChoice}
+            else {
+                match play.trajectory {
+                    Some (Trajectory::GroundBall) => Event::GroundOut,
+                    Some (Trajectory::FlyBall) => Event::FlyOut,
+                    Some (Trajectory::LineDrive) => Event::LineOut,
+                    Some (Trajectory::PopUp) => Event::PopOut,
+                    Some (Trajectory::BuntGroundBall) => Event::BuntGroundOut,
+                    Some (Trajectory::BuntPopUp) => Event::BuntPopOut,
+                    _ => Event::FieldOut,
+                }
+            }
+        );
+    };
+
+    Ok (play)
+}
+
+fn trajectory (code: &str) -> Option<Trajectory> {
+    match code {
+        "G" | "GDP" | "GTP" => Some (Trajectory::GroundBall),
+        "F" | "FDP" | "SF" => Some (Trajectory::FlyBall),
+        "L" | "LDP" | "LTP" => Some (Trajectory::LineDrive),
+        "P" | "PDP" => Some (Trajectory::PopUp),
+        "BG" | "BGDP" => Some (Trajectory::BuntGroundBall),
+        "BP" | "BPDP" => Some (Trajectory::BuntPopUp),
+        "BL" => Some (Trajectory::BuntLineDrive),
+        _ => None,
+    }
+}
+
+/// Fielding outs like "63", "64(1)3" or "8(B)84(2)". A runner base in parentheses marks a runner put out. The batter is out unless the
+/// play ends on one of those, in which case he reached first.
+fn fielding_outs (basic: &str, play: &mut Play) {
+    play.fielder = basic.chars().next().and_then(|c| c.to_digit(10)).map(|d| d as u8);
+
+    let mut batter_out = false;
+    for part in basic.split('(').skip(1) {
+        if let Some (runner) = part.chars().next().and_then(base) {
+            play.moves.insert(runner, None);
+            if runner == 0 {batter_out = true};
+        };
+    };
+    if batter_out || !basic.ends_with(')') {
+        play.moves.insert(0, None);
+    } else {
+        play.moves.insert(0, Some (1));
+    };
+}
+
+/// Plays by the runners, such as "SB2;SB3", "CS2(24)", "PO1(E1)" or "WP", either on their own or after a strikeout or walk.
+fn runner_plays (text: &str, play: &mut Play) -> Result<(), ()> {
+    for part in split_top(text, ';') {
+        let (event, rest) = match part {
+            _ if part.starts_with("SB") => (Event::StolenBase, &part[2 ..]),
+            _ if part.starts_with("POCS") => (Event::PickoffCaughtStealing, &part[4 ..]),
+            _ if part.starts_with("CS") => (Event::CaughtStealing, &part[2 ..]),
+            _ if part.starts_with("PO") => (Event::PickOff, &part[2 ..]),
+            _ if part.starts_with("WP") => (Event::WildPitch, ""),
+            _ if part.starts_with("PB") => (Event::PassedBall, ""),
+            _ if part.starts_with("BK") => (Event::Balk, ""),
+            _ if part.starts_with("DI") => (Event::DefensiveIndifference, ""),
+            _ if part.starts_with("OA") => (Event::Other, ""),
+            "" => continue,
+            _ => return Err (()),
+        };
+
+        let safe = rest.contains('E');
+        let target = rest.chars().next().and_then(base);
+        match (event, target) {
+            (Event::StolenBase, Some (to)) if to > 1 => {play.moves.insert(to - 1, Some (to));},
+            (Event::CaughtStealing, Some (to)) | (Event::PickoffCaughtStealing, Some (to)) if to > 1 => {
+                play.moves.insert(to - 1, if safe {Some (to)} else {None});
+            },
+            (Event::PickOff, Some (on)) if on > 0 && !safe => {play.moves.insert(on, None);},
+            // Safe on an error, the runner stays put unless the advances say otherwise.
+            (Event::PickOff, Some (on)) if on > 0 => {},
+            (Event::StolenBase, _) | (Event::CaughtStealing, _) | (Event::PickoffCaughtStealing, _) | (Event::PickOff, _) => return Err (()),
+            _ => {},
+        };
+
+        let event = match event {
+            Event::PickOff if safe => Event::PickOffError,
+            event => event,
+        };
+        play.runner_event.get_or_insert(event);
+    };
+    Ok (())
+}
+
+/// One pitch from a pitch sequence, like the "C" in "BC*B1X".
+#[derive(Debug, Copy, Clone)]
+struct SequencePitch {
+    code: char,
+    after_pickoff: bool,
+}
+
+fn parse_pitches (sequence: &str) -> Result<Vec<SequencePitch>, BossError> {
+    let mut pitches = Vec::new();
+    let mut after_pickoff = false;
+    for c in sequence.chars() {
+        match c {
+            'B' | 'C' | 'F' | 'H' | 'I' | 'K' | 'L' | 'M' | 'O' | 'P' | 'Q' | 'R' | 'S' | 'T' | 'U' | 'V' | 'X' | 'Y' => {
+                pitches.push(SequencePitch {code: c, after_pickoff});
+                after_pickoff = false;
+            },
+            // Pickoff throws, by the pitcher (1-3) or catcher (+1-3).
+            '1' | '2' | '3' => after_pickoff = true,
+            // Blocked by the catcher, runner going, catcher pickoff marker, play not involving the batter and no pitch.
+            '*' | '>' | '+' | '.' | 'N' => {},
+            _ => return Err (unexpected("retrosheet pitch sequence", sequence)),
+        };
+    };
+    Ok (pitches)
+}
+
+/// The plate appearance in progress.
+struct PlateAppearanceState {
+    pitches_seen: usize,
+    pitches: u8,
+    balls: u8,
+    strikes: u8,
+    outs_start: u8,
+    base_value_start: u8,
+    runs: u8,
+}
+
+struct GameReader <'c> {
+    context: &'c Context <'c>,
+    year: u16,
+    game_id: String,
+    game_pk: u32,
+    date: Date,
+    info: HashMap<String, String>,
+    home_team: String,
+    away_team: String,
+    home_league: Option<String>,
+    away_league: Option<String>,
+    pitch_level: bool,
+
+    /// Names from the start and sub records.
+    names: HashMap<String, String>,
+    /// Batting order by team (0 = visitors) and slot, where slot 0 is a pitcher who doesn't bat.
+    lineups: [[Option<String>; 10]; 2],
+    /// Fielders by team and position number, 1 (pitcher) to 10 (designated hitter).
+    fielders: [[Option<String>; 11]; 2],
+    /// Batting and pitching hands from `badj` and `padj` records.
+    hand_adjustments: HashMap<String, char>,
+
+    half: Option<(u8, HalfInning)>,
+    outs: u8,
+    bases: [Option<String>; 4],
+    pending_runner: Option<(usize, String)>,
+    plate_appearance: Option<PlateAppearanceState>,
+    num_plate_appearance: u8,
+    pitch_num_inning: u8,
+    pitch_num_game: u16,
+    /// Index of the first pitch of the half inning in `rows.pitches`.
+    half_start: usize,
+    /// The state before plays that came between pitches, such as a stolen base. Like the Stats API play by play, these go on the
+    /// next pitch, which isn't the batter's responsibility.
+    carried: Option<(u8, u8)>,
+    carried_runs: u8,
+    rows: GameRows,
+}
+
+impl <'c> GameReader <'c> {
+
+    fn new (context: &'c Context<'c>, records: &[Vec<String>]) -> Result<Self, BossError> {
+        let game_id = records[0].get(1).cloned().unwrap_or_default();
+
+        let info: HashMap<String, String> = records.iter()
+            .filter(|record| record[0] == "info" && record.len() >= 3)
+            .map(|record| (record[1].clone(), record[2].clone()))
+            .collect();
+
+        let date = info.get("date")
+            .and_then(|date| {
+                let parts: Vec<u32> = date.split('/').filter_map(|part| part.parse().ok()).collect();
+                match parts[..] {
+                    [year, month, day] => Some (Date {year: year as u16, month: month as u8, day: day as u8}),
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| unexpected("retrosheet date", info.get("date").map(|date| date.as_str()).unwrap_or("")))?;
+        let year = date.year;
+
+        let home_code = info.get("hometeam").cloned().unwrap_or_default();
+        let away_code = info.get("visteam").cloned().unwrap_or_default();
+        let year_teams: &[TeamInfo] = context.teams.get(&year).map(|teams| teams.as_slice()).unwrap_or(&[]);
+        let team = |code: &str| year_teams.iter().enumerate().find(|(_, team)| team.code == code);
+        let home_index = team(&home_code).map(|(index, _)| index as u32).ok_or_else(|| unexpected("retrosheet team", &home_code))?;
+
+        let league = |code: &str| team(code).map(|(_, team)| match team.league.as_str() {
+            "A" => "American League".to_string(),
+            "N" => "National League".to_string(),
+            league => league.to_string(),
+        });
+        let name = |code: &str| team(code).map(|(_, team)| team.name.clone()).unwrap_or_else(|| code.to_string());
+
+        let game_number = game_id.chars().last().and_then(|c| c.to_digit(10)).unwrap_or(0);
+        let days = (date.day_number() - FIRST_DAY.day_number()) as u32;
+        let game_pk = 1 << 31 | days << 15 | (home_index & 0x1fff) << 2 | game_number & 0x3;
+
+        // Pitch sequences go back to 1988, with some gaps. A game gets pitch rows only when all of its plays have their count.
+        let plays = records.iter().filter(|record| record[0] == "play" && record.len() >= 7);
+        let pitch_level = plays.clone().all(|record| record[4] != "??" && !record[4].is_empty())
+            && plays.clone().any(|record| !record[5].is_empty());
+
+        Ok (GameReader {
+            context,
+            year,
+            game_id,
+            game_pk,
+            date,
+            home_league: league(&home_code),
+            away_league: league(&away_code),
+            home_team: name(&home_code),
+            away_team: name(&away_code),
+            info,
+            pitch_level,
+            names: HashMap::new(),
+            lineups: Default::default(),
+            fielders: Default::default(),
+            hand_adjustments: HashMap::new(),
+            half: None,
+            outs: 0,
+            bases: Default::default(),
+            pending_runner: None,
+            plate_appearance: None,
+            num_plate_appearance: 0,
+            pitch_num_inning: 0,
+            pitch_num_game: 0,
+            half_start: 0,
+            carried: None,
+            carried_runs: 0,
+            rows: GameRows::default(),
+        })
+    }
+
+    fn record (&mut self, record: &[String]) -> Result<(), BossError> {
+        match record[0].as_str() {
+            "start" | "sub" if record.len() >= 6 => {
+                let id = record[1].clone();
+                let team = if record[3] == "1" {1} else {0};
+                let slot: usize = record[4].parse().map_err(|_| unexpected("retrosheet batting order", &record[4]))?;
+                let pos: usize = record[5].parse().map_err(|_| unexpected("retrosheet position", &record[5]))?;
+                self.names.insert(id.clone(), record[2].clone());
+
+                if slot < 10 {
+                    // A pinch runner takes the place of the runner he replaces.
+                    if pos == 12 {
+                        if let Some (replaced) = &self.lineups[team][slot] {
+                            for runner in self.bases.iter_mut().filter(|runner| runner.as_ref() == Some (replaced)) {
+                                *runner = Some (id.clone());
+                            };
+                        };
+                    };
+                    if slot > 0 || pos == 1 {
+                        self.lineups[team][slot] = Some (id.clone());
+                    };
+                };
+                if (1 ..= 10).contains(&pos) {
+                    for fielder in self.fielders[team].iter_mut().filter(|fielder| fielder.as_ref() == Some (&id)) {
+                        *fielder = None;
+                    };
+                    self.fielders[team][pos] = Some (id);
+                };
+            },
+            "badj" | "padj" if record.len() >= 3 => {
+                if let Some (hand) = record[2].chars().next() {
+                    self.hand_adjustments.insert(format!("{}{}", record[0], record[1]), hand);
+                };
+            },
+            "radj" if record.len() >= 3 => {
+                let base: usize = record[2].parse().map_err(|_| unexpected("retrosheet base", &record[2]))?;
+                self.pending_runner = Some ((base, record[1].clone()));
+            },
+            "play" if record.len() >= 7 => self.play(record)?,
+            _ => {},
+        };
+        Ok (())
+    }
+
+    fn base_value (&self) -> u8 {
+        (1 ..= 3).filter(|&base| self.bases[base].is_some()).map(|base| 1 << (base - 1)).sum()
+    }
+
+    fn play (&mut self, record: &[String]) -> Result<(), BossError> {
+        let inning: u8 = record[1].parse().map_err(|_| unexpected("retrosheet inning", &record[1]))?;
+        let half_inning = if record[2] == "1" {HalfInning::Bottom} else {HalfInning::Top};
+        let batter = record[3].clone();
+        let event = record[6].as_str();
+
+        if self.half != Some ((inning, half_inning)) {
+            self.half = Some ((inning, half_inning));
+            self.outs = 0;
+            self.bases = Default::default();
+            self.plate_appearance = None;
+            self.pitch_num_inning = 0;
+            self.half_start = self.rows.pitches.len();
+            self.carried = None;
+            self.carried_runs = 0;
+            if let Some ((base, runner)) = self.pending_runner.take() {
+                if (1 ..= 3).contains(&base) {self.bases[base] = Some (runner)};
+            };
+        };
+
+        let play = parse_play(event)?;
+        let sequence = if self.pitch_level {parse_pitches(&record[5])?} else {Vec::new()};
+
+        if self.plate_appearance.is_none() {
+            self.num_plate_appearance += 1;
+            self.plate_appearance = Some (PlateAppearanceState {
+                pitches_seen: 0,
+                pitches: 0,
+                balls: 0,
+                strikes: 0,
+                outs_start: self.outs,
+                base_value_start: self.base_value(),
+                runs: 0,
+            });
+        };
+
+        let outs_start = self.outs;
+        let base_value_start = self.base_value();
+        let runs = self.apply(&play, &batter);
+
+        if self.pitch_level {
+            self.pitches(&sequence, &play, &batter, outs_start, base_value_start, runs, half_inning, inning);
+        };
+
+        let plate_appearance = self.plate_appearance.as_mut().expect("opened above");
+        plate_appearance.runs += runs;
+
+        if play.batter_event.is_some() || self.outs >= 3 {
+            if !self.pitch_level {
+                let result = play.batter_event.or(play.runner_event).unwrap_or(Event::Other);
+                let count: Vec<u8> = record[4].chars().filter_map(|c| c.to_digit(10)).map(|d| d as u8).collect();
+                self.push_plate_appearance(&batter, half_inning, inning, result, event, &play, count);
+            };
+            self.plate_appearance = None;
+        };
+
+        Ok (())
+    }
+
+    /// Moves the runners and batter, returning the runs that scored.
+    fn apply (&mut self, play: &Play, batter: &str) -> u8 {
+        let mut moves = play.moves.clone();
+
+        // Walks and the like only say where the batter went, the runners he forced along are implied.
+        if play.forced {
+            for base in 1 ..= 3u8 {
+                if self.bases[base as usize].is_none() {break};
+                moves.entry(base).or_insert(Some (base + 1));
+            };
+        };
+
+        let mut bases: [Option<String>; 4] = Default::default();
+        let mut runs = 0;
+        let mut outs = 0;
+        for from in (0 ..= 3u8).rev() {
+            let runner = match from {
+                0 => batter.to_string(),
+                from => self.bases[from as usize].take().unwrap_or_default(),
+            };
+            match moves.get(&from) {
+                Some (None) => outs += 1,
+                Some (Some (4)) => runs += 1,
+                Some (Some (to)) => bases[*to as usize] = Some (runner),
+                None if from == 0 => {},
+                None => bases[from as usize] = Some (runner),
+            };
+        };
+        // A runner we didn't know about, such as one left over from a play that didn't parse the way it was meant, shouldn't show
+        // up as an empty name on base.
+        for base in bases.iter_mut() {
+            if base.as_deref() == Some ("") {*base = None};
+        };
+
+        self.bases = bases;
+        self.outs = (self.outs + outs).min(3);
+        runs
+    }
+
+    /// Adds the pitches we haven't seen yet in this plate appearance. The state change from the play goes on the last of them. When the
+    /// play came between pitches it goes on the next one, unless it ended the half inning, in which case it goes on the previous one.
+    #[allow(clippy::too_many_arguments)]
+    fn pitches (&mut self, sequence: &[SequencePitch], play: &Play, batter: &str, outs_start: u8, base_value_start: u8, runs: u8, half_inning: HalfInning, inning: u8) {
+        let seen = self.plate_appearance.as_ref().map(|plate_appearance| plate_appearance.pitches_seen).unwrap_or(0);
+        let new: Vec<SequencePitch> = sequence.iter().skip(seen).copied().collect();
+        if let Some (plate_appearance) = self.plate_appearance.as_mut() {
+            plate_appearance.pitches_seen = seen.max(sequence.len());
+        };
+
+        let outs_end = self.outs;
+        let base_value_end = self.base_value();
+        let changed = outs_end != outs_start || base_value_end != base_value_start || runs > 0;
+
+        if new.is_empty() {
+            if changed {
+                self.carried.get_or_insert((outs_start, base_value_start));
+                self.carried_runs += runs;
+            };
+            if outs_end >= 3 && self.carried.take().is_some() {
+                let runs = std::mem::take(&mut self.carried_runs);
+                if let Some (last) = self.rows.pitches[self.half_start ..].last_mut() {
+                    last.outs_end = outs_end;
+                    last.base_value_end = base_value_end;
+                    last.runs_scored += runs;
+                    last.re_288_batter_responsible = false;
+                };
+            };
+            return;
+        };
+
+        for (i, sequence_pitch) in new.iter().enumerate() {
+            let last = i + 1 == new.len();
+            let plate_appearance = self.plate_appearance.as_mut().expect("opened before the play");
+            let (balls_start, strikes_start) = (plate_appearance.balls, plate_appearance.strikes);
+
+            let (mut swing, mut swing_and_miss, mut foul, mut in_play, mut bunt) = (0, None, 0, 0, None);
+            match sequence_pitch.code {
+                'B' | 'I' | 'P' | 'V' => plate_appearance.balls += 1,
+                'C' | 'K' => plate_appearance.strikes += 1,
+                'S' | 'Q' => {
+                    plate_appearance.strikes += 1;
+                    swing = 1;
+                    swing_and_miss = Some (1);
+                },
+                'M' => {
+                    plate_appearance.strikes += 1;
+                    swing = 1;
+                    swing_and_miss = Some (1);
+                    bunt = Some (true);
+                },
+                'T' => {
+                    plate_appearance.strikes += 1;
+                    swing = 1;
+                    swing_and_miss = Some (0);
+                },
+                'L' | 'O' => {
+                    plate_appearance.strikes += 1;
+                    swing = 1;
+                    foul = 1;
+                    bunt = Some (true);
+                },
+                'F' | 'R' => {
+                    if plate_appearance.strikes < 2 {plate_appearance.strikes += 1};
+                    swing = 1;
+                    foul = 1;
+                },
+                'X' | 'Y' => {
+                    swing = 1;
+                    swing_and_miss = Some (0);
+                    in_play = 1;
+                    bunt = Some (play.bunt);
+                },
+                _ => {},
+            };
+            plate_appearance.pitches += 1;
+            let (balls_end, strikes_end, pitch_num_plate_appearance) = (plate_appearance.balls, plate_appearance.strikes, plate_appearance.pitches);
+
+            self.pitch_num_inning += 1;
+            self.pitch_num_game += 1;
+
+            let batter_event = if last {play.batter_event} else {None};
+            let in_play_result = if in_play == 1 {batter_event} else {None};
+            let hit = |event: Event| in_play_result.map(|result| if result == event {1} else {0});
+            let fielded_by = if in_play == 1 {play.fielder} else {None};
+            let (fielded_by_id, fielded_by_name) = match fielded_by {
+                Some (fielder) => self.fielder(fielder as usize),
+                None => (None, String::new()),
+            };
+
+            let carried = self.carried.take();
+            let carried_runs = std::mem::take(&mut self.carried_runs);
+            let (pitch_outs_start, pitch_base_value_start) = carried.unwrap_or((outs_start, base_value_start));
+            let mut pitch = Pitch {
+                half_inning,
+                num_inning: inning,
+                num_plate_appearance: self.num_plate_appearance,
+                pitch_num_plate_appearance,
+                pitch_num_inning: self.pitch_num_inning,
+                pitch_num_game: self.pitch_num_game,
+                preceded_by_pickoff: sequence_pitch.after_pickoff,
+                double_play_opportunity: pitch_outs_start < 2 && pitch_base_value_start % 2 == 1,
+                balls_start,
+                balls_end,
+                strikes_start,
+                strikes_end,
+                outs_start: pitch_outs_start,
+                outs_end: if last {outs_end} else {outs_start},
+                base_value_start: pitch_base_value_start,
+                base_value_end: if last {base_value_end} else {base_value_start},
+                runs_scored: if last {runs} else {0} + carried_runs,
+                re_288_batter_responsible: carried.is_none() && !(last && changed && play.batter_event.is_none()),
+                in_play,
+                swing,
+                swing_and_miss,
+                foul,
+                bunt,
+                in_play_result,
+                in_play_1b: hit(Event::Single),
+                in_play_2b: hit(Event::Double),
+                in_play_3b: hit(Event::Triple),
+                in_play_hr: hit(Event::HomeRun),
+                strikeout: if batter_event == Some (Event::StrikeOut) {1} else {0},
+                walk: if matches!(batter_event, Some (Event::Walk) | Some (Event::IntentionalWalk)) {1} else {0},
+                fielded_by_id,
+                fielded_by_pos: fielded_by.map(fielder_pos),
+                fielded_by_name,
+                hit_data_trajectory: if in_play == 1 {play.trajectory} else {None},
+                ..self.game_pitch()
+            };
+            self.people(&mut pitch, batter, half_inning);
+            self.rows.pitches.push(pitch);
+        };
+    }
+
+    /// The MLBAM id and name of whoever is playing a position for the fielding team.
+    fn fielder (&self, position: usize) -> (Option<u32>, String) {
+        let team = match self.half {
+            Some ((_, HalfInning::Bottom)) => 0,
+            _ => 1,
+        };
+        match self.fielders[team].get(position).cloned().flatten() {
+            Some (id) => (self.context.players.get(id.as_str()).map(|player| player.id), self.name(&id)),
+            None => (None, String::new()),
+        }
+    }
+
+    fn name (&self, id: &str) -> String {
+        self.names.get(id).cloned()
+            .or_else(|| self.context.rosters.get(&(self.year, id.to_string())).map(|player| player.name.clone()))
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// The batting and throwing hands, from the roster when we have it, otherwise from the player's bio.
+    fn hand (&self, id: &str, batting: bool) -> Option<char> {
+        let adjustment = if batting {format!("badj{}", id)} else {format!("padj{}", id)};
+        self.hand_adjustments.get(&adjustment).copied()
+            .or_else(|| self.context.rosters.get(&(self.year, id.to_string())).map(|player| if batting {player.bats} else {player.throws}))
+            .or_else(|| {
+                let player = self.context.players.get(id)?;
+                let side = if batting {player.bat_side_code} else {player.throws_code};
+                side.map(|side| match side {
+                    BatSideCode::L => 'L',
+                    BatSideCode::R => 'R',
+                    BatSideCode::S | BatSideCode::B => 'B',
+                })
+            })
+    }
+
+    /// Who's batting and pitching, and the side the batter actually hit from. Switch hitters bat opposite the pitcher's hand.
+    fn matchup (&self, batter: &str, half_inning: HalfInning) -> (usize, Option<String>, SideCode, Option<BatSideCode>, SideCode) {
+        let (batting, fielding) = match half_inning {
+            HalfInning::Top => (0, 1),
+            HalfInning::Bottom => (1, 0),
+        };
+        let pitcher = self.fielders[fielding][1].clone();
+
+        let throws = pitcher.as_deref().and_then(|pitcher| self.hand(pitcher, false));
+        let pitcher_throws = match throws {
+            Some ('L') => SideCode::L,
+            Some ('R') => SideCode::R,
+            _ => SideCode::Other,
+        };
+        let (batter_stands, batter_bats) = match self.hand(batter, true) {
+            Some ('L') => (Some (BatSideCode::L), SideCode::L),
+            Some ('R') => (Some (BatSideCode::R), SideCode::R),
+            Some ('B') | Some ('S') => (Some (BatSideCode::S), if throws == Some ('L') {SideCode::R} else {SideCode::L}),
+            _ => (None, SideCode::Other),
+        };
+        (batting, pitcher, batter_bats, batter_stands, pitcher_throws)
+    }
+
+    fn batting_order (&self, team: usize, batter: &str) -> Option<u16> {
+        self.lineups[team].iter()
+            .position(|id| id.as_deref() == Some (batter))
+            .filter(|&slot| slot > 0)
+            .map(|slot| slot as u16 * 100)
+    }
+
+    fn batter_pos (&self, team: usize, batter: &str) -> Pos {
+        self.fielders[team].iter()
+            .position(|id| id.as_deref() == Some (batter))
+            .map(|position| fielder_pos(position as u8))
+            .unwrap_or(Pos::Bench)
+    }
+
+    /// Fills in the batter, pitcher and fielders of a pitch.
+    fn people (&self, pitch: &mut Pitch, batter: &str, half_inning: HalfInning) {
+        let (batting, pitcher, batter_bats, batter_stands, pitcher_throws) = self.matchup(batter, half_inning);
+        let (batting_team, fielding_team) = match batting {
+            0 => (&self.away_team, &self.home_team),
+            _ => (&self.home_team, &self.away_team),
+        };
+
+        pitch.batter_team_name = batting_team.clone();
+        pitch.batter_parent_team_name = batting_team.clone();
+        pitch.pitcher_team_name = fielding_team.clone();
+        pitch.pitcher_parent_team_name = fielding_team.clone();
+        pitch.batter_bats = batter_bats;
+        pitch.batter_stands = batter_stands;
+        pitch.pitcher_throws = pitcher_throws;
+        pitch.batter_pos = self.batter_pos(batting, batter);
+        pitch.batter_batting_order = self.batting_order(batting, batter);
+
+        pitch.batter = self.bio(batter, |player| player.id).unwrap_or(0);
+        pitch.batter_name = self.name(batter);
+        pitch.batter_retrosheet_id = Some (batter.to_string());
+        pitch.batter_dob = self.bio(batter, |player| player.birth_date.map(|dob| dob.to_string())).flatten().unwrap_or_default();
+        pitch.batter_mlb_debut_date = self.bio(batter, |player| player.mlb_debut_date.map(|debut| debut.to_string())).flatten().unwrap_or_default();
+        pitch.batter_age = self.bio(batter, |player| player.birth_date.map(|dob| self.date - dob)).flatten();
+        pitch.batter_height_str = self.bio(batter, |player| player.height_str.clone()).flatten();
+        pitch.batter_height_in = self.bio(batter, |player| player.height_in).unwrap_or(0);
+        pitch.batter_weight = self.bio(batter, |player| player.weight).flatten();
+        pitch.batter_fangraphs_id = self.bio(batter, |player| player.fangraphs_id.clone()).flatten();
+
+        if let Some (pitcher) = pitcher {
+            pitch.pitcher = self.bio(&pitcher, |player| player.id).unwrap_or(0);
+            pitch.pitcher_name = self.name(&pitcher);
+            pitch.pitcher_dob = self.bio(&pitcher, |player| player.birth_date.map(|dob| dob.to_string())).flatten().unwrap_or_default();
+            pitch.pitcher_mlb_debut_date = self.bio(&pitcher, |player| player.mlb_debut_date.map(|debut| debut.to_string())).flatten().unwrap_or_default();
+            pitch.pitcher_age = self.bio(&pitcher, |player| player.birth_date.map(|dob| self.date - dob)).flatten();
+            pitch.pitcher_height_str = self.bio(&pitcher, |player| player.height_str.clone()).flatten();
+            pitch.pitcher_height_in = self.bio(&pitcher, |player| player.height_in).unwrap_or(0);
+            pitch.pitcher_weight = self.bio(&pitcher, |player| player.weight).flatten();
+            pitch.pitcher_fangraphs_id = self.bio(&pitcher, |player| player.fangraphs_id.clone()).flatten();
+            pitch.pitcher_retrosheet_id = Some (pitcher);
+        };
+
+        let fielder = |position| self.fielder(position);
+        let name = |name: String| if name.is_empty() {None} else {Some (name)};
+        let (id, fielder_name) = fielder(2);
+        pitch.catcher_id = id;
+        pitch.catcher_name = name(fielder_name);
+        let (id, fielder_name) = fielder(3);
+        pitch.first_base_id = id;
+        pitch.first_base_name = name(fielder_name);
+        let (id, fielder_name) = fielder(4);
+        pitch.second_base_id = id;
+        pitch.second_base_name = name(fielder_name);
+        let (id, fielder_name) = fielder(5);
+        pitch.third_base_id = id;
+        pitch.third_base_name = name(fielder_name);
+        let (id, fielder_name) = fielder(6);
+        pitch.short_stop_id = id;
+        pitch.short_stop_name = name(fielder_name);
+        let (id, fielder_name) = fielder(7);
+        pitch.left_field_id = id;
+        pitch.left_field_name = name(fielder_name);
+        let (id, fielder_name) = fielder(8);
+        pitch.center_field_id = id;
+        pitch.center_field_name = name(fielder_name);
+        let (id, fielder_name) = fielder(9);
+        pitch.right_field_id = id;
+        pitch.right_field_name = name(fielder_name);
+    }
+
+    fn bio <T> (&self, id: &str, field: impl Fn(&Player) -> T) -> Option<T> {
+        self.context.players.get(id).map(|player| field(player))
+    }
+
+    /// A pitch with just the game level fields filled in.
+    fn game_pitch (&self) -> Pitch {
+        let sport = crate::sports::get_sport(1).ok();
+        let info = |key: &str| self.info.get(key).map(|value| value.as_str()).unwrap_or("");
+
+        let temp_f = info("temp").parse::<u8>().ok().filter(|&temp| temp > 0);
+        let (sky, precip) = (info("sky"), info("precip"));
+        let condition = match precip {
+            "drizzle" => Some (WeatherCondition::Drizzle),
+            "rain" | "showers" => Some (WeatherCondition::Rain),
+            "snow" => Some (WeatherCondition::Snow),
+            _ => match sky {
+                "sunny" => Some (WeatherCondition::Sunny),
+                "cloudy" => Some (WeatherCondition::Cloudy),
+                "overcast" => Some (WeatherCondition::Overcast),
+                "dome" => Some (WeatherCondition::Dome),
+                "night" => Some (WeatherCondition::Clear),
+                _ => None,
+            },
+        };
+        let wind_direction = match info("winddir") {
+            "fromcf" => Some (WindDirection::InFromCF),
+            "fromlf" => Some (WindDirection::InFromLF),
+            "fromrf" => Some (WindDirection::InFromRF),
+            "ltor" => Some (WindDirection::LTOR),
+            "rtol" => Some (WindDirection::RTOL),
+            "tocf" => Some (WindDirection::OutToCF),
+            "tolf" => Some (WindDirection::OutToLF),
+            "torf" => Some (WindDirection::OutToRF),
+            _ => None,
+        };
+
+        // Start times look like "7:05PM", with "0:00PM" when they aren't known.
+        let start_time = info("starttime").to_uppercase();
+        let first_pitch = start_time.trim_end_matches("AM").trim_end_matches("PM").split_once(':')
+            .and_then(|(hours, minutes)| Some ((hours.parse::<f32>().ok()?, minutes.parse::<f32>().ok()?)))
+            .filter(|(hours, _)| *hours > 0f32)
+            .map(|(hours, minutes)| hours % 12f32 + minutes / 60f32 + if start_time.ends_with("PM") {12f32} else {0f32});
+
+        Pitch {
+            game_pk: self.game_pk,
+            game_type: GameType::R,
+            game_type_desc: GameTypeDescription::RegularSeason,
+            game_date: self.date.to_string(),
+            game_year: self.date.year,
+            game_month: self.date.month,
+            game_status: AbstractGameState::Final,
+            sport_id: 1,
+            sport_code: sport.map(|sport| sport.code.to_string()).unwrap_or_default(),
+            sport_name: sport.map(|sport| sport.name.to_string()).unwrap_or_default(),
+            sport_abbr: sport.map(|sport| sport.abbr.to_string()).unwrap_or_default(),
+            sport_affilliation: sport.map(|sport| sport.affiliation).unwrap_or_default(),
+            sport_level_of_play: sport.map(|sport| sport.level_of_play_rank).unwrap_or(0),
+            league_name_home: self.home_league.clone(),
+            league_name_away: self.away_league.clone(),
+            venue_retrosheet_id: info("site").to_string(),
+            game_attendance: info("attendance").parse::<u32>().ok().filter(|&attendance| attendance > 0),
+            game_first_pitch: first_pitch,
+            game_weather_temp_f: temp_f,
+            game_weather_temp_c: temp_f.map(|temp| ((temp as f32 - 32f32) * 5f32 / 9f32) as i8),
+            game_weather_condition: condition,
+            game_wind_speed_mph: info("windspeed").parse::<u8>().ok(),
+            game_wind_direction: wind_direction,
+            ..Pitch::default()
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_plate_appearance (&mut self, batter: &str, half_inning: HalfInning, inning: u8, result: Event, event: &str, play: &Play, count: Vec<u8>) {
+        let (batting, pitcher, batter_bats, _, pitcher_throws) = self.matchup(batter, half_inning);
+        let (batting_team, fielding_team) = match batting {
+            0 => (&self.away_team, &self.home_team),
+            _ => (&self.home_team, &self.away_team),
+        };
+        let plate_appearance = self.plate_appearance.as_ref().expect("opened before the play");
+        let (fielded_by_id, fielded_by_name) = match play.fielder {
+            Some (fielder) if play.batter_event.is_some() => self.fielder(fielder as usize),
+            _ => (None, String::new()),
+        };
+        let pitcher = pitcher.unwrap_or_default();
+
+        let outs_end = self.outs;
+        let base_value_end = self.base_value();
+        let re_24_start = re_24(plate_appearance.base_value_start, plate_appearance.outs_start);
+        let re_24_end = if outs_end >= 3 {0f32} else {re_24(base_value_end, outs_end)};
+
+        let row = PlateAppearance {
+            game_pk: self.game_pk,
+            retrosheet_game_id: self.game_id.clone(),
+            game_date: self.date.to_string(),
+            game_year: self.date.year,
+            game_month: self.date.month,
+            venue_retrosheet_id: self.info.get("site").cloned().unwrap_or_default(),
+            half_inning,
+            num_inning: inning,
+            num_plate_appearance: self.num_plate_appearance,
+            batter: self.bio(batter, |player| player.id).unwrap_or(0),
+            batter_retrosheet_id: batter.to_string(),
+            batter_name: self.name(batter),
+            batter_bats,
+            batter_pos: self.batter_pos(batting, batter),
+            batter_batting_order: self.batting_order(batting, batter),
+            batter_team_name: batting_team.clone(),
+            pitcher: self.bio(&pitcher, |player| player.id).unwrap_or(0),
+            pitcher_name: self.name(&pitcher),
+            pitcher_retrosheet_id: pitcher,
+            pitcher_throws,
+            pitcher_team_name: fielding_team.clone(),
+            balls: count.first().copied(),
+            strikes: count.get(1).copied(),
+            outs_start: plate_appearance.outs_start,
+            outs_end,
+            base_value_start: plate_appearance.base_value_start,
+            base_value_end,
+            runs_scored: plate_appearance.runs,
+            re_24_batter_responsible: play.batter_event.is_some(),
+            re_24_start,
+            re_24_end,
+            re_24_val: re_24_end - re_24_start + plate_appearance.runs as f32,
+            result,
+            retrosheet_event: event.to_string(),
+            fielded_by_id,
+            fielded_by_pos: play.fielder.filter(|_| play.batter_event.is_some()).map(fielder_pos),
+            fielded_by_name,
+            hit_data_trajectory: play.trajectory,
+            bunt: play.batter_event.map(|_| play.bunt),
+        };
+        self.rows.plate_appearances.push(row);
+    }
+
+    /// Fills in the run expectancy of the pitches, now that their end states are final.
+    fn finish (mut self) -> GameRows {
+        for pitch in self.rows.pitches.iter_mut() {
+            pitch.re_288_start = re_288(pitch.balls_start, pitch.strikes_start, pitch.base_value_start, pitch.outs_start);
+            pitch.re_288_end = if pitch.outs_end >= 3 {0f32} else {re_288(pitch.balls_end % 4, pitch.strikes_end % 3, pitch.base_value_end, pitch.outs_end)};
+            pitch.re_288_val = pitch.re_288_end - pitch.re_288_start + pitch.runs_scored as f32;
+        };
+        self.rows
+    }
+}
+
+fn re_288 (balls: u8, strikes: u8, base_value: u8, outs: u8) -> f32 {
+    crate::run_expectancy::RE288_DEFAULT.iter()
+        .find(|re| re.balls == balls && re.strikes == strikes && re.base_value == base_value && re.outs == outs)
+        .map(|re| re.run_expectancy)
+        .unwrap_or(0f32)
+}
+
+/// RE24 is the 0-0 count of RE288.
+fn re_24 (base_value: u8, outs: u8) -> f32 {
+    re_288(0, 0, base_value, outs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves (play: &Play) -> Vec<(u8, Option<u8>)> {
+        play.moves.iter().map(|(from, to)| (*from, *to)).collect()
+    }
+
+    #[test]
+    fn hit_with_advances () {
+        let play = parse_play("S8/L.2-H;1-3").unwrap();
+        assert_eq!(play.batter_event, Some (Event::Single));
+        assert_eq!(play.runner_event, None);
+        assert_eq!(moves(&play), vec![(0, Some (1)), (1, Some (3)), (2, Some (4))]);
+        assert_eq!(play.fielder, Some (8));
+        assert!(matches!(play.trajectory, Some (Trajectory::LineDrive)));
+    }
+
+    #[test]
+    fn double_plays () {
+        let play = parse_play("64(1)3/GDP").unwrap();
+        assert_eq!(play.batter_event, Some (Event::GroundedIntoDoublePlay));
+        assert_eq!(moves(&play), vec![(0, None), (1, None)]);
+        assert_eq!(play.fielder, Some (6));
+
+        let play = parse_play("8(B)84(2)/LDP").unwrap();
+        assert_eq!(play.batter_event, Some (Event::DoublePlay));
+        assert_eq!(play.runner_event, None);
+        assert_eq!(moves(&play), vec![(0, None), (2, None)]);
+        assert!(matches!(play.trajectory, Some (Trajectory::LineDrive)));
+    }
+
+    #[test]
+    fn strikeout_and_walk_with_runner_plays () {
+        let play = parse_play("K+SB2").unwrap();
+        assert_eq!(play.batter_event, Some (Event::StrikeOut));
+        assert_eq!(play.runner_event, Some (Event::StolenBase));
+        assert_eq!(moves(&play), vec![(0, None), (1, Some (2))]);
+        assert!(!play.forced);
+
+        let play = parse_play("W+WP.1-2").unwrap();
+        assert_eq!(play.batter_event, Some (Event::Walk));
+        assert_eq!(play.runner_event, Some (Event::WildPitch));
+        assert_eq!(moves(&play), vec![(0, Some (1)), (1, Some (2))]);
+        assert!(play.forced);
+    }
+
+    #[test]
+    fn runner_plays_on_errors () {
+        // Safe at second on the error, then on to third.
+        let play = parse_play("CS2(2E4).2-3").unwrap();
+        assert_eq!(play.batter_event, None);
+        assert_eq!(play.runner_event, Some (Event::CaughtStealing));
+        assert_eq!(moves(&play), vec![(1, Some (2)), (2, Some (3))]);
+
+        let play = parse_play("PO1(E1)").unwrap();
+        assert_eq!(play.batter_event, None);
+        assert_eq!(play.runner_event, Some (Event::PickOffError));
+        assert!(play.moves.is_empty());
+    }
+
+    #[test]
+    fn fielders_choice_and_hit_by_pitch () {
+        let play = parse_play("FC6.1X2(64)").unwrap();
+        assert_eq!(play.batter_event, Some (Event::FieldersChoice));
+        assert_eq!(moves(&play), vec![(0, Some (1)), (1, None)]);
+        assert_eq!(play.fielder, Some (6));
+
+        let play = parse_play("HP").unwrap();
+        assert_eq!(play.batter_event, Some (Event::HitByPitch));
+        assert_eq!(play.runner_event, None);
+        assert_eq!(moves(&play), vec![(0, Some (1))]);
+        assert!(play.forced);
+    }
+
+    // The top of the first has a single, a stolen base between plate appearances, a double that scores the runner, a ground out, a
+    // strikeout and a caught stealing for the third out. The bottom of the first is a home run. `count` fills in the count field.
+    fn game (count: &str) -> Vec<Vec<String>> {
+        let lines = [
+            "id,NYA201904010".to_string(),
+            "info,visteam,BOS".to_string(),
+            "info,hometeam,NYA".to_string(),
+            "info,date,2019/04/01".to_string(),
+            "start,a1,Away One,0,1,8".to_string(),
+            "start,a2,Away Two,0,2,6".to_string(),
+            "start,a3,Away Three,0,3,3".to_string(),
+            "start,a4,Away Four,0,4,7".to_string(),
+            "start,ap,Away Pitcher,0,0,1".to_string(),
+            "start,h1,Home One,1,1,6".to_string(),
+            "start,h2,Home Catcher,1,2,2".to_string(),
+            "start,h8,Home Center,1,3,8".to_string(),
+            "start,hp,Home Pitcher,1,0,1".to_string(),
+            format!("play,1,0,a1,{},BCX,S8/L", count),
+            format!("play,1,0,a2,{},,SB2", count),
+            format!("play,1,0,a2,{},BX,D7/F.2-H", count),
+            format!("play,1,0,a3,{},CSX,63/G", count),
+            format!("play,1,0,a4,{},CSS,K", count),
+            format!("play,1,0,a1,{},,CS3(25)", count),
+            format!("play,1,1,h1,{},X,H9/F", count),
+        ];
+        lines.iter().map(|line| line.split(',').map(|field| field.to_string()).collect()).collect()
+    }
+
+    fn read (records: &[Vec<String>]) -> GameRows {
+        let teams = vec![
+            TeamInfo {code: "BOS".to_string(), league: "A".to_string(), name: "Boston Red Sox".to_string()},
+            TeamInfo {code: "NYA".to_string(), league: "A".to_string(), name: "New York Yankees".to_string()},
+        ];
+        let context = Context {
+            players: HashMap::new(),
+            rosters: HashMap::new(),
+            teams: vec![(2019, teams)].into_iter().collect(),
+        };
+        match read_game(&context, records) {
+            Ok (rows) => rows,
+            Err (failure) => panic!("game didn't read: {}", failure),
+        }
+    }
+
+    #[test]
+    fn pitch_states () {
+        let rows = read(&game("00"));
+        assert!(rows.plate_appearances.is_empty());
+
+        let states: Vec<(u8, u8, u8, u8, u8, bool)> = rows.pitches.iter()
+            .map(|pitch| (pitch.outs_start, pitch.outs_end, pitch.base_value_start, pitch.base_value_end, pitch.runs_scored, pitch.re_288_batter_responsible))
+            .collect();
+        assert_eq!(states, vec![
+            // Single
+            (0, 0, 0, 0, 0, true),
+            (0, 0, 0, 0, 0, true),
+            (0, 0, 0, 1, 0, true),
+            // The stolen base goes on the next pitch, which isn't the batter's doing. Then the double.
+            (0, 0, 1, 2, 0, false),
+            (0, 0, 2, 2, 1, true),
+            // Ground out
+            (0, 0, 2, 2, 0, true),
+            (0, 0, 2, 2, 0, true),
+            (0, 1, 2, 2, 0, true),
+            // Strikeout, with the caught stealing that ends the inning put on its last pitch
+            (1, 1, 2, 2, 0, true),
+            (1, 1, 2, 2, 0, true),
+            (1, 3, 2, 0, 0, false),
+            // Home run
+            (0, 0, 0, 0, 1, true),
+        ]);
+
+        let counts: Vec<(u8, u8, u8, u8)> = rows.pitches.iter()
+            .map(|pitch| (pitch.balls_start, pitch.strikes_start, pitch.balls_end, pitch.strikes_end))
+            .collect();
+        assert_eq!(&counts[.. 3], &[(0, 0, 1, 0), (1, 0, 1, 1), (1, 1, 1, 1)]);
+        assert_eq!(counts[10], (0, 2, 0, 3));
+
+        for pitch in rows.pitches.iter() {
+            assert_eq!(pitch.re_288_start, re_288(pitch.balls_start, pitch.strikes_start, pitch.base_value_start, pitch.outs_start));
+            assert_eq!(pitch.re_288_val, pitch.re_288_end - pitch.re_288_start + pitch.runs_scored as f32);
+        };
+        assert!(rows.pitches[0].re_288_start > 0f32);
+        assert_eq!(rows.pitches[2].re_288_end, re_288(1, 1, 1, 0));
+        assert_eq!(rows.pitches[10].re_288_end, 0f32);
+        assert_eq!(rows.pitches[11].re_288_val, 1f32);
+
+        assert_eq!(rows.pitches[2].in_play_result, Some (Event::Single));
+        assert_eq!(rows.pitches[2].fielded_by_pos, Some (Pos::CenterField));
+        assert_eq!(rows.pitches[2].fielded_by_name, "Home Center");
+        assert_eq!(rows.pitches[10].strikeout, 1);
+        assert_eq!(rows.pitches[11].half_inning, HalfInning::Bottom);
+        assert_eq!(rows.pitches[11].pitch_num_inning, 1);
+        assert_eq!(rows.pitches[11].pitch_num_game, 12);
+    }
+
+    #[test]
+    fn plate_appearances_without_counts () {
+        let rows = read(&game("??"));
+        assert!(rows.pitches.is_empty());
+
+        let states: Vec<(Event, u8, u8, u8, u8, u8, bool)> = rows.plate_appearances.iter()
+            .map(|pa| (pa.result, pa.outs_start, pa.outs_end, pa.base_value_start, pa.base_value_end, pa.runs_scored, pa.re_24_batter_responsible))
+            .collect();
+        assert_eq!(states, vec![
+            (Event::Single, 0, 0, 0, 1, 0, true),
+            // The stolen base is part of the double's plate appearance.
+            (Event::Double, 0, 0, 1, 2, 1, true),
+            (Event::GroundOut, 0, 1, 2, 2, 0, true),
+            (Event::StrikeOut, 1, 2, 2, 2, 0, true),
+            (Event::CaughtStealing, 2, 3, 2, 0, 0, false),
+            (Event::HomeRun, 0, 0, 0, 0, 1, true),
+        ]);
+
+        for pa in rows.plate_appearances.iter() {
+            assert_eq!(pa.balls, None);
+            assert_eq!(pa.strikes, None);
+            assert_eq!(pa.re_24_val, pa.re_24_end - pa.re_24_start + pa.runs_scored as f32);
+        };
+        assert_eq!(rows.plate_appearances[4].re_24_end, 0f32);
+        assert_eq!(rows.plate_appearances[1].re_24_start, re_24(1, 0));
+    }
+}
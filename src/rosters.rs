@@ -0,0 +1,429 @@
+//! Team roster snapshots. The boxscore tells us who played, but not who was on the roster, so for playing time and call-up work we
+//! pull the Stats API roster (`teams/{id}/roster?rosterType=40Man&date=...`) for each team and day we're interested in:
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::config::BossConfig;
+//! use boss::get_data::get_roster_data;
+//! use boss::rosters::{RosterHistory, RosterType, roster_requests};
+//! use boss::team::TeamData;
+//! use boss::transport::LiveTransport;
+//! use boss::utils::Date;
+//!
+//! let data = Boss::new().years(2019 ..= 2019).sports(vec![1]).fetch()?;
+//! let teams: Vec<TeamData> = data.meta_data.teams.iter()
+//!     .map(|((_, year), team)| TeamData {year: *year, team: team.clone()})
+//!     .collect();
+//!
+//! let start = Date {year: 2019, month: 3, day: 28};
+//! let end = Date {year: 2019, month: 9, day: 29};
+//! let days: Vec<Date> = (start.day_number() ..= end.day_number()).map(Date::from_day_number).collect();
+//!
+//! let requests = roster_requests(&teams, &days, &[RosterType::Active, RosterType::FortyMan]);
+//! let (snapshots, _failures) = get_roster_data(&BossConfig::default(), &LiveTransport::new()?, &requests)?;
+//!
+//! let history = RosterHistory::from(snapshots);
+//! for moves in history.moves(111, RosterType::Active) {
+//!     println!("{} to {}: {} added, {} removed", moves.from.to_string(), moves.to.to_string(), moves.added.len(), moves.removed.len());
+//! }
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! Snapshots are cached one file per team and season (`rosters_{team_id}_{season}.json`), so adding a day or a team only pulls
+//! what's missing. A roster for a day that hadn't finished when we pulled it can still change, so it's pulled again next time.
+
+use crate::error::BossError;
+use crate::team::TeamData;
+use crate::utils::Date;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Which roster to pull. The active roster is the 26 (or 25) players available for the game, the 40 man roster also has the players
+/// on the injured list and those optioned to the minors.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RosterType {
+    #[serde(rename="active")]
+    Active,
+    #[serde(rename="40Man")]
+    FortyMan,
+}
+
+impl RosterType {
+    /// The `rosterType` parameter for the API.
+    pub fn param (&self) -> &'static str {
+        match self {
+            RosterType::Active => "active",
+            RosterType::FortyMan => "40Man",
+        }
+    }
+}
+
+/// A player's status on the roster, from the API's status code. Codes we don't group are kept as `Other` with the code, such as
+/// "BRV" for the bereavement list or "SU" for suspended. Reads and writes as the status code.
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(from="String", into="String")]
+pub enum RosterStatus {
+    /// "A"
+    Active,
+    /// Any of the injured (or disabled) lists, kept with the code: "D7", "D10", "D15" or "D60".
+    InjuredList(String),
+    /// On the 40 man roster but in the minors ("MIN"), which is where optioned players show up.
+    Optioned,
+    Other(String),
+}
+
+impl RosterStatus {
+    pub fn code (&self) -> &str {
+        match self {
+            RosterStatus::Active => "A",
+            RosterStatus::InjuredList (code) => code,
+            RosterStatus::Optioned => "MIN",
+            RosterStatus::Other (code) => code,
+        }
+    }
+
+    pub fn is_injured_list (&self) -> bool {
+        matches!(self, RosterStatus::InjuredList (_))
+    }
+}
+
+impl From<String> for RosterStatus {
+    fn from (code: String) -> RosterStatus {
+        match code.as_str() {
+            "A" => RosterStatus::Active,
+            "MIN" => RosterStatus::Optioned,
+            _ if code.len() > 1 && code.starts_with('D') && code[1..].chars().all(|c| c.is_ascii_digit()) => RosterStatus::InjuredList(code),
+            _ => RosterStatus::Other(code),
+        }
+    }
+}
+
+impl From<RosterStatus> for String {
+    fn from (status: RosterStatus) -> String {
+        status.code().to_string()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RosterEntry {
+    pub player_id: u32,
+    pub name: String,
+    pub jersey_number: Option<String>,
+    /// The position abbreviation, such as "SS" or "P".
+    pub position: Option<String>,
+    pub status: RosterStatus,
+    pub status_description: String,
+}
+
+/// One team's roster of one type, as it stood on one day.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RosterSnapshot {
+    pub team_id: u32,
+    pub date: Date,
+    pub roster_type: RosterType,
+    pub entries: Vec<RosterEntry>,
+}
+
+impl RosterSnapshot {
+    pub fn get (&self, player_id: u32) -> Option<&RosterEntry> {
+        self.entries.iter().find(|entry| entry.player_id == player_id)
+    }
+
+    pub fn request (&self) -> RosterRequest {
+        RosterRequest {team_id: self.team_id, date: self.date, roster_type: self.roster_type}
+    }
+
+    /// What changed between this snapshot and a later one of the same team and roster type.
+    pub fn diff (&self, later: &RosterSnapshot) -> RosterMoves {
+        let before: HashMap<u32, &RosterEntry> = self.entries.iter().map(|entry| (entry.player_id, entry)).collect();
+        let after: HashMap<u32, &RosterEntry> = later.entries.iter().map(|entry| (entry.player_id, entry)).collect();
+
+        let added = later.entries.iter().filter(|entry| !before.contains_key(&entry.player_id)).cloned().collect();
+        let removed = self.entries.iter().filter(|entry| !after.contains_key(&entry.player_id)).cloned().collect();
+        let status_changes = self.entries.iter()
+            .filter_map(|entry| {
+                let now = after.get(&entry.player_id)?;
+                if now.status == entry.status {return None};
+                Some (StatusChange {player_id: entry.player_id, before: entry.status.clone(), after: now.status.clone()})
+            })
+            .collect();
+
+        RosterMoves {
+            team_id: self.team_id,
+            roster_type: self.roster_type,
+            from: self.date,
+            to: later.date,
+            added,
+            removed,
+            status_changes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub player_id: u32,
+    pub before: RosterStatus,
+    pub after: RosterStatus,
+}
+
+/// The roster movement between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RosterMoves {
+    pub team_id: u32,
+    pub roster_type: RosterType,
+    pub from: Date,
+    pub to: Date,
+    pub added: Vec<RosterEntry>,
+    pub removed: Vec<RosterEntry>,
+    /// Players on both rosters whose status changed, such as a move to the injured list on the 40 man roster.
+    pub status_changes: Vec<StatusChange>,
+}
+
+impl RosterMoves {
+    pub fn is_empty (&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.status_changes.is_empty()
+    }
+}
+
+/// One roster to pull: a team, a day and a roster type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct RosterRequest {
+    pub team_id: u32,
+    pub date: Date,
+    pub roster_type: RosterType,
+}
+
+impl RosterRequest {
+    pub fn url (&self) -> String {
+        format!("{}teams/{}/roster?rosterType={}&date={}/{}/{}",
+            crate::BASE_URL, self.team_id, self.roster_type.param(), self.date.month, self.date.day, self.date.year)
+    }
+}
+
+/// Every roster type for every team on each day of its season. Days from other seasons than the team's are skipped.
+pub fn roster_requests (teams: &[TeamData], dates: &[Date], roster_types: &[RosterType]) -> Vec<RosterRequest> {
+    let mut requests = Vec::new();
+    for team in teams {
+        for date in dates.iter().filter(|date| date.year == team.year) {
+            for roster_type in roster_types {
+                requests.push(RosterRequest {team_id: team.team.id, date: *date, roster_type: *roster_type});
+            };
+        };
+    };
+    requests.sort();
+    requests.dedup();
+    requests
+}
+
+/// Roster snapshots by team, roster type and day.
+#[derive(Debug, Clone, Default)]
+pub struct RosterHistory {
+    snapshots: BTreeMap<(u32, RosterType, Date), RosterSnapshot>,
+}
+
+impl RosterHistory {
+    /// The roster as it stood on `date`: the latest snapshot on or before it.
+    pub fn on (&self, team_id: u32, roster_type: RosterType, date: Date) -> Option<&RosterSnapshot> {
+        let first = Date {year: 0, month: 0, day: 0};
+        self.snapshots.range((team_id, roster_type, first) ..= (team_id, roster_type, date))
+            .next_back()
+            .map(|(_, snapshot)| snapshot)
+    }
+
+    /// The snapshots for a team and roster type, in date order.
+    pub fn snapshots (&self, team_id: u32, roster_type: RosterType) -> impl Iterator<Item = &RosterSnapshot> {
+        let first = Date {year: 0, month: 0, day: 0};
+        let last = Date {year: u16::MAX, month: u8::MAX, day: u8::MAX};
+        self.snapshots.range((team_id, roster_type, first) ..= (team_id, roster_type, last)).map(|(_, snapshot)| snapshot)
+    }
+
+    /// The movement between each pair of consecutive snapshots for a team and roster type. Days without any movement are left out.
+    pub fn moves (&self, team_id: u32, roster_type: RosterType) -> Vec<RosterMoves> {
+        let snapshots: Vec<&RosterSnapshot> = self.snapshots(team_id, roster_type).collect();
+        snapshots.windows(2)
+            .map(|pair| pair[0].diff(pair[1]))
+            .filter(|moves| !moves.is_empty())
+            .collect()
+    }
+
+    pub fn len (&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+impl From<Vec<RosterSnapshot>> for RosterHistory {
+    fn from (snapshots: Vec<RosterSnapshot>) -> RosterHistory {
+        RosterHistory {
+            snapshots: snapshots.into_iter()
+                .map(|snapshot| ((snapshot.team_id, snapshot.roster_type, snapshot.date), snapshot))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all="camelCase")]
+pub (crate) struct RosterJson {
+    #[serde(default)]
+    roster: Vec<RosterEntryDe>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all="camelCase")]
+struct RosterEntryDe {
+    person: PersonDe,
+    jersey_number: Option<String>,
+    position: Option<PositionDe>,
+    status: Option<StatusDe>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all="camelCase")]
+struct PersonDe {
+    id: u32,
+    full_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PositionDe {
+    abbreviation: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StatusDe {
+    code: String,
+    description: Option<String>,
+}
+
+/// Parses the roster endpoint's response for `request`. A team without a roster that day comes back as an empty snapshot.
+pub (crate) fn parse_roster (request: RosterRequest, json: &str) -> Result<RosterSnapshot, BossError> {
+    let roster: RosterJson = serde_json::from_str(json)?;
+    let entries = roster.roster.into_iter()
+        .map(|entry| {
+            let (status, status_description) = match entry.status {
+                Some (status) => (status.code.into(), status.description.unwrap_or_default()),
+                None => (RosterStatus::Other(String::new()), String::new()),
+            };
+            RosterEntry {
+                player_id: entry.person.id,
+                name: entry.person.full_name.unwrap_or_default(),
+                jersey_number: entry.jersey_number.filter(|number| !number.is_empty()),
+                position: entry.position.and_then(|position| position.abbreviation),
+                status,
+                status_description,
+            }
+        })
+        .collect();
+
+    Ok(RosterSnapshot {
+        team_id: request.team_id,
+        date: request.date,
+        roster_type: request.roster_type,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry (player_id: u32, status: &str) -> RosterEntry {
+        RosterEntry {
+            player_id,
+            name: format!("Player {}", player_id),
+            jersey_number: None,
+            position: Some ("P".to_string()),
+            status: status.to_string().into(),
+            status_description: String::new(),
+        }
+    }
+
+    fn snapshot (day: u8, roster_type: RosterType, entries: Vec<RosterEntry>) -> RosterSnapshot {
+        RosterSnapshot {team_id: 111, date: Date {year: 2019, month: 4, day}, roster_type, entries}
+    }
+
+    fn ids (entries: &[RosterEntry]) -> Vec<u32> {
+        entries.iter().map(|entry| entry.player_id).collect()
+    }
+
+    #[test]
+    fn diff_finds_additions_removals_and_status_changes () {
+        let before = snapshot(1, RosterType::FortyMan, vec![entry(1, "A"), entry(2, "A"), entry(3, "MIN"), entry(4, "A")]);
+        let after = snapshot(2, RosterType::FortyMan, vec![entry(1, "A"), entry(2, "D10"), entry(3, "A"), entry(5, "A")]);
+
+        let moves = before.diff(&after);
+        assert_eq!((moves.team_id, moves.roster_type), (111, RosterType::FortyMan));
+        assert_eq!((moves.from.day, moves.to.day), (1, 2));
+        assert_eq!(ids(&moves.added), vec![5]);
+        assert_eq!(ids(&moves.removed), vec![4]);
+        assert_eq!(moves.status_changes, vec![
+            StatusChange {player_id: 2, before: RosterStatus::Active, after: RosterStatus::InjuredList("D10".to_string())},
+            StatusChange {player_id: 3, before: RosterStatus::Optioned, after: RosterStatus::Active},
+        ]);
+
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn moves_between_consecutive_snapshots () {
+        let history = RosterHistory::from(vec![
+            snapshot(3, RosterType::Active, vec![entry(1, "A"), entry(6, "A")]),
+            snapshot(1, RosterType::Active, vec![entry(1, "A"), entry(2, "A")]),
+            snapshot(2, RosterType::Active, vec![entry(1, "A"), entry(2, "A")]),
+            snapshot(1, RosterType::FortyMan, vec![entry(9, "A")]),
+            RosterSnapshot {team_id: 147, ..snapshot(2, RosterType::Active, vec![])},
+        ]);
+
+        // The 1st to the 2nd had no movement, so only the 2nd to the 3rd is left. Other teams and roster types aren't mixed in.
+        let moves = history.moves(111, RosterType::Active);
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].from.day, moves[0].to.day), (2, 3));
+        assert_eq!(ids(&moves[0].added), vec![6]);
+        assert_eq!(ids(&moves[0].removed), vec![2]);
+        assert!(history.moves(111, RosterType::FortyMan).is_empty());
+    }
+
+    #[test]
+    fn roster_on_a_day () {
+        let history = RosterHistory::from(vec![
+            snapshot(1, RosterType::Active, vec![entry(1, "A")]),
+            snapshot(5, RosterType::Active, vec![entry(2, "A")]),
+        ]);
+        let on = |day: u8| history.on(111, RosterType::Active, Date {year: 2019, month: 4, day}).map(|snapshot| snapshot.date.day);
+        assert_eq!(on(1), Some (1));
+        assert_eq!(on(4), Some (1));
+        assert_eq!(on(30), Some (5));
+        assert!(history.on(111, RosterType::Active, Date {year: 2019, month: 3, day: 31}).is_none());
+        assert!(history.on(111, RosterType::FortyMan, Date {year: 2019, month: 4, day: 5}).is_none());
+    }
+
+    #[test]
+    fn status_codes () {
+        assert_eq!(RosterStatus::from("D60".to_string()), RosterStatus::InjuredList("D60".to_string()));
+        assert_eq!(RosterStatus::from("DFA".to_string()), RosterStatus::Other("DFA".to_string()));
+        assert_eq!(RosterStatus::from("MIN".to_string()).code(), "MIN");
+        assert!(!RosterStatus::from("D".to_string()).is_injured_list());
+    }
+
+    #[test]
+    fn parses_a_roster () {
+        let request = RosterRequest {team_id: 111, date: Date {year: 2019, month: 4, day: 1}, roster_type: RosterType::FortyMan};
+        let json = r#"{"roster": [
+            {"person": {"id": 1, "fullName": "One"}, "jerseyNumber": "", "position": {"abbreviation": "SS"},
+             "status": {"code": "D10", "description": "Injured 10-Day"}},
+            {"person": {"id": 2}}
+        ]}"#;
+        let roster = parse_roster(request, json).unwrap();
+        assert_eq!(roster.request(), request);
+        let first = roster.get(1).unwrap();
+        assert_eq!((first.name.as_str(), first.jersey_number.as_deref(), first.position.as_deref()), ("One", None, Some ("SS")));
+        assert!(first.status.is_injured_list());
+        assert_eq!(roster.get(2).unwrap().status, RosterStatus::Other(String::new()));
+        assert!(parse_roster(request, "{}").unwrap().entries.is_empty());
+    }
+}
@@ -1,3 +1,8 @@
+use crate::game::Pitch;
+use crate::play_by_play::HalfInning;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
 /// run_expectancy tables are built using the pitch by pitch data. There are 2 potnetial algorithms to generate the tables:
 /// 
 /// ## Method 1: Use averages
@@ -12,7 +17,7 @@
  
 
 /// RE288: The run expectancy for all 288 possible base, out, ball, strike states
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct RE288 {
     pub outs: u8,
     pub balls: u8,
@@ -311,4 +316,58 @@ pub const RE288_DEFAULT: [RE288; 288] = [
     RE288 {balls: 0, strikes:  1, base_value: 0, outs: 2, run_expectancy: 0.086},
     RE288 {balls: 1, strikes:  2, base_value: 0, outs: 2, run_expectancy: 0.069},
     RE288 {balls: 0, strikes:  2, base_value: 0, outs: 2, run_expectancy: 0.06},
-];
\ No newline at end of file
+];
+/// States with fewer pitches than this keep the default value when building a table, since a handful of pitches is mostly noise.
+pub const RE288_MIN_SAMPLE: usize = 30;
+
+/// Builds an RE288 table from pitch by pitch data using the averages method: for each state, the average number of runs scored from
+/// that pitch to the end of the half inning. Only complete half innings (the last pitch ends with 3 outs) are used, so walk-offs and
+/// rain-shortened innings don't drag the averages down. States with fewer than `RE288_MIN_SAMPLE` pitches fall back to `RE288_DEFAULT`.
+///
+/// Pass in the pitches for a single league and season to get a table for that environment. The table is sorted from the highest run
+/// expectancy to the lowest, like the default one.
+pub fn build_re288 (pitches: &[Pitch]) -> Vec<RE288> {
+
+    let mut half_innings: BTreeMap<(u32, u8, bool), Vec<&Pitch>> = BTreeMap::new();
+    for pitch in pitches {
+        half_innings.entry((pitch.game_pk, pitch.num_inning, pitch.half_inning == HalfInning::Top))
+            .or_default()
+            .push(pitch);
+    };
+
+    // (balls, strikes, base_value, outs) => (total runs to the end of the inning, pitches)
+    let mut states: BTreeMap<(u8, u8, u8, u8), (u32, usize)> = BTreeMap::new();
+
+    for (_, mut half_inning) in half_innings {
+        half_inning.sort_by_key(|pitch| (pitch.num_plate_appearance, pitch.pitch_num_plate_appearance));
+        if half_inning.last().map(|pitch| pitch.outs_end) != Some(3) {continue};
+
+        let mut runs_to_end: u32 = 0;
+        for pitch in half_inning.iter().rev() {
+            runs_to_end += pitch.runs_scored as u32;
+            let state = states.entry((pitch.balls_start, pitch.strikes_start, pitch.base_value_start, pitch.outs_start)).or_insert((0, 0));
+            state.0 += runs_to_end;
+            state.1 += 1;
+        };
+    };
+
+    let mut table: Vec<RE288> = RE288_DEFAULT.iter()
+        .map(|re| {
+            let run_expectancy = match states.get(&(re.balls, re.strikes, re.base_value, re.outs)) {
+                Some ((runs, count)) if *count >= RE288_MIN_SAMPLE => *runs as f32 / *count as f32,
+                _ => re.run_expectancy,
+            };
+            RE288 {
+                outs: re.outs,
+                balls: re.balls,
+                strikes: re.strikes,
+                base_value: re.base_value,
+                run_expectancy,
+            }
+        })
+        .collect()
+        ;
+
+    table.sort_by(|a, b| b.run_expectancy.partial_cmp(&a.run_expectancy).unwrap_or(std::cmp::Ordering::Equal));
+    table
+}
@@ -21,15 +21,19 @@ use std::ops::{Range, RangeInclusive};
 use crate::utils::*;
 use crate::sports;
 use crate::cache::{cache_schedule, load_schedule};
-use isahc::prelude::*;
+use crate::config::BossConfig;
+use crate::error::{BossError, Dataset, Failure, split_failures};
+use crate::transport::{Transport, LiveTransport};
 use rayon::prelude::*;
 use std::collections::{BTreeSet, BTreeMap};
+use std::convert::{TryFrom, TryInto};
 
 
-pub fn test_schedule() {
+pub fn test_schedule() -> Result<(), BossError> {
 
     let start_time = std::time::Instant::now();
-    let schedule_cache = load_schedule();  
+    let config = BossConfig::default();
+    let schedule_cache = load_schedule(&config)?;  
     
     // Figure out which seasons need to be re-pulled. Any season/sport_id combination
     // that has any items that aren't "Final" should be re-pulled. We first map all the
@@ -57,12 +61,16 @@ pub fn test_schedule() {
 
     let sport_ids = sports::get_all_sport_ids();
 
-    let sched = Schedule::get_data(years, sport_ids, &season_sports);
+    let (sched, failures) = Schedule::get_data(&LiveTransport::new()?, years, sport_ids, &season_sports);
     let games: Vec<GameMetaData> = sched.games.into_iter()
                                         .map(|game| game.into())
                                         .collect()
                                         ;
 
+    for failure in failures {
+        println!("{}", failure);
+    };
+
     let mut serialze_schedule: Vec<GameMetaData> = schedule_cache.into_iter()
                     .filter(|sched| season_sports.get(&(sched.game_date.year, sched.sport_id)) == Some(&SeasonStatus::Complete))
                     .collect::<Vec<GameMetaData>>()
@@ -71,10 +79,12 @@ pub fn test_schedule() {
     serialze_schedule.extend(games);
 
     dbg!(&serialze_schedule.len());
-    cache_schedule(&serialze_schedule);
+    cache_schedule(&config, &serialze_schedule)?;
     
     dbg!(start_time.elapsed().as_secs());
 
+    Ok(())
+
 }
 
 
@@ -121,7 +131,7 @@ impl From<GameWithSportId> for GameMetaData {
 
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(from = "GameDe")]
+#[serde(try_from = "GameDe")]
 pub struct Game {
     pub game_type: GameType,
     pub game_type_desc: GameTypeDescription,
@@ -137,8 +147,10 @@ pub struct Game {
 }
 
 
-impl From <GameDe> for Game {
-    fn from (game: GameDe) -> Game {
+impl TryFrom <GameDe> for Game {
+    type Error = BossError;
+
+    fn try_from (game: GameDe) -> Result<Game, BossError> {
 
         let game_url_play_by_play = format!("{}game/{}/playByPlay",  crate::BASE_URL, game.game_pk);
         let game_url_boxscore =     format!("{}game/{}/boxscore",    crate::BASE_URL, game.game_pk);
@@ -146,12 +158,12 @@ impl From <GameDe> for Game {
         //to 2008. 
         let game_url_feed_live =    format!("{}game/{}/feed/live",   crate::BASE_URL_V11, game.game_pk);
 
-        let game_date: GameDate = game.game_date.into();
+        let game_date: GameDate = game.game_date.try_into()?;
 
         let coaches_home_url =      format!("{}teams/{}/coaches/?date={}/{}/{}",crate::BASE_URL, game.teams.home.team.id, game_date.month, game_date.day, game_date.year);
         let coaches_away_url =      format!("{}teams/{}/coaches/?date={}/{}/{}",crate::BASE_URL, game.teams.away.team.id, game_date.month, game_date.day, game_date.year);
 
-        Game {
+        Ok(Game {
             game_type: game.game_type,
             game_type_desc: game.game_type.into(),
             game_pk: game.game_pk,
@@ -163,7 +175,7 @@ impl From <GameDe> for Game {
             coaches_home_url,
             coaches_away_url,
             game_status: game.status.abstract_game_state,
-        }
+        })
     }
 } 
 
@@ -200,47 +212,53 @@ pub struct Schedule {
 
 impl Schedule {   
 
-    fn download_years (years: Vec<u16>, sport_id: u32, cache: &SeasonSportCache) -> Schedule {
+    fn download_years (transport: &dyn Transport, years: Vec<u16>, sport_id: u32, cache: &SeasonSportCache) -> (Schedule, Vec<Failure>) {
         let base_url = format!("{}schedule?sportId={}&startDate=01/01/", crate::BASE_URL, sport_id);
 
         // Build the list of URLs to query, filtering out any years where we have complete seasons.
-        let schedule_urls: Vec<(String, u32)> = years
+        let schedule_urls: Vec<(u16, String)> = years
             .into_iter()
             .filter(|year| cache.get(&(*year, sport_id)) != Some(&SeasonStatus::Complete))
-            .map(|year| (format!("{}{}&endDate=12/31/{}", base_url, year, year), sport_id))
+            .map(|year| (year, format!("{}{}&endDate=12/31/{}", base_url, year, year)))
             .collect()
             ;
 
-        let games: Games = schedule_urls
+        let results: Vec<Result<Games, Failure>> = schedule_urls
         .into_par_iter()
-            .map(|url| {
-                //TODO: Add proper error handling
-                // println!("Downloading: {}", &url.0);
-                let json: String = isahc::get(url.clone().0).unwrap().text().unwrap();
-                let sched: ScheduleDe = serde_json::from_str(&json).unwrap();
-                let sched_with_context = ScheduleWithContext {
-                    sched, 
-                    sport_id: url.1,
+            .map(|(year, url)| {
+                // println!("Downloading: {}", &url);
+                let download = || -> Result<Games, BossError> {
+                    let json: String = transport.get(&url)?;
+                    let sched: ScheduleDe = serde_json::from_str(&json)?;
+                    let sched_with_context = ScheduleWithContext {
+                        sched, 
+                        sport_id,
+                    };
+                    Ok(sched_with_context.into())
                 };
-                let games: Games = sched_with_context.into();
-                games
+                download().map_err(|error| Failure {dataset: Dataset::Schedule, key: format!("{}/{}", year, sport_id), error})
             })
-            .flatten()
             .collect()
             ;
-        Schedule {games}
+        
+        let (games, failures) = split_failures(results);
+        (Schedule {games: games.into_iter().flatten().collect()}, failures)
     }
 
-
-    pub fn get_data (years: Vec<u16>, sport_ids: Vec<u32>, cache: &SeasonSportCache) -> Schedule 
+    /// Downloads the schedule for every year/sport_id combination that isn't already complete in the cache. Seasons that fail
+    /// to download are returned as failures and will be re-tried the next time the schedule is pulled.
+    pub fn get_data (transport: &dyn Transport, years: Vec<u16>, sport_ids: Vec<u32>, cache: &SeasonSportCache) -> (Schedule, Vec<Failure>)
     {
-        let games: Games = sport_ids
-            .into_iter()
-            .map(|sport_id| Self::download_years(years.clone(), sport_id, &cache).games)
-            .flatten()
-            .collect()
-            ;
-        Schedule {games}
+        let mut games: Games = Vec::new();
+        let mut failures: Vec<Failure> = Vec::new();
+
+        for sport_id in sport_ids {
+            let (schedule, sport_failures) = Self::download_years(transport, years.clone(), sport_id, &cache);
+            games.extend(schedule.games);
+            failures.extend(sport_failures);
+        };
+
+        (Schedule {games}, failures)
     }
 
 }
@@ -301,19 +319,26 @@ impl From <ScheduleWithContext> for Games {
 
 type GameDate = Date;
 
-impl From <String> for GameDate {
-    fn from (date_string: String) -> GameDate {
+/// Parses dates in the "2019-06-19" or "2019-06-19T23:05:00Z" format used throughout the API.
+impl TryFrom <String> for GameDate {
+    type Error = BossError;
+
+    fn try_from (date_string: String) -> Result<GameDate, BossError> {
         
-        let date: Vec<&str> = date_string.split("T").nth(0).unwrap().split("-").collect();
-        let year = date[0].parse::<u16>().unwrap();
-        let month = date[1].parse::<u8>().unwrap();
-        let day = date[2].parse::<u8>().unwrap();
+        let unexpected = || BossError::UnexpectedValue {field: "date", value: date_string.clone()};
+
+        let date: Vec<&str> = date_string.split("T").nth(0).unwrap_or("").split("-").collect();
+        if date.len() != 3 {return Err(unexpected())};
 
-        GameDate {
+        let year = date[0].parse::<u16>().map_err(|_| unexpected())?;
+        let month = date[1].parse::<u8>().map_err(|_| unexpected())?;
+        let day = date[2].parse::<u8>().map_err(|_| unexpected())?;
+
+        Ok(GameDate {
             year,
             month,
             day,
-        }
+        })
     }
 }
 
@@ -339,8 +364,9 @@ struct GameStatus {
     abstract_game_state: AbstractGameState,
 }
 
-#[derive(Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Default)]
 pub enum AbstractGameState {
+    #[default]
     Final,
     #[serde(other)]
     NotFinal,
@@ -380,10 +406,11 @@ struct Venue {
     id: u32,
 }
 
-#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default)]
 // #[serde(field_identifier)]
 pub enum GameType {
     /// Regular Season
+    #[default]
     R,
     /// First Round
     F,
@@ -409,8 +436,9 @@ pub enum GameType {
     P,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default)]
 pub enum GameTypeDescription {
+    #[default]
     RegularSeason,
     FirstRound,
     DivisionSeries,
@@ -0,0 +1,326 @@
+//! Request scheduling for large pulls. The Stats API will start timing out and throttling us if we flood it with requests, so every
+//! request in a pull goes through one `RequestScheduler`, which wraps the real `Transport` and enforces:
+//!
+//! * **Max in flight:** no more than `max_in_flight` requests are waiting on the network at once, no matter how many threads ask.
+//! * **Rate limit:** requests to the same host are spaced at least `min_interval` apart.
+//! * **Backoff:** timeouts, dropped connections and 5xx/429 responses are retried up to `max_retries` times, doubling the wait each time.
+//!
+//! Anything that still fails after that is recorded, and `failed_requests` gives a summary of every url that failed permanently.
+//!
+//! Fetches are run on the rayon thread pool, so the number of requests actually in flight is also capped by the number of rayon threads.
+
+use crate::error::BossError;
+use crate::transport::Transport;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::{Condvar, Mutex};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Limits applied by the `RequestScheduler`. The defaults are conservative enough for a full pull of every level of play.
+#[derive(Debug, Copy, Clone)]
+pub struct RequestLimits {
+    pub max_in_flight: usize,
+    /// Minimum time between the start of two requests to the same host.
+    pub min_interval: Duration,
+    pub max_retries: u32,
+    /// Wait before the first retry. Each retry after that waits twice as long as the previous one, up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RequestLimits {
+    /// No limits at all, useful with a `FixtureTransport` where there's no server to protect.
+    pub fn unlimited () -> RequestLimits {
+        RequestLimits {
+            max_in_flight: usize::MAX,
+            min_interval: Duration::from_millis(0),
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(0),
+            max_backoff: Duration::from_millis(0),
+        }
+    }
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        RequestLimits {
+            max_in_flight: 8,
+            min_interval: Duration::from_millis(20),
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A url that was still failing after every retry.
+#[derive(Debug, Clone)]
+pub struct FailedRequest {
+    pub url: String,
+    pub attempts: u32,
+    pub error: String,
+}
+
+impl Display for FailedRequest {
+    fn fmt (&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{} (after {} attempts): {}", self.url, self.attempts, self.error)
+    }
+}
+
+/// Wraps a transport and schedules every request made through it. See the module docs for the rules it enforces.
+pub struct RequestScheduler <T: Transport> {
+    inner: T,
+    limits: RequestLimits,
+    in_flight: Mutex<usize>,
+    slot_freed: Condvar,
+    next_request: Mutex<HashMap<String, Instant>>,
+    failed: Mutex<Vec<FailedRequest>>,
+}
+
+impl <T: Transport> RequestScheduler <T> {
+    pub fn new (inner: T, limits: RequestLimits) -> RequestScheduler<T> {
+        RequestScheduler {
+            inner,
+            limits,
+            in_flight: Mutex::new(0),
+            slot_freed: Condvar::new(),
+            next_request: Mutex::new(HashMap::new()),
+            failed: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Every url that failed permanently so far.
+    pub fn failed_requests (&self) -> Vec<FailedRequest> {
+        self.failed.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    // Blocks until fewer than max_in_flight requests are running, then takes a slot.
+    fn acquire (&self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        while *in_flight >= self.limits.max_in_flight {
+            in_flight = self.slot_freed.wait(in_flight).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *in_flight += 1;
+    }
+
+    fn release (&self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *in_flight -= 1;
+        self.slot_freed.notify_one();
+    }
+
+    // Books the next free start time for the host and waits until then. Booking before sleeping keeps requests to the
+    // same host in order, without holding the lock while we wait.
+    fn wait_for_host (&self, url: &str) {
+        if self.limits.min_interval == Duration::from_millis(0) {return};
+
+        let now = Instant::now();
+        let start = {
+            let mut next_request = self.next_request.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let next = next_request.entry(host(url).to_string()).or_insert(now);
+            let start = if *next > now {*next} else {now};
+            *next = start + self.limits.min_interval;
+            start
+        };
+
+        if start > now {sleep(start - now)};
+    }
+
+    fn backoff (&self, retry: u32) -> Duration {
+        let backoff = self.limits.initial_backoff.checked_mul(2u32.saturating_pow(retry)).unwrap_or(self.limits.max_backoff);
+        if backoff > self.limits.max_backoff {self.limits.max_backoff} else {backoff}
+    }
+}
+
+impl <T: Transport> Transport for RequestScheduler <T> {
+    fn get (&self, url: &str) -> Result<String, BossError> {
+        let mut attempts = 0;
+
+        loop {
+            self.wait_for_host(url);
+            self.acquire();
+            let result = self.inner.get(url);
+            self.release();
+            attempts += 1;
+
+            match result {
+                Err (ref error) if is_transient(error) && attempts <= self.limits.max_retries => {
+                    sleep(self.backoff(attempts - 1));
+                },
+                Err (error) => {
+                    self.failed.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(FailedRequest {
+                        url: url.to_string(),
+                        attempts,
+                        error: error.to_string(),
+                    });
+                    return Err(error);
+                },
+                Ok (text) => return Ok(text),
+            }
+        }
+    }
+}
+
+/// Errors that are worth retrying: timeouts, connection problems and the server telling us it's overloaded.
+pub fn is_transient (error: &BossError) -> bool {
+    match error {
+        BossError::Network (err) => matches!(err,
+            isahc::Error::Timeout
+            | isahc::Error::ConnectFailed
+            | isahc::Error::NoResponse
+            | isahc::Error::Io(_)
+            | isahc::Error::ResponseBodyError(_)
+        ),
+        BossError::HttpStatus {status, ..} => *status >= 500 || *status == 429,
+        _ => false,
+    }
+}
+
+fn host (url: &str) -> &str {
+    let without_scheme = url.splitn(2, "://").last().unwrap_or(url);
+    without_scheme.split('/').next().unwrap_or(without_scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every url after `delay`, failing with a 503 for the first `failures` calls, and keeps track of how many calls
+    /// were running at once and when each one started.
+    struct FakeTransport {
+        delay: Duration,
+        failures: usize,
+        calls: AtomicUsize,
+        running: AtomicUsize,
+        most_running: AtomicUsize,
+        starts: Mutex<Vec<(String, Instant)>>,
+    }
+
+    impl FakeTransport {
+        fn new (delay: Duration, failures: usize) -> FakeTransport {
+            FakeTransport {
+                delay,
+                failures,
+                calls: AtomicUsize::new(0),
+                running: AtomicUsize::new(0),
+                most_running: AtomicUsize::new(0),
+                starts: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl Transport for &FakeTransport {
+        fn get (&self, url: &str) -> Result<String, BossError> {
+            self.starts.lock().unwrap().push((url.to_string(), Instant::now()));
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_running.fetch_max(running, Ordering::SeqCst);
+            sleep(self.delay);
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err (BossError::HttpStatus {url: url.to_string(), status: 503})
+            } else {
+                Ok (url.to_string())
+            }
+        }
+    }
+
+    fn limits (max_in_flight: usize, min_interval_ms: u64, max_retries: u32) -> RequestLimits {
+        RequestLimits {
+            max_in_flight,
+            min_interval: Duration::from_millis(min_interval_ms),
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        }
+    }
+
+    #[test]
+    fn caps_requests_in_flight () {
+        let transport = FakeTransport::new(Duration::from_millis(20), 0);
+        let scheduler = RequestScheduler::new(&transport, limits(2, 0, 0));
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let scheduler = &scheduler;
+                scope.spawn(move || scheduler.get(&format!("https://a.example.com/{}", i)).unwrap());
+            };
+        });
+
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 8);
+        assert_eq!(transport.most_running.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn spaces_requests_to_the_same_host () {
+        let transport = FakeTransport::new(Duration::from_millis(0), 0);
+        let scheduler = RequestScheduler::new(&transport, limits(8, 25, 0));
+
+        std::thread::scope(|scope| {
+            for i in 0..3 {
+                let scheduler = &scheduler;
+                scope.spawn(move || scheduler.get(&format!("https://a.example.com/{}", i)).unwrap());
+                scope.spawn(move || scheduler.get(&format!("https://b.example.com/{}", i)).unwrap());
+            };
+        });
+
+        let starts = transport.starts.lock().unwrap();
+        for prefix in &["https://a.", "https://b."] {
+            let mut times: Vec<Instant> = starts.iter().filter(|(url, _)| url.starts_with(prefix)).map(|(_, time)| *time).collect();
+            times.sort();
+            assert_eq!(times.len(), 3);
+            for pair in times.windows(2) {
+                // The start times are booked 25ms apart, leave some slack for a thread that was slow to record its start.
+                assert!(pair[1] - pair[0] >= Duration::from_millis(15), "{:?}", pair[1] - pair[0]);
+            };
+        };
+    }
+
+    #[test]
+    fn retries_transient_errors () {
+        let transport = FakeTransport::new(Duration::from_millis(0), 2);
+        let scheduler = RequestScheduler::new(&transport, limits(1, 0, 3));
+
+        assert_eq!(scheduler.get("https://a.example.com/x").unwrap(), "https://a.example.com/x");
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 3);
+        assert!(scheduler.failed_requests().is_empty());
+    }
+
+    #[test]
+    fn records_requests_that_run_out_of_retries () {
+        let transport = FakeTransport::new(Duration::from_millis(0), usize::MAX);
+        let scheduler = RequestScheduler::new(&transport, limits(1, 0, 2));
+
+        assert!(matches!(scheduler.get("https://a.example.com/x"), Err (BossError::HttpStatus {status: 503, ..})));
+        let failed = scheduler.failed_requests();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].url, "https://a.example.com/x");
+        assert_eq!(failed[0].attempts, 3);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max () {
+        let transport = FakeTransport::new(Duration::from_millis(0), 0);
+        let scheduler = RequestScheduler::new(&transport, limits(1, 0, 5));
+
+        let waits: Vec<Duration> = (0..5).map(|retry| scheduler.backoff(retry)).collect();
+        assert_eq!(waits, vec![1, 2, 4, 4, 4].into_iter().map(Duration::from_millis).collect::<Vec<_>>());
+        assert_eq!(scheduler.backoff(40), Duration::from_millis(4));
+    }
+
+    #[test]
+    fn only_some_errors_are_transient () {
+        assert!(is_transient(&BossError::HttpStatus {url: String::new(), status: 503}));
+        assert!(is_transient(&BossError::HttpStatus {url: String::new(), status: 429}));
+        assert!(!is_transient(&BossError::HttpStatus {url: String::new(), status: 404}));
+        assert!(!is_transient(&BossError::NotArchived {url: String::new()}));
+    }
+
+    #[test]
+    fn host_of_a_url () {
+        assert_eq!(host("https://statsapi.mlb.com/api/v1/schedule"), "statsapi.mlb.com");
+        assert_eq!(host("statsapi.mlb.com/api"), "statsapi.mlb.com");
+    }
+}
@@ -6,11 +6,13 @@
 //! 
 
 use serde::{Serialize, Deserialize};
+use crate::error::BossError;
 
-pub fn test_sports() {
+pub fn test_sports() -> Result<(), BossError> {
     
-    dbg! (get_sport(1));
+    dbg! (get_sport(1)?);
     dbg! (get_all_sport_ids());
+    Ok(())
 }
 
 
@@ -22,13 +24,14 @@ pub fn get_all_sport_ids () -> Vec<u32> {
         .collect()
 }
 
-pub (crate) fn get_sport(id: u32) -> Sport {
-    *SPORTS
+/// Looks up a hardcoded sport. The ids come from the schedule we pulled, so an unknown id means the API added a sport we haven't mapped yet.
+pub (crate) fn get_sport(id: u32) -> Result<Sport, BossError> {
+    SPORTS
         .iter()
         .filter(|sport| sport.id == id)
         .nth(0)
-        // We control the ids fed to this function and make sure we feed it a valid id
-        .unwrap()
+        .copied()
+        .ok_or(BossError::UnexpectedValue {field: "sport_id", value: id.to_string()})
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -45,8 +48,9 @@ pub struct Sport {
     pub level_of_play_rank: u8,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Default)]
 pub enum MLB {
+    #[default]
     MLB,
     Minors,
     Unaffiliated,
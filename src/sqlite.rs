@@ -0,0 +1,740 @@
+//! Normalized SQLite output, behind the `sqlite` feature. `Pitch` repeats everything about the game, the players and the venue on
+//! every row, which is what makes it easy to load into a spreadsheet. BI tools would rather join, so here each piece of metadata
+//! gets its own table and the pitches are cut down to what actually changes from pitch to pitch:
+//!
+//! | table               | one row per                  | key                                                        |
+//! |---------------------|------------------------------|------------------------------------------------------------|
+//! | `schedule`          | game                         | game_pk                                                    |
+//! | `boxscore`          | game                         | game_pk                                                    |
+//! | `officials`         | umpire in a game             | game_pk, official_type, official_id                        |
+//! | `lineups`           | player in a game's boxscore  | game_pk, player_id                                         |
+//! | `coaches`           | game                         | game_pk                                                    |
+//! | `coaching_staff`    | staff member in a game       | game_pk, team_id, role, person_id                          |
+//! | `players`           | player, coach or umpire      | id                                                         |
+//! | `venues`            | venue and season             | id, year                                                   |
+//! | `teams`             | team and season              | id, year                                                   |
+//! | `plate_appearances` | plate appearance             | game_pk, num_plate_appearance                              |
+//! | `pitches`           | pitch                        | game_pk, num_plate_appearance, pitch_num_plate_appearance  |
+//!
+//! Foreign keys are declared so the tools can find the joins, but aren't enforced: a game can be missing its boxscore, or a player
+//! their bio, and the rest of the game is still worth having. Dates are stored as "yyyy-mm-dd" text and enums as their labels.
+
+use crate::columns::{self, Cell};
+use crate::boxscore::{Pos, WeatherCondition, WindDirection};
+use crate::error::BossError;
+use crate::game::Pitch;
+use crate::metadata::MetaData;
+use crate::play_by_play::{Event, HalfInning, Hardness, PitchTypeCode, SideCode, Trajectory};
+use crate::players::SideCode as BatSideCode;
+use crate::schedule::{AbstractGameState, GameType, GameTypeDescription};
+use crate::utils::Date;
+use rusqlite::types::Value;
+use rusqlite::{Connection, Transaction};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE schedule (
+    game_pk INTEGER PRIMARY KEY,
+    game_type TEXT,
+    game_type_desc TEXT,
+    game_date TEXT,
+    season INTEGER,
+    venue_id INTEGER,
+    sport_id INTEGER,
+    game_status TEXT,
+    game_url_play_by_play TEXT,
+    game_url_boxscore TEXT,
+    game_url_feed_live TEXT,
+    FOREIGN KEY (venue_id, season) REFERENCES venues (id, year)
+);
+CREATE INDEX schedule_game_date ON schedule (game_date);
+CREATE INDEX schedule_sport_season ON schedule (sport_id, season);
+
+CREATE TABLE boxscore (
+    game_pk INTEGER PRIMARY KEY REFERENCES schedule (game_pk),
+    attendance INTEGER,
+    first_pitch REAL,
+    game_weather_temp_f INTEGER,
+    game_weather_temp_c INTEGER,
+    game_weather_condition TEXT,
+    game_wind_speed_mph INTEGER,
+    game_wind_direction TEXT,
+    home_team_id INTEGER,
+    away_team_id INTEGER,
+    home_league_id INTEGER,
+    home_league_name TEXT,
+    away_league_id INTEGER,
+    away_league_name TEXT,
+    home_division_id INTEGER,
+    away_division_id INTEGER,
+    home_division_name TEXT,
+    away_division_name TEXT,
+    home_sport_id INTEGER,
+    away_sport_id INTEGER,
+    home_parent_team_id INTEGER,
+    away_parent_team_id INTEGER,
+    hp_umpire_id INTEGER REFERENCES players (id)
+);
+CREATE INDEX boxscore_home_team ON boxscore (home_team_id);
+CREATE INDEX boxscore_away_team ON boxscore (away_team_id);
+
+CREATE TABLE officials (
+    game_pk INTEGER REFERENCES schedule (game_pk),
+    official_id INTEGER REFERENCES players (id),
+    official_type TEXT,
+    PRIMARY KEY (game_pk, official_type, official_id)
+);
+CREATE INDEX officials_official ON officials (official_id);
+
+CREATE TABLE lineups (
+    game_pk INTEGER REFERENCES schedule (game_pk),
+    home INTEGER,
+    player_id INTEGER REFERENCES players (id),
+    position TEXT,
+    batting_order INTEGER,
+    PRIMARY KEY (game_pk, player_id)
+);
+CREATE INDEX lineups_player ON lineups (player_id);
+
+CREATE TABLE coaches (
+    game_pk INTEGER PRIMARY KEY REFERENCES schedule (game_pk),
+    home_manager INTEGER REFERENCES players (id),
+    home_batting_coach INTEGER REFERENCES players (id),
+    home_pitching_coach INTEGER REFERENCES players (id),
+    away_manager INTEGER REFERENCES players (id),
+    away_batting_coach INTEGER REFERENCES players (id),
+    away_pitching_coach INTEGER REFERENCES players (id)
+);
+
+CREATE TABLE coaching_staff (
+    game_pk INTEGER REFERENCES schedule (game_pk),
+    team_id INTEGER,
+    role TEXT,
+    person_id INTEGER,
+    name TEXT,
+    jersey_number TEXT,
+    PRIMARY KEY (game_pk, team_id, role, person_id)
+);
+CREATE INDEX coaching_staff_team_role ON coaching_staff (team_id, role);
+
+CREATE TABLE players (
+    id INTEGER PRIMARY KEY,
+    name TEXT,
+    birth_city TEXT,
+    birth_state_province TEXT,
+    birth_country TEXT,
+    height_str TEXT,
+    height_in INTEGER,
+    weight INTEGER,
+    highschool_city TEXT,
+    highschool_prov_state TEXT,
+    college_name TEXT,
+    bat_side_code TEXT,
+    throws_code TEXT,
+    birth_date TEXT,
+    draft_school_name TEXT,
+    draft_year INTEGER,
+    draft_pick_round TEXT,
+    draft_pick_number INTEGER,
+    fangraphs_id TEXT,
+    retrosheet_id TEXT,
+    mlb_debut_date TEXT
+);
+
+CREATE TABLE venues (
+    id INTEGER,
+    year INTEGER,
+    venue_name TEXT,
+    venue_city TEXT,
+    venue_state TEXT,
+    venue_state_abbr TEXT,
+    venue_time_zone TEXT,
+    venue_time_zone_offset INTEGER,
+    venue_capacity INTEGER,
+    venue_surface TEXT,
+    venue_roof TEXT,
+    venue_left_line INTEGER,
+    venue_left INTEGER,
+    venue_left_center INTEGER,
+    venue_center INTEGER,
+    venue_right_center INTEGER,
+    venue_right INTEGER,
+    venue_right_line INTEGER,
+    venue_retrosheet_id TEXT,
+    venue_latitude REAL,
+    venue_longitude REAL,
+    PRIMARY KEY (id, year)
+);
+
+CREATE TABLE teams (
+    id INTEGER,
+    year INTEGER,
+    sport_id INTEGER,
+    team_city_name TEXT,
+    team_name TEXT,
+    division_name TEXT,
+    league_name TEXT,
+    PRIMARY KEY (id, year)
+);
+
+CREATE TABLE plate_appearances (
+    game_pk INTEGER REFERENCES schedule (game_pk),
+    num_plate_appearance INTEGER,
+    num_inning INTEGER,
+    half_inning TEXT,
+    batter INTEGER REFERENCES players (id),
+    batter_team_id INTEGER,
+    batter_stands TEXT,
+    batter_pos TEXT,
+    batter_batting_order INTEGER,
+    pitcher INTEGER REFERENCES players (id),
+    pitcher_team_id INTEGER,
+    pitcher_throws TEXT,
+    outs_start INTEGER,
+    base_value_start INTEGER,
+    outs_end INTEGER,
+    base_value_end INTEGER,
+    runs_scored INTEGER,
+    pitches INTEGER,
+    result TEXT,
+    strikeout INTEGER,
+    walk INTEGER,
+    PRIMARY KEY (game_pk, num_plate_appearance)
+);
+CREATE INDEX plate_appearances_batter ON plate_appearances (batter);
+CREATE INDEX plate_appearances_pitcher ON plate_appearances (pitcher);
+
+CREATE TABLE pitches (
+    game_pk INTEGER,
+    num_plate_appearance INTEGER,
+    pitch_num_plate_appearance INTEGER,
+    pitch_num_game INTEGER,
+    catcher_id INTEGER REFERENCES players (id),
+    balls_start INTEGER,
+    strikes_start INTEGER,
+    outs_start INTEGER,
+    base_value_start INTEGER,
+    balls_end INTEGER,
+    strikes_end INTEGER,
+    outs_end INTEGER,
+    base_value_end INTEGER,
+    runs_scored INTEGER,
+    re_288_start REAL,
+    re_288_end REAL,
+    re_288_val REAL,
+    in_play INTEGER,
+    swing INTEGER,
+    swing_and_miss INTEGER,
+    foul INTEGER,
+    bunt INTEGER,
+    pitch_type_code TEXT,
+    pitch_speed_start REAL,
+    pitch_speed_end REAL,
+    pitch_spin_rate REAL,
+    pitch_spin_direction REAL,
+    pitch_break_length REAL,
+    pitch_extension REAL,
+    pitch_pfx_x REAL,
+    pitch_pfx_z REAL,
+    pitch_p_x REAL,
+    pitch_p_z REAL,
+    strike_zone_bottom REAL,
+    strike_zone_top REAL,
+    in_play_result TEXT,
+    fielded_by_id INTEGER REFERENCES players (id),
+    fielded_by_pos TEXT,
+    hit_data_trajectory TEXT,
+    hit_data_contact_quality TEXT,
+    hit_data_launch_angle REAL,
+    hit_data_exit_velocity REAL,
+    hit_data_total_distance REAL,
+    hit_data_spray_angle REAL,
+    hit_data_coord_x REAL,
+    hit_data_coord_y REAL,
+    statcast_spin_axis REAL,
+    statcast_hit_distance REAL,
+    statcast_launch_speed_angle INTEGER,
+    statcast_xba REAL,
+    statcast_xwoba REAL,
+    statcast_woba_value REAL,
+    PRIMARY KEY (game_pk, num_plate_appearance, pitch_num_plate_appearance),
+    FOREIGN KEY (game_pk, num_plate_appearance) REFERENCES plate_appearances (game_pk, num_plate_appearance)
+);
+CREATE INDEX pitches_pitch_type ON pitches (pitch_type_code);
+";
+
+/// Writes the metadata and pitches from a pull to a new SQLite database at `path`, replacing the file if it's already there.
+pub fn write_sqlite <P: AsRef<Path>> (meta_data: &MetaData, pitches: &[Pitch], path: P) -> Result<(), BossError> {
+    let path = path.as_ref();
+    match std::fs::remove_file(path) {
+        Err (err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {},
+    };
+
+    let mut conn = Connection::open(path)?;
+    // The bundled SQLite turns enforcement on by default.
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+
+    let mut schedule: Vec<_> = meta_data.schedule.values().collect();
+    schedule.sort_by_key(|game| game.game_pk);
+    insert(&tx, "schedule", schedule.into_iter().map(|game| columns::row(&ScheduleRow {
+        game_pk: game.game_pk,
+        game_type: game.game_type,
+        game_type_desc: game.game_type_desc,
+        game_date: iso_date(&game.game_date),
+        season: game.game_date.year,
+        venue_id: game.game_venue_id,
+        sport_id: game.sport_id,
+        game_status: game.game_status,
+        game_url_play_by_play: &game.game_url_play_by_play,
+        game_url_boxscore: &game.game_url_boxscore,
+        game_url_feed_live: &game.game_url_feed_live,
+    })))?;
+
+    let mut boxscores: Vec<_> = meta_data.boxscore.iter().collect();
+    boxscores.sort_by_key(|(game_pk, _)| **game_pk);
+    insert(&tx, "boxscore", boxscores.iter().map(|(game_pk, boxscore)| columns::row(&BoxScoreRow {
+        game_pk: **game_pk,
+        attendance: boxscore.attendance,
+        first_pitch: boxscore.first_pitch,
+        game_weather_temp_f: boxscore.game_weather_temp_f,
+        game_weather_temp_c: boxscore.game_weather_temp_c,
+        game_weather_condition: boxscore.game_weather_condition,
+        game_wind_speed_mph: boxscore.game_wind_speed_mph,
+        game_wind_direction: boxscore.game_wind_direction,
+        home_team_id: boxscore.home_team_id,
+        away_team_id: boxscore.away_team_id,
+        home_league_id: boxscore.home_league_id,
+        home_league_name: &boxscore.home_league_name,
+        away_league_id: boxscore.away_league_id,
+        away_league_name: &boxscore.away_league_name,
+        home_division_id: boxscore.home_division_id,
+        away_division_id: boxscore.away_division_id,
+        home_division_name: &boxscore.home_division_name,
+        away_division_name: &boxscore.away_division_name,
+        home_sport_id: boxscore.home_sport_id,
+        away_sport_id: boxscore.away_sport_id,
+        home_parent_team_id: boxscore.home_parent_team_id,
+        away_parent_team_id: boxscore.away_parent_team_id,
+        hp_umpire_id: boxscore.hp_umpire_id,
+    })))?;
+
+    let officials = boxscores.iter().flat_map(|(game_pk, boxscore)| {
+        boxscore.officials.iter().map(move |official| OfficialRow {
+            game_pk: **game_pk,
+            official_id: official.id,
+            official_type: official.official_type.name(),
+        })
+    });
+    insert(&tx, "officials", officials.map(|official| columns::row(&official)))?;
+
+    let lineups = boxscores.iter().flat_map(|(game_pk, boxscore)| {
+        let home = boxscore.home_players.iter().map(move |player| (true, player));
+        let away = boxscore.away_players.iter().map(move |player| (false, player));
+        home.chain(away).map(move |(home, player)| LineupRow {
+            game_pk: **game_pk,
+            home,
+            player_id: player.id,
+            position: player.position,
+            batting_order: player.batting_order,
+        })
+    });
+    insert(&tx, "lineups", lineups.map(|lineup| columns::row(&lineup)))?;
+
+    let mut coaches: Vec<_> = meta_data.coaches.iter().collect();
+    coaches.sort_by_key(|(game_pk, _)| **game_pk);
+    insert(&tx, "coaches", coaches.into_iter().map(|(game_pk, coaches)| columns::row(&CoachRow {
+        game_pk: *game_pk,
+        home_manager: coaches.home_coaches.manager,
+        home_batting_coach: coaches.home_coaches.batting_coach,
+        home_pitching_coach: coaches.home_coaches.pitching_coach,
+        away_manager: coaches.away_coaches.manager,
+        away_batting_coach: coaches.away_coaches.batting_coach,
+        away_pitching_coach: coaches.away_coaches.pitching_coach,
+    })))?;
+
+    let staff = meta_data.coaches.iter().collect::<BTreeMap<_, _>>().into_iter().flat_map(|(game_pk, coaches)| {
+        coaches.staff.iter().map(move |member| StaffRow {
+            game_pk: *game_pk,
+            team_id: member.team_id,
+            role: member.role.job_id(),
+            person_id: member.person_id,
+            name: &member.name,
+            jersey_number: &member.jersey_number,
+        })
+    });
+    insert(&tx, "coaching_staff", staff.map(|member| columns::row(&member)))?;
+
+    let mut players: Vec<_> = meta_data.players.values().collect();
+    players.sort_by_key(|player| player.id);
+    insert(&tx, "players", players.into_iter().map(|player| columns::row(&PlayerRow {
+        id: player.id,
+        name: &player.name,
+        birth_city: &player.birth_city,
+        birth_state_province: &player.birth_state_province,
+        birth_country: &player.birth_country,
+        height_str: &player.height_str,
+        height_in: player.height_in,
+        weight: player.weight,
+        highschool_city: &player.highschool_city,
+        highschool_prov_state: &player.highschool_prov_state,
+        college_name: &player.college_name,
+        bat_side_code: player.bat_side_code,
+        throws_code: player.throws_code,
+        birth_date: player.birth_date.as_ref().map(iso_date),
+        draft_school_name: &player.draft_school_name,
+        draft_year: player.draft_year,
+        draft_pick_round: &player.draft_pick_round,
+        draft_pick_number: player.draft_pick_number,
+        fangraphs_id: &player.fangraphs_id,
+        retrosheet_id: &player.retrosheet_id,
+        mlb_debut_date: player.mlb_debut_date.as_ref().map(iso_date),
+    })))?;
+
+    // Venues and teams are already flat, they just need the year they belong to.
+    let venues: BTreeMap<_, _> = meta_data.venue.iter().collect();
+    insert(&tx, "venues", venues.into_iter().map(|((_, year), venue)| with_year(*year, columns::row(venue))))?;
+
+    let teams: BTreeMap<_, _> = meta_data.teams.iter().collect();
+    insert(&tx, "teams", teams.into_iter().map(|((_, year), team)| with_year(*year, columns::row(team))))?;
+
+    let mut plate_appearances: BTreeMap<(u32, u8), Vec<&Pitch>> = BTreeMap::new();
+    for pitch in pitches {
+        plate_appearances.entry((pitch.game_pk, pitch.num_plate_appearance)).or_default().push(pitch);
+    };
+    for pitches in plate_appearances.values_mut() {
+        pitches.sort_by_key(|pitch| pitch.pitch_num_plate_appearance);
+    };
+
+    insert(&tx, "plate_appearances", plate_appearances.values().map(|pitches| columns::row(&PlateAppearanceRow::from(pitches.as_slice()))))?;
+    insert(&tx, "pitches", plate_appearances.values().flatten().map(|pitch| columns::row(&PitchRow::from(*pitch))))?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+// Inserts each row into the table, by column name. A repeated key keeps the last row.
+fn insert <I: Iterator<Item = Result<Vec<(&'static str, Cell)>, BossError>>> (tx: &Transaction, table: &str, rows: I) -> Result<(), BossError> {
+    let mut rows = rows.peekable();
+
+    let names: Vec<&str> = match rows.peek() {
+        Some (Ok (row)) => row.iter().map(|(name, _)| *name).collect(),
+        Some (Err (_)) => return rows.next().map(|row| row.map(|_| ())).unwrap_or(Ok(())),
+        None => return Ok(()),
+    };
+
+    let sql = format!(
+        "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+        table, names.join(", "), vec!["?"; names.len()].join(", "),
+    );
+    let mut statement = tx.prepare(&sql)?;
+
+    for row in rows {
+        statement.execute(rusqlite::params_from_iter(row?.into_iter().map(|(_, cell)| value(cell))))?;
+    };
+    Ok(())
+}
+
+fn value (cell: Cell) -> Value {
+    match cell {
+        Cell::Null => Value::Null,
+        Cell::Bool (value) => Value::Integer(value as i64),
+        Cell::Int32 (value) => Value::Integer(value as i64),
+        Cell::Int64 (value) => Value::Integer(value),
+        // Going through the shortest string for the f32 keeps 92.3 from being stored as 92.30000305175781.
+        Cell::Float (value) => Value::Real(value.to_string().parse().unwrap_or(value as f64)),
+        Cell::Double (value) => Value::Real(value),
+        Cell::Utf8 (value) => Value::Text(value),
+        Cell::Enum (value) => Value::Text(value.to_string()),
+    }
+}
+
+fn with_year (year: u16, row: Result<Vec<(&'static str, Cell)>, BossError>) -> Result<Vec<(&'static str, Cell)>, BossError> {
+    let mut row = row?;
+    row.insert(1, ("year", Cell::Int32(year as i32)));
+    Ok(row)
+}
+
+fn iso_date (date: &Date) -> String {
+    format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+#[derive(Serialize)]
+struct ScheduleRow <'a> {
+    game_pk: u32,
+    game_type: GameType,
+    game_type_desc: GameTypeDescription,
+    game_date: String,
+    season: u16,
+    venue_id: u32,
+    sport_id: u32,
+    game_status: AbstractGameState,
+    game_url_play_by_play: &'a str,
+    game_url_boxscore: &'a str,
+    game_url_feed_live: &'a str,
+}
+
+#[derive(Serialize)]
+struct BoxScoreRow <'a> {
+    game_pk: u32,
+    attendance: Option<u32>,
+    first_pitch: Option<f32>,
+    game_weather_temp_f: Option<u8>,
+    game_weather_temp_c: Option<i8>,
+    game_weather_condition: Option<WeatherCondition>,
+    game_wind_speed_mph: Option<u8>,
+    game_wind_direction: Option<WindDirection>,
+    home_team_id: u32,
+    away_team_id: u32,
+    home_league_id: Option<u32>,
+    home_league_name: &'a Option<String>,
+    away_league_id: Option<u32>,
+    away_league_name: &'a Option<String>,
+    home_division_id: Option<u32>,
+    away_division_id: Option<u32>,
+    home_division_name: &'a Option<String>,
+    away_division_name: &'a Option<String>,
+    home_sport_id: u32,
+    away_sport_id: u32,
+    home_parent_team_id: u32,
+    away_parent_team_id: u32,
+    hp_umpire_id: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct OfficialRow <'a> {
+    game_pk: u32,
+    official_id: u32,
+    official_type: &'a str,
+}
+
+#[derive(Serialize)]
+struct LineupRow {
+    game_pk: u32,
+    home: bool,
+    player_id: u32,
+    position: Pos,
+    batting_order: Option<u16>,
+}
+
+#[derive(Serialize)]
+struct CoachRow {
+    game_pk: u32,
+    home_manager: Option<u32>,
+    home_batting_coach: Option<u32>,
+    home_pitching_coach: Option<u32>,
+    away_manager: Option<u32>,
+    away_batting_coach: Option<u32>,
+    away_pitching_coach: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct StaffRow <'a> {
+    game_pk: u32,
+    team_id: u32,
+    role: &'a str,
+    person_id: u32,
+    name: &'a str,
+    jersey_number: &'a Option<String>,
+}
+
+#[derive(Serialize)]
+struct PlayerRow <'a> {
+    id: u32,
+    name: &'a str,
+    birth_city: &'a Option<String>,
+    birth_state_province: &'a Option<String>,
+    birth_country: &'a Option<String>,
+    height_str: &'a Option<String>,
+    height_in: u8,
+    weight: Option<u16>,
+    highschool_city: &'a Option<String>,
+    highschool_prov_state: &'a Option<String>,
+    college_name: &'a Option<String>,
+    bat_side_code: Option<BatSideCode>,
+    throws_code: Option<BatSideCode>,
+    birth_date: Option<String>,
+    draft_school_name: &'a Option<String>,
+    draft_year: Option<u16>,
+    draft_pick_round: &'a Option<String>,
+    draft_pick_number: Option<u16>,
+    fangraphs_id: &'a Option<String>,
+    retrosheet_id: &'a Option<String>,
+    mlb_debut_date: Option<String>,
+}
+
+/// The plate appearance a set of pitches belongs to. The pitches must be in order.
+#[derive(Serialize)]
+struct PlateAppearanceRow {
+    game_pk: u32,
+    num_plate_appearance: u8,
+    num_inning: u8,
+    half_inning: HalfInning,
+    batter: u32,
+    batter_team_id: u32,
+    batter_stands: Option<BatSideCode>,
+    batter_pos: Pos,
+    batter_batting_order: Option<u16>,
+    pitcher: u32,
+    pitcher_team_id: u32,
+    pitcher_throws: SideCode,
+    outs_start: u8,
+    base_value_start: u8,
+    outs_end: u8,
+    base_value_end: u8,
+    runs_scored: u32,
+    pitches: u32,
+    result: Option<Event>,
+    strikeout: u8,
+    walk: u8,
+}
+
+impl From<&[&Pitch]> for PlateAppearanceRow {
+    fn from (pitches: &[&Pitch]) -> PlateAppearanceRow {
+        let first = pitches[0];
+        let last = pitches[pitches.len() - 1];
+
+        PlateAppearanceRow {
+            game_pk: first.game_pk,
+            num_plate_appearance: first.num_plate_appearance,
+            num_inning: first.num_inning,
+            half_inning: first.half_inning,
+            batter: last.batter,
+            batter_team_id: last.batter_team_id,
+            batter_stands: last.batter_stands,
+            batter_pos: last.batter_pos,
+            batter_batting_order: last.batter_batting_order,
+            pitcher: last.pitcher,
+            pitcher_team_id: last.pitcher_team_id,
+            pitcher_throws: last.pitcher_throws,
+            outs_start: first.outs_start,
+            base_value_start: first.base_value_start,
+            outs_end: last.outs_end,
+            base_value_end: last.base_value_end,
+            runs_scored: pitches.iter().map(|pitch| pitch.runs_scored as u32).sum(),
+            pitches: pitches.len() as u32,
+            result: last.in_play_result,
+            strikeout: last.strikeout,
+            walk: last.walk,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PitchRow <'a> {
+    game_pk: u32,
+    num_plate_appearance: u8,
+    pitch_num_plate_appearance: u8,
+    pitch_num_game: u16,
+    catcher_id: Option<u32>,
+    balls_start: u8,
+    strikes_start: u8,
+    outs_start: u8,
+    base_value_start: u8,
+    balls_end: u8,
+    strikes_end: u8,
+    outs_end: u8,
+    base_value_end: u8,
+    runs_scored: u8,
+    re_288_start: f32,
+    re_288_end: f32,
+    re_288_val: f32,
+    in_play: u8,
+    swing: u8,
+    swing_and_miss: Option<u8>,
+    foul: u8,
+    bunt: Option<bool>,
+    pitch_type_code: &'a Option<PitchTypeCode>,
+    pitch_speed_start: Option<f32>,
+    pitch_speed_end: Option<f32>,
+    pitch_spin_rate: Option<f32>,
+    pitch_spin_direction: Option<f32>,
+    pitch_break_length: Option<f32>,
+    pitch_extension: Option<f32>,
+    pitch_pfx_x: Option<f32>,
+    pitch_pfx_z: Option<f32>,
+    pitch_p_x: Option<f32>,
+    pitch_p_z: Option<f32>,
+    strike_zone_bottom: f32,
+    strike_zone_top: f32,
+    in_play_result: Option<Event>,
+    fielded_by_id: Option<u32>,
+    fielded_by_pos: Option<Pos>,
+    hit_data_trajectory: Option<Trajectory>,
+    hit_data_contact_quality: Option<Hardness>,
+    hit_data_launch_angle: Option<f32>,
+    hit_data_exit_velocity: Option<f32>,
+    hit_data_total_distance: Option<f32>,
+    hit_data_spray_angle: Option<f32>,
+    hit_data_coord_x: Option<f32>,
+    hit_data_coord_y: Option<f32>,
+    statcast_spin_axis: Option<f32>,
+    statcast_hit_distance: Option<f32>,
+    statcast_launch_speed_angle: Option<u8>,
+    statcast_xba: Option<f32>,
+    statcast_xwoba: Option<f32>,
+    statcast_woba_value: Option<f32>,
+}
+
+impl <'a> From<&'a Pitch> for PitchRow<'a> {
+    fn from (pitch: &'a Pitch) -> PitchRow<'a> {
+        PitchRow {
+            game_pk: pitch.game_pk,
+            num_plate_appearance: pitch.num_plate_appearance,
+            pitch_num_plate_appearance: pitch.pitch_num_plate_appearance,
+            pitch_num_game: pitch.pitch_num_game,
+            catcher_id: pitch.catcher_id,
+            balls_start: pitch.balls_start,
+            strikes_start: pitch.strikes_start,
+            outs_start: pitch.outs_start,
+            base_value_start: pitch.base_value_start,
+            balls_end: pitch.balls_end,
+            strikes_end: pitch.strikes_end,
+            outs_end: pitch.outs_end,
+            base_value_end: pitch.base_value_end,
+            runs_scored: pitch.runs_scored,
+            re_288_start: pitch.re_288_start,
+            re_288_end: pitch.re_288_end,
+            re_288_val: pitch.re_288_val,
+            in_play: pitch.in_play,
+            swing: pitch.swing,
+            swing_and_miss: pitch.swing_and_miss,
+            foul: pitch.foul,
+            bunt: pitch.bunt,
+            pitch_type_code: &pitch.pitch_type_code,
+            pitch_speed_start: pitch.pitch_speed_start,
+            pitch_speed_end: pitch.pitch_speed_end,
+            pitch_spin_rate: pitch.pitch_spin_rate,
+            pitch_spin_direction: pitch.pitch_spin_direction,
+            pitch_break_length: pitch.pitch_break_length,
+            pitch_extension: pitch.pitch_extension,
+            pitch_pfx_x: pitch.pitch_pfx_x,
+            pitch_pfx_z: pitch.pitch_pfx_z,
+            pitch_p_x: pitch.pitch_p_x,
+            pitch_p_z: pitch.pitch_p_z,
+            strike_zone_bottom: pitch.strike_zone_bottom,
+            strike_zone_top: pitch.strike_zone_top,
+            in_play_result: pitch.in_play_result,
+            fielded_by_id: pitch.fielded_by_id,
+            fielded_by_pos: pitch.fielded_by_pos,
+            hit_data_trajectory: pitch.hit_data_trajectory,
+            hit_data_contact_quality: pitch.hit_data_contact_quality,
+            hit_data_launch_angle: pitch.hit_data_launch_angle,
+            hit_data_exit_velocity: pitch.hit_data_exit_velocity,
+            hit_data_total_distance: pitch.hit_data_total_distance,
+            hit_data_spray_angle: pitch.hit_data_spray_angle,
+            hit_data_coord_x: pitch.hit_data_coord_x,
+            hit_data_coord_y: pitch.hit_data_coord_y,
+            statcast_spin_axis: pitch.statcast_spin_axis,
+            statcast_hit_distance: pitch.statcast_hit_distance,
+            statcast_launch_speed_angle: pitch.statcast_launch_speed_angle,
+            statcast_xba: pitch.statcast_xba,
+            statcast_xwoba: pitch.statcast_xwoba,
+            statcast_woba_value: pitch.statcast_woba_value,
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Merges Statcast search CSVs, as downloaded from Baseball Savant, into MLB pitches. The Stats API has the spin rate, extension and
+//! launch angle, but not the spin axis, Statcast's hit distance or the expected stats, so we read those from the CSVs:
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::statcast::{read_statcast, merge_statcast};
+//!
+//! let mut data = Boss::new().years(2019 ..= 2019).sports(vec![1]).fetch()?;
+//! let report = merge_statcast(&mut data.pitches, read_statcast("savant/")?);
+//! println!("{}", report);
+//! report.write_csv("statcast_unmatched.csv")?;
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+//!
+//! A row matches a pitch on `game_pk`, `at_bat_number` (our `num_plate_appearance`) and `pitch_number` (our
+//! `pitch_num_plate_appearance`). Every row that doesn't match a pitch ends up in the report, with the reason, so a season that
+//! doesn't line up can be tracked down.
+
+use crate::error::BossError;
+use crate::game::Pitch;
+use csv::{ReaderBuilder, WriterBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The columns we use from one row of a Statcast search CSV. Columns that aren't in an older download are left as `None`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct StatcastRow {
+    #[serde(skip)]
+    pub file: String,
+    /// Line in the file, counting the header as line 1.
+    #[serde(skip)]
+    pub line: u64,
+    pub game_pk: u32,
+    pub at_bat_number: u8,
+    pub pitch_number: u8,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub spin_axis: Option<f32>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub hit_distance_sc: Option<f32>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub launch_speed_angle: Option<u8>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub estimated_ba_using_speedangle: Option<f32>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub estimated_woba_using_speedangle: Option<f32>,
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub woba_value: Option<f32>,
+}
+
+/// Everything read from a folder of Statcast CSVs.
+#[derive(Debug, Clone, Default)]
+pub struct StatcastData {
+    pub rows: Vec<StatcastRow>,
+    /// Rows without a usable game_pk, at_bat_number or pitch_number.
+    pub unreadable: Vec<UnmatchedRow>,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnmatchedReason {
+    /// The row is missing its game_pk, at_bat_number or pitch_number, or they aren't numbers.
+    Unreadable,
+    /// None of the pitches passed in are from this game.
+    GameNotLoaded,
+    /// We have the game, but not a pitch with this plate appearance and pitch number.
+    NoSuchPitch,
+    /// An earlier row, possibly in another file, already matched this pitch.
+    Duplicate,
+}
+
+/// A Statcast row that wasn't merged.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UnmatchedRow {
+    pub file: String,
+    pub line: u64,
+    pub game_pk: Option<u32>,
+    pub at_bat_number: Option<u8>,
+    pub pitch_number: Option<u8>,
+    pub reason: UnmatchedReason,
+}
+
+/// How the Statcast rows lined up with the pitches.
+#[derive(Debug, Clone, Default)]
+pub struct StatcastReport {
+    pub matched: usize,
+    pub unmatched: Vec<UnmatchedRow>,
+    /// Pitches from games that had Statcast rows, but that didn't get a row of their own.
+    pub pitches_without_row: usize,
+}
+
+impl StatcastReport {
+    /// Writes the unmatched rows out as a CSV.
+    pub fn write_csv <P: AsRef<Path>> (&self, path: P) -> Result<(), BossError> {
+        let mut writer = WriterBuilder::new().from_path(path)?;
+        for row in self.unmatched.iter() {
+            writer.serialize(row)?;
+        };
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn count (&self, reason: UnmatchedReason) -> usize {
+        self.unmatched.iter().filter(|row| row.reason == reason).count()
+    }
+}
+
+impl Display for StatcastReport {
+    fn fmt (&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{} Statcast rows matched, {} unmatched (unreadable: {}, game not loaded: {}, no such pitch: {}, duplicate: {}). \
+            {} pitches from those games had no Statcast row.",
+            self.matched, self.unmatched.len(), self.count(UnmatchedReason::Unreadable), self.count(UnmatchedReason::GameNotLoaded),
+            self.count(UnmatchedReason::NoSuchPitch), self.count(UnmatchedReason::Duplicate), self.pitches_without_row,
+        )
+    }
+}
+
+/// Reads every `.csv` file in `dir`, in file name order.
+pub fn read_statcast <P: AsRef<Path>> (dir: P) -> Result<StatcastData, BossError> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir.as_ref())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, std::io::Error>>()?
+        .into_iter()
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.eq_ignore_ascii_case("csv")).unwrap_or(false))
+        .collect();
+    files.sort();
+
+    let mut data = StatcastData::default();
+    for path in files {
+        read_file(&path, &mut data)?;
+    };
+    Ok(data)
+}
+
+fn read_file (path: &Path, data: &mut StatcastData) -> Result<(), BossError> {
+    let file = path.display().to_string();
+    let mut reader = ReaderBuilder::new().flexible(true).from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header.trim_start_matches('\u{feff}') == name);
+    let (game_pk, at_bat_number, pitch_number) = (column("game_pk"), column("at_bat_number"), column("pitch_number"));
+
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map(|position| position.line()).unwrap_or(0);
+        match record.deserialize::<StatcastRow>(Some (&headers)) {
+            Ok (row) => data.rows.push(StatcastRow {file: file.clone(), line, ..row}),
+            Err (_) => {
+                let field = |index: Option<usize>| index.and_then(|index| record.get(index));
+                data.unreadable.push(UnmatchedRow {
+                    file: file.clone(),
+                    line,
+                    game_pk: field(game_pk).and_then(|value| value.parse().ok()),
+                    at_bat_number: field(at_bat_number).and_then(|value| value.parse().ok()),
+                    pitch_number: field(pitch_number).and_then(|value| value.parse().ok()),
+                    reason: UnmatchedReason::Unreadable,
+                });
+            },
+        };
+    };
+    Ok(())
+}
+
+/// Fills in the Statcast fields of the pitches that have a row, and reports on the rows that don't match a pitch.
+pub fn merge_statcast (pitches: &mut [Pitch], data: StatcastData) -> StatcastReport {
+    let index: HashMap<(u32, u8, u8), usize> = pitches.iter()
+        .enumerate()
+        .map(|(i, pitch)| ((pitch.game_pk, pitch.num_plate_appearance, pitch.pitch_num_plate_appearance), i))
+        .collect();
+    let games: HashSet<u32> = pitches.iter().map(|pitch| pitch.game_pk).collect();
+
+    let mut report = StatcastReport {unmatched: data.unreadable, ..StatcastReport::default()};
+    let mut merged: HashSet<usize> = HashSet::new();
+    let mut statcast_games: HashSet<u32> = HashSet::new();
+
+    for row in data.rows {
+        statcast_games.insert(row.game_pk);
+        let reason = match index.get(&(row.game_pk, row.at_bat_number, row.pitch_number)) {
+            Some (i) if merged.contains(i) => UnmatchedReason::Duplicate,
+            Some (i) => {
+                let pitch = &mut pitches[*i];
+                pitch.statcast_spin_axis = row.spin_axis;
+                pitch.statcast_hit_distance = row.hit_distance_sc;
+                pitch.statcast_launch_speed_angle = row.launch_speed_angle;
+                pitch.statcast_xba = row.estimated_ba_using_speedangle;
+                pitch.statcast_xwoba = row.estimated_woba_using_speedangle;
+                pitch.statcast_woba_value = row.woba_value;
+                merged.insert(*i);
+                report.matched += 1;
+                continue;
+            },
+            None if games.contains(&row.game_pk) => UnmatchedReason::NoSuchPitch,
+            None => UnmatchedReason::GameNotLoaded,
+        };
+        report.unmatched.push(UnmatchedRow {
+            file: row.file,
+            line: row.line,
+            game_pk: Some (row.game_pk),
+            at_bat_number: Some (row.at_bat_number),
+            pitch_number: Some (row.pitch_number),
+            reason,
+        });
+    };
+
+    report.pitches_without_row = pitches.iter()
+        .enumerate()
+        .filter(|(i, pitch)| statcast_games.contains(&pitch.game_pk) && !merged.contains(i))
+        .count();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pitch (game_pk: u32, num_plate_appearance: u8, pitch_num_plate_appearance: u8) -> Pitch {
+        Pitch {game_pk, num_plate_appearance, pitch_num_plate_appearance, ..Pitch::default()}
+    }
+
+    fn savant_dir (name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boss_statcast_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file_name, contents) in files {
+            fs::write(dir.join(file_name), contents).unwrap();
+        };
+        dir
+    }
+
+    const HEADER: &str = "pitch_type,game_pk,at_bat_number,pitch_number,spin_axis,hit_distance_sc,launch_speed_angle,\
+        estimated_ba_using_speedangle,estimated_woba_using_speedangle,woba_value\n";
+
+    #[test]
+    fn merges_rows_and_reports_the_rest () {
+        let first = format!("{}{}", HEADER, "\
+FF,566083,1,1,205,,,,,
+SL,566083,1,2,140,12,2,0.08,0.075,0
+FF,566083,4,1,210,,,,,
+FF,999999,1,1,200,,,,,
+FF,566083,,3,200,,,,,
+");
+        // An older download, without the spin axis, that has one of the same pitches again.
+        let second = "\u{feff}game_pk,at_bat_number,pitch_number,woba_value\n566083,1,1,0.9\n";
+        let dir = savant_dir("merge", &[("a.csv", &first), ("b.csv", second), ("notes.txt", "not a csv")]);
+
+        let data = read_statcast(&dir).unwrap();
+        assert_eq!(data.rows.len(), 5);
+        assert_eq!(data.unreadable.len(), 1);
+
+        let mut pitches = vec![pitch(566083, 1, 1), pitch(566083, 1, 2), pitch(566083, 2, 1), pitch(566083, 2, 2), pitch(777777, 1, 1)];
+        let report = merge_statcast(&mut pitches, data);
+
+        assert_eq!(report.matched, 2);
+        assert_eq!(pitches[0].statcast_spin_axis, Some (205.0));
+        assert_eq!(pitches[0].statcast_woba_value, None);
+        assert_eq!(pitches[1].statcast_hit_distance, Some (12.0));
+        assert_eq!(pitches[1].statcast_launch_speed_angle, Some (2));
+        assert_eq!(pitches[1].statcast_xba, Some (0.08));
+        assert_eq!(pitches[1].statcast_xwoba, Some (0.075));
+        assert_eq!(pitches[1].statcast_woba_value, Some (0.0));
+
+        let reasons: Vec<(String, u64, UnmatchedReason)> = report.unmatched.iter()
+            .map(|row| (Path::new(&row.file).file_name().unwrap().to_string_lossy().to_string(), row.line, row.reason))
+            .collect();
+        assert_eq!(reasons, vec![
+            ("a.csv".to_string(), 6, UnmatchedReason::Unreadable),
+            ("a.csv".to_string(), 4, UnmatchedReason::NoSuchPitch),
+            ("a.csv".to_string(), 5, UnmatchedReason::GameNotLoaded),
+            ("b.csv".to_string(), 2, UnmatchedReason::Duplicate),
+        ]);
+        let unreadable = &report.unmatched[0];
+        assert_eq!((unreadable.game_pk, unreadable.at_bat_number, unreadable.pitch_number), (Some (566083), None, Some (3)));
+
+        // The two pitches of the second plate appearance had no row. Game 777777 had no Statcast rows at all, so it isn't counted.
+        assert_eq!(report.pitches_without_row, 2);
+        assert_eq!(report.count(UnmatchedReason::Duplicate), 1);
+    }
+
+    #[test]
+    fn writes_the_unmatched_rows () {
+        let dir = savant_dir("report", &[("a.csv", &format!("{}FF,1,1,1,,,,,,\n", HEADER))]);
+        let report = merge_statcast(&mut [], read_statcast(&dir).unwrap());
+        assert_eq!(report.count(UnmatchedReason::GameNotLoaded), 1);
+        assert_eq!(report.pitches_without_row, 0);
+
+        let out = dir.join("unmatched.out");
+        report.write_csv(&out).unwrap();
+        let written = fs::read_to_string(&out).unwrap();
+        assert!(written.starts_with("file,line,game_pk,at_bat_number,pitch_number,reason\n"), "{}", written);
+        assert!(written.trim_end().ends_with(",2,1,1,1,GameNotLoaded"), "{}", written);
+    }
+}
@@ -1,6 +1,8 @@
 /// Download team metadata. We'll pull the division and other metadata from here, as well as social media channels.
 
 use serde::{Deserialize, Serialize};
+use crate::error::BossError;
+use std::convert::TryFrom;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct TeamData {
@@ -64,17 +66,19 @@ impl Default for Team {
     }
 }
 
-impl From<TeamDeserialize> for Team {
-    fn from (team: TeamDeserialize) -> Team {
+impl TryFrom<TeamDeserialize> for Team {
+    type Error = BossError;
+
+    fn try_from (team: TeamDeserialize) -> Result<Team, BossError> {
         
         let division_name = match team.division {
             Some (div) => div.name,
             None => None,
         };
         
-        Team {
+        Ok(Team {
             id: team.id,
-            sport_id: team.sport.id.unwrap(),
+            sport_id: team.sport.id.ok_or(BossError::MissingField {id: team.id, field: "sport.id"})?,
             team_city_name: team.name,
             team_name: team.team_name,
             division_name,
@@ -82,6 +86,6 @@ impl From<TeamDeserialize> for Team {
             // twitter: team.social.unwrap_or(twitter[0].clone(),
             // facebook: team.social.facebook[0].clone(),
             // instagram: team.social.instagram[0].clone(),
-        }
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+//! Player transactions: injured list moves, trades, options, releases and so on. They come along with the player's bio (the people
+//! endpoint is pulled with `hydrate=transactions`) and are cached separately in `transactions.json`, one row per transaction.
+//!
+//! The API only has a two or three letter type code for each transaction. Injured list moves are all "SC" (status change), so we
+//! look at the description to tell a placement from an activation. Before 2019 the injured list was called the disabled list, and
+//! the descriptions say so.
+//!
+//! From the transactions we tag each pitch with how many days it has been since the batter and pitcher came off the injured list
+//! and since they were last traded.
+//!
+//! Transactions are only picked up when a player's bio is pulled, so players that were already cached have none until they're
+//! pulled again, such as with `.refresh(Dataset::Players, Overwrite::Always)`. Until then their pitches have no days since a
+//! return or a trade.
+
+use crate::utils::Date;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TransactionKind {
+    InjuredListPlacement,
+    /// Moved from one injured list to another, such as from the 10-day to the 60-day list. The player is still out.
+    InjuredListTransfer,
+    InjuredListActivation,
+    Trade,
+    Optioned,
+    Recalled,
+    Released,
+    DesignatedForAssignment,
+    Signed,
+    Other,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub id: Option<u32>,
+    pub player_id: u32,
+    pub date: Date,
+    pub effective_date: Option<Date>,
+    pub resolution_date: Option<Date>,
+    pub kind: TransactionKind,
+    pub type_code: String,
+    pub type_desc: String,
+    pub from_team_id: Option<u32>,
+    pub to_team_id: Option<u32>,
+    pub description: String,
+}
+
+impl Transaction {
+    /// The day the transaction took effect. That's usually the date of the transaction, but injured list placements are often
+    /// backdated.
+    pub fn effective (&self) -> Date {
+        self.effective_date.unwrap_or(self.date)
+    }
+}
+
+/// The transactions for each player, in the order they took effect.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerTransactions {
+    players: HashMap<u32, Vec<Transaction>>,
+}
+
+impl PlayerTransactions {
+    pub fn get (&self, player_id: u32) -> &[Transaction] {
+        self.players.get(&player_id).map(|transactions| transactions.as_slice()).unwrap_or(&[])
+    }
+
+    /// The days from the player's last transaction of this kind to `date`, counting only transactions that took effect on or before
+    /// `date`. None if there isn't one.
+    pub fn days_since (&self, player_id: u32, kind: TransactionKind, date: Date) -> Option<u16> {
+        self.get(player_id).iter()
+            .rev()
+            .filter(|transaction| transaction.kind == kind)
+            .map(|transaction| date.day_number() - transaction.effective().day_number())
+            .find(|days| *days >= 0)
+            .map(|days| days.min(u16::MAX as i32) as u16)
+    }
+}
+
+impl From<Vec<Transaction>> for PlayerTransactions {
+    fn from (transactions: Vec<Transaction>) -> PlayerTransactions {
+        let mut players: HashMap<u32, Vec<Transaction>> = HashMap::new();
+        for transaction in transactions {
+            players.entry(transaction.player_id).or_default().push(transaction);
+        };
+        for transactions in players.values_mut() {
+            transactions.sort_by_key(|transaction| transaction.effective());
+        };
+        PlayerTransactions {players}
+    }
+}
+
+// Every field is optional: transactions come along with the player's bio, so one we can't read mustn't cost us the player.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all="camelCase")]
+pub (crate) struct TransactionDe {
+    id: Option<u32>,
+    date: Option<String>,
+    effective_date: Option<String>,
+    resolution_date: Option<String>,
+    type_code: Option<String>,
+    type_desc: Option<String>,
+    description: Option<String>,
+    from_team: Option<TeamRef>,
+    to_team: Option<TeamRef>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub (crate) struct TeamRef {
+    id: Option<u32>,
+}
+
+/// Turns the player's transactions from the people endpoint into `Transaction`s. Transactions without a date we can read are
+/// dropped, since we can't place them.
+pub (crate) fn parse_transactions (player_id: u32, transactions: Vec<TransactionDe>) -> Vec<Transaction> {
+    transactions.into_iter()
+        .filter_map(|transaction| {
+            let date: Date = transaction.date?.try_into().ok()?;
+            let type_code = transaction.type_code.unwrap_or_default();
+            let description = transaction.description.unwrap_or_default();
+            Some (Transaction {
+                id: transaction.id,
+                player_id,
+                date,
+                effective_date: transaction.effective_date.and_then(|date| date.try_into().ok()),
+                resolution_date: transaction.resolution_date.and_then(|date| date.try_into().ok()),
+                kind: kind(&type_code, &description),
+                type_code,
+                type_desc: transaction.type_desc.unwrap_or_default(),
+                from_team_id: transaction.from_team.and_then(|team| team.id),
+                to_team_id: transaction.to_team.and_then(|team| team.id),
+                description,
+            })
+        })
+        .collect()
+}
+
+fn kind (type_code: &str, description: &str) -> TransactionKind {
+    let description = description.to_lowercase();
+    match type_code {
+        "TR" => TransactionKind::Trade,
+        "OPT" => TransactionKind::Optioned,
+        "CU" => TransactionKind::Recalled,
+        "REL" => TransactionKind::Released,
+        "DES" => TransactionKind::DesignatedForAssignment,
+        "SFA" | "SGN" => TransactionKind::Signed,
+        "SC" if description.contains("injured list") || description.contains("disabled list") => {
+            if description.contains(" transferred ") {TransactionKind::InjuredListTransfer}
+            else if description.contains(" placed ") {TransactionKind::InjuredListPlacement}
+            else if description.contains(" activated ") || description.contains(" reinstated ") {TransactionKind::InjuredListActivation}
+            else {TransactionKind::Other}
+        },
+        _ => TransactionKind::Other,
+    }
+}
@@ -0,0 +1,137 @@
+//! Every request BOSS makes goes through a `Transport`. The default is `LiveTransport`, which pulls from the network with isahc.
+//! `FixtureTransport` answers requests from a folder of recorded responses instead, which lets the whole pipeline run on a machine
+//! with no network access, or against a known set of games when testing.
+//!
+//! Fixtures are plain files named after the url they answer for (see `fixture_name`). The easiest way to build a set is to run a pull
+//! once through a `RecordingTransport`, which saves every response it gets from the live transport into the fixture folder.
+//!
+//! ```no_run
+//! use boss::Boss;
+//! use boss::transport::FixtureTransport;
+//! use boss::scheduler::RequestLimits;
+//!
+//! let data = Boss::new()
+//!     .years(2019 ..= 2019)
+//!     .sports(vec![1])
+//!     .transport(FixtureTransport::new("fixtures"))
+//!     .limits(RequestLimits::unlimited())
+//!     .fetch()?;
+//! # Ok::<(), boss::error::BossError>(())
+//! ```
+
+use crate::error::BossError;
+use isahc::prelude::*;
+use isahc::HttpClient;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Source of the raw text for a url. Implementations need to be `Send + Sync` since requests are made from many threads at once.
+pub trait Transport: Send + Sync {
+    fn get (&self, url: &str) -> Result<String, BossError>;
+}
+
+impl <T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+    fn get (&self, url: &str) -> Result<String, BossError> {
+        (**self).get(url)
+    }
+}
+
+/// Pulls from the network. A failed connection and an unreadable body are both treated as network errors. Server errors (5xx) and
+/// throttling (429) are returned as `BossError::HttpStatus`. Other statuses, such as a 404, are passed through as is, since the body
+/// tells us what we need to know (e.g. a venue that has no svg).
+pub struct LiveTransport {
+    http_client: HttpClient,
+}
+
+impl LiveTransport {
+    /// Uses a 60 second timeout for each request.
+    pub fn new () -> Result<LiveTransport, BossError> {
+        LiveTransport::with_timeout(Duration::from_secs(60))
+    }
+
+    pub fn with_timeout (timeout: Duration) -> Result<LiveTransport, BossError> {
+        Ok(LiveTransport {
+            http_client: HttpClient::builder().timeout(timeout).build()?,
+        })
+    }
+}
+
+impl Transport for LiveTransport {
+    fn get (&self, url: &str) -> Result<String, BossError> {
+        let mut response = self.http_client.get(url)?;
+
+        let status = response.status().as_u16();
+        if status >= 500 || status == 429 {
+            return Err(BossError::HttpStatus {url: url.to_string(), status});
+        }
+
+        Ok(response.text()?)
+    }
+}
+
+/// Answers every request from a folder of recorded responses. A url without a fixture returns a NotFound IO error, which shows
+/// up as a failure for that item, same as a network error would.
+pub struct FixtureTransport {
+    dir: PathBuf,
+}
+
+impl FixtureTransport {
+    pub fn new <P: Into<PathBuf>> (dir: P) -> FixtureTransport {
+        FixtureTransport {
+            dir: dir.into(),
+        }
+    }
+
+    /// Full path of the fixture that answers for `url`.
+    pub fn fixture_path (&self, url: &str) -> PathBuf {
+        self.dir.join(fixture_name(url))
+    }
+}
+
+impl Transport for FixtureTransport {
+    fn get (&self, url: &str) -> Result<String, BossError> {
+        let path = self.fixture_path(url);
+        match std::fs::read_to_string(&path) {
+            Ok (text) => Ok(text),
+            Err (err) if err.kind() == std::io::ErrorKind::NotFound => Err(BossError::IO(
+                std::io::Error::new(err.kind(), format!("No fixture for {} (expected {})", url, path.display()))
+            )),
+            Err (err) => Err(err.into()),
+        }
+    }
+}
+
+/// Wraps another transport and saves every successful response as a fixture, so that the same pull can be replayed later with
+/// a `FixtureTransport` pointed at the same folder.
+pub struct RecordingTransport <T: Transport> {
+    inner: T,
+    fixtures: FixtureTransport,
+}
+
+impl <T: Transport> RecordingTransport <T> {
+    pub fn new <P: Into<PathBuf>> (inner: T, dir: P) -> RecordingTransport<T> {
+        RecordingTransport {
+            inner,
+            fixtures: FixtureTransport::new(dir),
+        }
+    }
+}
+
+impl <T: Transport> Transport for RecordingTransport <T> {
+    fn get (&self, url: &str) -> Result<String, BossError> {
+        let text = self.inner.get(url)?;
+        std::fs::create_dir_all(&self.fixtures.dir)?;
+        std::fs::write(self.fixtures.fixture_path(url), &text)?;
+        Ok(text)
+    }
+}
+
+/// File name used for a url's fixture. The scheme is dropped (so http and https share a fixture) and anything that isn't a letter,
+/// number, dot or dash becomes an underscore, which gives names that are safe on every OS and still readable, e.g.
+/// `statsapi.mlb.com_api_v1_game_566083_playByPlay`.
+pub fn fixture_name (url: &str) -> String {
+    let url = url.splitn(2, "://").last().unwrap_or(url);
+    url.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' {c} else {'_'})
+        .collect()
+}
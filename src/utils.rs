@@ -4,13 +4,13 @@
 //! 
 
 
-use isahc::prelude::*;
-use crate::*;
-use std::env;
+use crate::transport::Transport;
+use rayon::prelude::*;
 // use std::time;
 use serde::{Serialize, Deserialize};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-// use crate::error::*;
+use crate::error::BossError;
 
 /// Date Struct used by the crate. We'll build our own date handling rather than bring in an extra depedency. The date maths we need to do are
 /// fairly simple (calculating Batter/Pitcher age) and should help keep our dependency tree as small as possible.
@@ -25,6 +25,40 @@ pub struct Date {
     pub day: u8,
 }
 
+impl Date {
+    /// Days since 1 January 1970, negative before it. Handy when we need a whole number of days between two dates.
+    pub fn day_number (&self) -> i32 {
+        // Howard Hinnant's days_from_civil, with March as the first month of the year so leap days fall at the end.
+        let year = self.year as i32 - if self.month <= 2 {1} else {0};
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i32;
+        let day_of_year = (153 * (month + if month > 2 {-3} else {9}) + 2) / 5 + self.day as i32 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The date `day_number` days after 1 January 1970, the reverse of `day_number`.
+    pub fn from_day_number (day_number: i32) -> Date {
+        let days = day_number + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let march_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * march_month + 2) / 5 + 1;
+        let month = if march_month < 10 {march_month + 3} else {march_month - 9};
+        let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+        Date {year: year as u16, month: month as u8, day: day as u8}
+    }
+
+    /// Today's date, in UTC.
+    pub fn today () -> Date {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Date::from_day_number((seconds / 86_400) as i32)
+    }
+}
+
 impl ToString for Date {
     fn to_string(&self) -> String {
         format!("{}-{}-{}", self.year, self.month, self.day)
@@ -50,47 +84,68 @@ impl std::ops::Sub for Date {
 }
 
 
-//TODO - ADD ERROR HANDLING
-pub fn get_directory() -> String {
-    let path = env::current_dir().unwrap();
-    path.to_str().unwrap().into()
-}
-
+/// Stream will send out a bunch of requests in parallel through the transport and collect them as they come in. Concurrency and retries
+/// are left to the transport, see the `scheduler` module. Each result is returned alongside the url it was requested from.
+pub fn stream (transport: &dyn Transport, urls: Vec<String>) -> Vec<(String, Result<String, BossError>)> {
+   
+    urls.into_par_iter()
+        .map(|url| {
+            let result = transport.get(&url);
+            (url, result)
+        })
+        .collect()
 
-/// Splits the network request into CHUNK_SIZE items. Only use if the regular stream function throws a network
-/// timeout error. Will perform slightly worse than the stream function since it waits for each CHUNK_SIZE to come in.
-pub fn stream_chunked (urls: Vec<String>) -> Vec<Result<String, std::io::Error>> {
+}
 
-    let mut stream_result: Vec<Result<String, std::io::Error>> = Vec::with_capacity(urls.len());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for chunk in urls.chunks(CHUNK_SIZE) {
-        let result = stream(chunk.to_owned());
-        stream_result.extend(result);
+    fn date (year: u16, month: u8, day: u8) -> Date {
+        Date {year, month, day}
     }
 
-    stream_result
-}
-
-
-/// Stream will send out a bunch of requests and collect them as they come in. This is an extremely efficient
-/// method for collecting an arbitrary number of files from the network. If there is a risk that too many requests
-/// will cause a time_out, use stream_chunked instead
-pub fn stream (urls: Vec<String>) -> Vec<Result<String, std::io::Error>> {
-   
-    let resp_stream = futures::stream::FuturesUnordered::new();
-    
-    for url in urls {
-        resp_stream.push (
-            async {
-                isahc::get_async(url).await?.text_async().await
-            }
-        )
-    };
+    #[test]
+    fn day_number_known_dates () {
+        assert_eq!(date(1970, 1, 1).day_number(), 0);
+        assert_eq!(date(1969, 12, 31).day_number(), -1);
+        assert_eq!(date(2000, 3, 1).day_number(), 11_017);
+        assert_eq!(Date::from_day_number(0), date(1970, 1, 1));
+        assert_eq!(Date::from_day_number(-1), date(1969, 12, 31));
+    }
 
-    futures::executor::block_on_stream(resp_stream).collect()
+    #[test]
+    fn day_number_round_trips () {
+        // Every day from 1871 through 2100 takes in ordinary, leap and century years, and every month end.
+        let mut previous = date(1870, 12, 31);
+        for day_number in date(1871, 1, 1).day_number() ..= date(2100, 12, 31).day_number() {
+            let current = Date::from_day_number(day_number);
+            assert_eq!(current.day_number(), day_number, "{:?}", current);
+            assert!(current > previous, "{:?} after {:?}", current, previous);
+            previous = current;
+        };
+    }
 
+    #[test]
+    fn month_ends_and_leap_days () {
+        let next = |date: Date| Date::from_day_number(date.day_number() + 1);
+        assert_eq!(next(date(2019, 1, 31)), date(2019, 2, 1));
+        assert_eq!(next(date(2019, 2, 28)), date(2019, 3, 1));
+        assert_eq!(next(date(2020, 2, 28)), date(2020, 2, 29));
+        assert_eq!(next(date(2020, 2, 29)), date(2020, 3, 1));
+        assert_eq!(next(date(1900, 2, 28)), date(1900, 3, 1));
+        assert_eq!(next(date(2000, 2, 28)), date(2000, 2, 29));
+        assert_eq!(next(date(2019, 4, 30)), date(2019, 5, 1));
+        assert_eq!(next(date(2019, 12, 31)), date(2020, 1, 1));
+        assert_eq!(date(2021, 1, 1).day_number() - date(2020, 1, 1).day_number(), 366);
+        assert_eq!(date(2020, 1, 1).day_number() - date(2019, 1, 1).day_number(), 365);
+    }
 
+    #[test]
+    fn today_matches_the_clock () {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let today = Date::today();
+        assert!(today >= date(2020, 1, 1));
+        assert!((today.day_number() as u64 * 86_400).abs_diff(seconds) < 2 * 86_400);
+    }
 }
-
-
-
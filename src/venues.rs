@@ -8,8 +8,10 @@
 
 use serde::{Deserialize, Serialize};
 // use crate::utils::*;
-use isahc::prelude::*;
+use crate::transport::{Transport, LiveTransport};
 use crate::cache;
+use crate::config::BossConfig;
+use crate::error::{BossError, Dataset};
 use std::collections::BTreeSet;
 
 /// Link to all the venues used by the MLB Stats API. "Hydrated" fields include the location, field dimension and
@@ -17,18 +19,19 @@ use std::collections::BTreeSet;
 const VENUES: &str = "https://statsapi.mlb.com/api/v1/venues/?hydrate=location,fieldInfo,timezone,xrefId";
 
 
-pub fn test_venues () {
-    let venues = isahc::get(VENUES).unwrap().text().unwrap();
+pub fn test_venues () -> Result<(), BossError> {
+    let transport = LiveTransport::new()?;
+    let venues = transport.get(VENUES)?;
 
-    let venue_data: Venues = serde_json::from_str(&venues).unwrap();
+    let venue_data: Venues = serde_json::from_str(&venues)?;
  
 
-    dbg!(&venue_data.venues[138]);
+    dbg!(&venue_data.venues.get(138));
 
-    let mut venues_x_y = cache::load_venue_x_y();
+    let config = BossConfig::default();
+    let venues_x_y = cache::CachedSet::new(cache::load_venue_x_y(&config)?, config.overwrite(Dataset::VenueXY), |venue| venue.id);
 
-    let venues_cached: BTreeSet<u32> = venues_x_y
-        .iter()
+    let venues_cached: BTreeSet<u32> = venues_x_y.kept()
         .map(|venue| venue.id)
         .collect()
         ;
@@ -40,44 +43,46 @@ pub fn test_venues () {
         .map(|venue| 
             {
                 let id = venue.id;
-                let (x,y) = get_svg(id);
-                VenueXY {
+                let (x,y) = get_svg(&transport, id)?;
+                Ok(VenueXY {
                     id, x, y
-                }
+                })
             }
         )
-        .collect()
+        .collect::<Result<Vec<VenueXY>, BossError>>()?
         ;
 
-    venues_x_y.extend(venues_x_y_new);
+    let venues_x_y = venues_x_y.merge(venues_x_y_new, |venue| venue.id);
     
-    cache::cache_venue_x_y(&venues_x_y);
+    cache::cache_venue_x_y(&config, &venues_x_y)
 
 }
 
-pub fn get_svg (id: u32) -> (Option<f32>, Option<f32>) {
+/// Pulls the (x,y) coordinates of home plate from the venue's svg. A venue without an svg isn't an error, we just won't have its
+/// coordinates, but failing to reach the server is.
+pub fn get_svg (transport: &dyn Transport, id: u32) -> Result<(Option<f32>, Option<f32>), BossError> {
 
     let link = format!("http://mlb.mlb.com/images/gameday/fields/svg/{}.svg", id);
-    let svg_data = isahc::get(link).unwrap().text().unwrap();
+    let svg_data = transport.get(&link)?;
 
     if svg_data.contains("Page Not Found") {
-        return (None, None);
+        return Ok((None, None));
     }
 
     // The last <polyline> tag in the svg represents the baselines. The middle element is where the fair lines meet, which is the ideal
     // point to set the (x,y) coordinates
 
     let result = svg_data
-        .split("<polyline").last().unwrap()
+        .split("<polyline").last().unwrap_or("")
         .split("points=").nth(1).unwrap_or("")
         .split(" ").nth(1).unwrap_or("")
         .to_owned();
 
-    if !result.contains(",") {return (None, None)};
+    if !result.contains(",") {return Ok((None, None))};
     
     let split:Vec<&str> = result.split(",").collect();
 
-    (split[0].parse::<f32>().ok(), split[1].parse::<f32>().ok()) 
+    Ok((split[0].parse::<f32>().ok(), split[1].parse::<f32>().ok()))
 
 }
 
@@ -129,7 +134,7 @@ impl From<VenueDe> for Venue {
         let venue_retrosheet_id = v.xref_ids
                     .into_iter()
                     .filter(|id| id.xref_type == Some("retrosheet".to_string()))
-                    .map(|id| id.xref_id.unwrap())
+                    .filter_map(|id| id.xref_id)
                     .nth(0)
                     .unwrap_or("".to_string())
                     ;
@@ -297,7 +302,7 @@ fn default_xref_ids() -> Vec<XRefID> {
 }
 
 // #[serde(field_identifier)]
-#[derive(Deserialize, Serialize, Debug, Copy, Clone)]
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Default)]
 pub enum TimeZone {
     /// ### GMT -10
     /// * Pacific/Honolulu
@@ -340,6 +345,7 @@ pub enum TimeZone {
     /// * America/Kentucky/Louisville
     /// * America/Indiana/Indianapolis
     #[serde(alias="America/New_York", alias="America/Panama", alias="America/Toronto", alias="America/Detroit", alias="America/Kentucky/Louisville", alias="America/Indiana/Indianapolis")]
+    #[default]
     EST,
     /// ### GMT -4
     /// * America/Caracas
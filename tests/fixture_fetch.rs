@@ -0,0 +1,234 @@
+//! Runs a whole pull against the recorded responses in `tests/fixtures/fetch`: the 2019 MLB schedule with a single game, and every
+//! endpoint that game needs.
+
+use boss::Boss;
+use boss::config::BossConfig;
+use boss::error::{BossError, Dataset};
+use boss::game::Pitch;
+use boss::manifest::{Manifest, GameStatus};
+use boss::players::Player;
+use boss::query::{BossData, stream_event_games, stream_pitches};
+use boss::retrosheet::write_event_files;
+use boss::retrosheet_reader::read_retrosheet;
+use boss::scheduler::RequestLimits;
+use boss::transport::FixtureTransport;
+use futures::StreamExt;
+use futures::executor::block_on;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fetch");
+const SCHEDULE: &str = "statsapi.mlb.com_api_v1_schedule_sportId_1_startDate_01_01_2019_endDate_12_31_2019";
+
+/// A fresh folder under the temp dir, so a cache left over from an earlier run isn't used instead of the fixtures.
+fn temp_dir (name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("boss_fixture_fetch_{}_{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn query (fixtures: &Path, cache: &Path) -> Boss {
+    Boss::new()
+        .config(BossConfig::new(cache))
+        .years(vec![2019])
+        .sports(vec![1])
+        .transport(FixtureTransport::new(fixtures))
+        .limits(RequestLimits::unlimited())
+}
+
+fn fetch (fixtures: &Path, cache: &Path) -> BossData {
+    query(fixtures, cache).fetch().unwrap()
+}
+
+#[test]
+fn fetches_a_game_from_fixtures () {
+    let cache = temp_dir("full");
+    let data = fetch(Path::new(FIXTURES), &cache);
+
+    assert!(data.failures.is_empty(), "{:?}", data.failures);
+    assert!(data.failed_requests.is_empty(), "{:?}", data.failed_requests);
+
+    assert_eq!(data.pitches.len(), 3);
+    assert!(data.pitches.iter().all(|pitch| pitch.game_pk == 566083));
+}
+
+fn person_fixture (id: u32) -> String {
+    format!("statsapi.mlb.com_api_v1_people_{}_hydrate_xrefId_draft_transactions_awards_education", id)
+}
+
+/// A copy of the fixtures that a test can change.
+fn copy_fixtures (name: &str) -> PathBuf {
+    let fixtures = temp_dir(name);
+    for entry in fs::read_dir(FIXTURES).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, fixtures.join(path.file_name().unwrap())).unwrap();
+    };
+    fixtures
+}
+
+#[test]
+fn missing_fixture_is_a_failure () {
+    let fixtures = copy_fixtures("missing_fixtures");
+    fs::remove_file(fixtures.join("statsapi.mlb.com_api_v1_game_566083_boxscore")).unwrap();
+
+    let cache = temp_dir("missing_cache");
+    let data = fetch(&fixtures, &cache);
+
+    assert!(data.failures.iter().any(|failure| failure.dataset == Dataset::BoxScore && failure.key == "566083"));
+    assert!(data.failed_requests.iter().any(|request| request.url.ends_with("/game/566083/boxscore")));
+    assert!(data.pitches.is_empty());
+}
+
+#[test]
+fn stream_reports_metadata_failures () {
+    let fixtures = copy_fixtures("stream_fixtures");
+    fs::remove_file(fixtures.join("statsapi.mlb.com_api_v1_game_566083_boxscore")).unwrap();
+
+    let cache = temp_dir("stream_cache");
+    let results: Vec<Result<Vec<Pitch>, BossError>> = block_on(async {
+        stream_pitches(query(&fixtures, &cache)).await.collect().await
+    });
+
+    let failures: Vec<(Dataset, String)> = results.iter()
+        .map(|result| match result {
+            Err (BossError::Failed (failure)) => (failure.dataset, failure.key.clone()),
+            other => panic!("expected a failure, got {:?}", other),
+        })
+        .collect();
+    assert!(failures.contains(&(Dataset::BoxScore, "566083".to_string())));
+    assert!(failures.contains(&(Dataset::PlayByPlay, "566083".to_string())));
+
+    let manifest = Manifest::load(&BossConfig::new(&cache)).unwrap();
+    assert_eq!(manifest.get(566083).map(|entry| entry.status), Some(GameStatus::NoBoxScore));
+}
+
+#[test]
+fn gameday_umpires_get_bios () {
+    let fixtures = copy_fixtures("gameday_fixtures");
+    let person = fs::read_to_string(fixtures.join(person_fixture(300001))).unwrap();
+    fs::write(fixtures.join(person_fixture(300002)), person.replace("300001", "300002").replace("Plate Umpire", "First Base Umpire")).unwrap();
+
+    // The umpire at first is only in the Gameday files, the boxscore just has the plate umpire.
+    let mirror = temp_dir("gameday_mirror");
+    let game = mirror.join("mlb/year_2019/month_04/day_01/gid_2019_04_01_nyamlb_bosmlb_1");
+    fs::create_dir_all(&game).unwrap();
+    fs::write(game.join("players.xml"), r#"<game>
+  <umpires>
+    <umpire position="home" name="Plate Umpire" id="300001"/>
+    <umpire position="first" name="First Base Umpire" id="300002"/>
+  </umpires>
+</game>"#).unwrap();
+
+    let cache = temp_dir("gameday_cache");
+    let data = query(&fixtures, &cache)
+        .config(BossConfig::new(&cache).gameday_mirror(&mirror))
+        .fetch()
+        .unwrap();
+
+    assert!(data.failures.is_empty(), "{:?}", data.failures);
+    assert_eq!(data.meta_data.players.get(&300002).map(|player| player.name.as_str()), Some("First Base Umpire"));
+    assert_eq!(data.pitches.len(), 3);
+}
+
+#[test]
+fn failed_schedule_pull_keeps_cached_games () {
+    // A game that hasn't been played yet leaves 2019 incomplete, so the next pull asks for the season again.
+    let fixtures = copy_fixtures("incomplete_fixtures");
+    let schedule = fixtures.join(SCHEDULE);
+    let mut json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&schedule).unwrap()).unwrap();
+    let games = json["dates"][0]["games"].as_array_mut().unwrap();
+    let mut upcoming = games[0].clone();
+    upcoming["gamePk"] = 566084.into();
+    upcoming["status"]["abstractGameState"] = "Preview".into();
+    games.push(upcoming);
+    fs::write(&schedule, json.to_string()).unwrap();
+
+    let cache = temp_dir("incomplete_cache");
+    let data = fetch(&fixtures, &cache);
+    assert!(data.meta_data.schedule.contains_key(&566083));
+
+    fs::remove_file(&schedule).unwrap();
+    let data = fetch(&fixtures, &cache);
+    assert!(data.failures.iter().any(|failure| failure.dataset == Dataset::Schedule && failure.key == "2019/1"));
+    assert!(data.meta_data.schedule.contains_key(&566083));
+}
+
+#[test]
+fn event_files_round_trip () {
+    // A reliever comes in for the visitors before the bottom of the first, so the game has a substitution.
+    let fixtures = copy_fixtures("retrosheet_fixtures");
+    let person = fs::read_to_string(fixtures.join(person_fixture(100002))).unwrap();
+    fs::write(fixtures.join(person_fixture(100003)), person.replace("100002", "100003").replace("Away Pitcher", "Away Reliever")).unwrap();
+
+    // The boxscore is edited as text, since it's only read in the API's own layout (see `boxscore::fix_boxscore`).
+    let boxscore = fixtures.join("statsapi.mlb.com_api_v1_game_566083_boxscore");
+    let reliever = r#""ID100003" : {"person" : {"id" : 100003}, "position" : {"abbreviation" : "P"}}, "ID100002" : {"#;
+    fs::write(&boxscore, fs::read_to_string(&boxscore).unwrap().replace(r#""ID100002" : {"#, reliever)).unwrap();
+
+    let play_by_play = fixtures.join("statsapi.mlb.com_api_v1_game_566083_playByPlay");
+    let mut json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&play_by_play).unwrap()).unwrap();
+    let bottom = &mut json["allPlays"][1];
+    bottom["matchup"]["pitcher"]["id"] = 100003.into();
+    bottom["runners"][0]["details"]["playIndex"] = 1.into();
+    bottom["playEvents"][0]["index"] = 1.into();
+    bottom["playEvents"].as_array_mut().unwrap().insert(0, serde_json::json!({
+        "details": {"event": "Pitching Substitution", "hasReview": false},
+        "count": {"balls": 0, "strikes": 0},
+        "isPitch": false,
+        "index": 0,
+        "type": "action",
+        "player": {"id": 100003}
+    }));
+    fs::write(&play_by_play, json.to_string()).unwrap();
+
+    let cache = temp_dir("retrosheet_cache");
+    let games: Vec<_> = block_on(async {
+        stream_event_games(query(&fixtures, &cache)).await.collect::<Vec<_>>().await
+    });
+    let games: Vec<_> = games.into_iter().map(|game| game.unwrap()).collect();
+    assert_eq!(games.len(), 1);
+
+    let game = &games[0];
+    assert_eq!(game.file_name(), "2019BOS.EVA");
+    let records: Vec<&str> = game.records.iter()
+        .map(|record| record.as_str())
+        .filter(|record| record.starts_with("start,") || record.starts_with("play,") || record.starts_with("sub,"))
+        .collect();
+    assert_eq!(records, vec![
+        "start,100001,\"Away Batter\",0,1,6",
+        "start,100002,\"Away Pitcher\",0,0,1",
+        "start,200001,\"Home Batter\",1,1,8",
+        "start,200002,\"Home Pitcher\",1,0,1",
+        "play,1,0,100001,10,BX,8/G",
+        "play,1,1,200001,00,,NP",
+        "sub,100003,\"Away Reliever\",0,0,1",
+        "play,1,1,200001,00,X,S",
+    ]);
+    assert!(game.records.contains(&"info,site,BOS07".to_string()));
+
+    let dir = temp_dir("retrosheet_events");
+    let paths = write_event_files(games, &dir).unwrap();
+    assert_eq!(paths, vec![dir.join("2019BOS.EVA")]);
+    let text = fs::read_to_string(&paths[0]).unwrap();
+    assert!(text.starts_with("id,BOS201904010\nversion,2\ninfo,number,0\ninfo,visteam,NYA\n"), "{}", text);
+
+    // The reader needs the team file that Retrosheet ships with each season.
+    fs::write(dir.join("TEAM2019"), "BOS,A,Boston,Red Sox\nNYA,A,New York,Yankees\n").unwrap();
+
+    // None of the players have a Retrosheet id, so they were written with their MLBAM ids, which is what we match them back on.
+    let players: HashMap<u32, Player> = fetch(&fixtures, &cache).meta_data.players.into_iter()
+        .map(|(id, player)| (id, Player {retrosheet_id: Some (id.to_string()), ..player}))
+        .collect();
+    let data = read_retrosheet(&dir, &players).unwrap();
+    assert!(data.failures.is_empty(), "{:?}", data.failures);
+
+    let pitches: Vec<(u32, u32, u8, u8)> = data.pitches.iter()
+        .map(|pitch| (pitch.batter, pitch.pitcher, pitch.num_plate_appearance, pitch.pitch_num_plate_appearance))
+        .collect();
+    assert_eq!(pitches, vec![(100001, 200002, 1, 1), (100001, 200002, 1, 2), (200001, 100003, 2, 1)]);
+    assert_eq!(data.pitches[1].in_play, 1);
+    assert_eq!(data.pitches[2].in_play_1b, Some (1));
+    assert!(data.pitches.iter().all(|pitch| pitch.game_date == "2019-4-1" && pitch.venue_retrosheet_id == "BOS07"));
+}